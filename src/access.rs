@@ -0,0 +1,231 @@
+use std::fs; // Importation de fs pour lire les fichiers de plages d'adresses
+use std::net::IpAddr; // Importation de IpAddr pour l'adresse des clients
+use std::path::{Path, PathBuf}; // Importation des chemins des fichiers de plages
+use std::sync::{Arc, Mutex, RwLock}; // Importation des primitives de synchronisation
+use std::time::{Duration, SystemTime}; // Importation des types de mesure du temps
+use ipnet::IpNet; // Importation de IpNet pour les plages d'adresses (CIDR)
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration
+use crate::error::AppError; // Importation des erreurs de l'application
+
+/// Empreinte d'un fichier surveillé : date de modification et taille.
+type FileStamp = Option<(SystemTime, u64)>;
+
+/// Listes d'adresses autorisées et refusées, globales ou propres à une route.
+///
+/// Une adresse comprise dans une plage refusée reçoit un 403 ; si des plages autorisées sont
+/// déclarées, seules les adresses qui y figurent sont acceptées. Les fichiers contiennent une
+/// plage CIDR ou une adresse par ligne (`#` introduit un commentaire) et sont relus lorsqu'ils changent.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AccessListConfig {
+    #[serde(default)]
+    pub allow: Vec<IpNet>, // Plages autorisées
+    #[serde(default)]
+    pub deny: Vec<IpNet>, // Plages refusées, prioritaires sur les plages autorisées
+    #[serde(default)]
+    pub allow_files: Vec<PathBuf>, // Fichiers de plages autorisées
+    #[serde(default)]
+    pub deny_files: Vec<PathBuf>, // Fichiers de plages refusées
+}
+
+/// Plages en service d'une liste d'accès.
+#[derive(Debug, Default)]
+struct AccessRules {
+    allow: Vec<IpNet>, // Plages autorisées, déclarées et lues dans les fichiers
+    deny: Vec<IpNet>, // Plages refusées, déclarées et lues dans les fichiers
+}
+
+impl AccessRules {
+    /// Réunit les plages déclarées et celles des fichiers.
+    fn load(config: &AccessListConfig) -> Result<Self, AppError> {
+        let mut allow = config.allow.clone();
+        let mut deny = config.deny.clone();
+        for path in &config.allow_files {
+            allow.extend(read_ranges(path)?);
+        }
+        for path in &config.deny_files {
+            deny.extend(read_ranges(path)?);
+        }
+        Ok(Self { allow: IpNet::aggregate(&allow), deny: IpNet::aggregate(&deny) })
+    }
+
+    /// Indique si l'adresse est acceptée.
+    fn allows(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical(); // Une adresse IPv4 mappée en IPv6 est comparée aux plages IPv4
+        if self.deny.iter().any(|range| range.contains(&ip)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|range| range.contains(&ip))
+    }
+}
+
+/// Liste d'accès par adresse cliente, rechargeable à chaud.
+///
+/// Si un fichier modifié ne peut pas être lu, l'erreur est journalisée et les plages
+/// précédentes restent en service.
+#[derive(Debug)]
+pub struct AccessList {
+    config: AccessListConfig, // Plages déclarées et fichiers surveillés
+    rules: RwLock<Arc<AccessRules>>, // Plages actuellement en service
+    stamps: Mutex<Vec<FileStamp>>, // Empreintes des fichiers lors du dernier chargement
+}
+
+impl AccessList {
+    /// Charge la liste ; échoue si un fichier est illisible ou contient une plage invalide.
+    pub fn new(config: AccessListConfig) -> Result<Self, AppError> {
+        let stamps = file_stamps(&config);
+        let rules = AccessRules::load(&config)?;
+        Ok(Self { config, rules: RwLock::new(Arc::new(rules)), stamps: Mutex::new(stamps) })
+    }
+
+    /// Indique si une requête venant de l'adresse donnée est acceptée.
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        self.rules.read().unwrap().allows(ip)
+    }
+
+    /// Relit les fichiers ; en cas d'échec, les plages en service sont conservées.
+    pub fn reload(&self) -> Result<(), AppError> {
+        *self.stamps.lock().unwrap() = file_stamps(&self.config); // Un fichier invalide n'est relu qu'après une nouvelle modification
+        match AccessRules::load(&self.config) {
+            Ok(rules) => {
+                *self.rules.write().unwrap() = Arc::new(rules);
+                log::info!("Access list reloaded");
+                Ok(())
+            }
+            Err(e) => {
+                log::error!("Failed to reload access list, keeping the previous ranges: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Relit les fichiers si l'un d'eux a changé depuis le dernier chargement.
+    ///
+    /// Retourne `true` si de nouvelles plages ont été mises en service.
+    pub fn reload_if_changed(&self) -> bool {
+        let changed = *self.stamps.lock().unwrap() != file_stamps(&self.config);
+        changed && self.reload().is_ok()
+    }
+
+    /// Surveille les fichiers de la liste toutes les `interval`.
+    pub fn watch(self: Arc<Self>, interval: Duration) {
+        if self.config.allow_files.is_empty() && self.config.deny_files.is_empty() {
+            return;
+        }
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                self.reload_if_changed();
+            }
+        });
+    }
+}
+
+/// Lit un fichier de plages : une plage CIDR ou une adresse par ligne.
+fn read_ranges(path: &Path) -> Result<Vec<IpNet>, AppError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| AppError::ConfigError(format!("Cannot read access list {}: {}", path.display(), e)))?;
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            line.parse::<IpNet>()
+                .or_else(|_| line.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| AppError::ConfigError(format!("Invalid address range {:?} in {}", line, path.display())))
+        })
+        .collect()
+}
+
+/// Empreintes des fichiers d'une liste d'accès.
+fn file_stamps(config: &AccessListConfig) -> Vec<FileStamp> {
+    config
+        .allow_files
+        .iter()
+        .chain(&config.deny_files)
+        .map(|path| fs::metadata(path).ok().and_then(|m| Some((m.modified().ok()?, m.len()))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use hyper::{Body, Request, StatusCode};
+    use crate::backend::BackendServer;
+    use crate::config::{BackendConfig, PoolConfig, RouteConfig, SplitConfig};
+    use crate::load_balancer::RoundRobinLoadBalancer;
+    use crate::request_handler::RequestHandler;
+    use crate::router::Router;
+
+    /// Écrit un fichier de plages temporaire.
+    fn ranges_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("access-{}-{}.txt", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    /// Teste les plages IPv4 et IPv6, la priorité des refus et la relecture des fichiers.
+    #[test]
+    fn test_allow_deny_and_reload() {
+        let path = ranges_file("deny", "# Réseaux bloqués\n203.0.113.0/24\n2001:db8::/32 # documentation\n198.51.100.7\n");
+        let config = AccessListConfig {
+            allow: vec!["203.0.113.0/24".parse().unwrap(), "10.0.0.0/8".parse().unwrap(), "2001:db8::/16".parse().unwrap()],
+            deny_files: vec![path.clone()],
+            ..Default::default()
+        };
+        let list = AccessList::new(config).unwrap();
+        assert!(list.allows("10.1.2.3".parse().unwrap()));
+        assert!(list.allows("::ffff:10.1.2.3".parse().unwrap())); // IPv4 mappée en IPv6
+        assert!(list.allows("2001:db9::1".parse().unwrap()));
+        assert!(!list.allows("203.0.113.9".parse().unwrap())); // Refus prioritaire sur l'autorisation
+        assert!(!list.allows("2001:db8::1".parse().unwrap()));
+        assert!(!list.allows("192.168.1.1".parse().unwrap())); // Hors des plages autorisées
+
+        // Une modification du fichier est prise en compte, une plage invalide est ignorée
+        assert!(!list.reload_if_changed());
+        fs::write(&path, "2001:db8::/32\n").unwrap();
+        list.reload().unwrap();
+        assert!(list.allows("203.0.113.9".parse().unwrap()));
+        fs::write(&path, "not a range\n").unwrap();
+        assert!(list.reload().is_err());
+        assert!(!list.allows("2001:db8::1".parse().unwrap()));
+        fs::remove_file(&path).unwrap();
+    }
+
+    /// Teste le 403 des listes globale et de route dans le gestionnaire de requêtes.
+    #[tokio::test]
+    async fn test_forbidden_response() {
+        let backend = BackendConfig { address: "127.0.0.1".to_string(), port: 1, ..Default::default() };
+        let pools = vec![PoolConfig {
+            name: "internal".to_string(),
+            backends: vec![backend],
+            ..Default::default()
+        }];
+        let routes = vec![RouteConfig {
+            name: "admin".to_string(),
+            path_prefix: "/admin".to_string(),
+            split: vec![SplitConfig { pool: "internal".to_string(), weight: 1 }],
+            access: Some(AccessListConfig { allow: vec!["10.0.0.0/8".parse().unwrap()], ..Default::default() }),
+            ..Default::default()
+        }];
+        let router = Router::new(&pools, &routes, "round_robin", None).unwrap();
+        let global = AccessList::new(AccessListConfig { deny: vec!["192.0.2.0/24".parse().unwrap()], ..Default::default() }).unwrap();
+        let handler = RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(vec![BackendServer::new("127.0.0.1".to_string(), 1)])))
+            .with_router(Arc::new(router))
+            .with_access_list(Arc::new(global));
+
+        let status = |path: &str, client: &str| {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            let client: SocketAddr = client.parse().unwrap();
+            let handler = &handler;
+            async move { handler.handle_request(req, client).await.unwrap().status() }
+        };
+        assert_eq!(status("/", "192.0.2.1:1000").await, StatusCode::FORBIDDEN);
+        assert_eq!(status("/admin", "192.0.2.1:1000").await, StatusCode::FORBIDDEN);
+        assert_eq!(status("/admin", "198.51.100.1:1000").await, StatusCode::FORBIDDEN);
+        // Les clients acceptés atteignent le backend (injoignable : 502)
+        assert_eq!(status("/", "198.51.100.1:1000").await, StatusCode::BAD_GATEWAY);
+        assert_eq!(status("/admin", "10.0.0.1:1000").await, StatusCode::BAD_GATEWAY);
+    }
+}
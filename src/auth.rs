@@ -0,0 +1,368 @@
+use std::collections::HashMap; // Importation de HashMap pour indexer les utilisateurs et les clés
+use std::fs; // Importation de fs pour lire les fichiers htpasswd et de clés
+use std::sync::Arc; // Importation de Arc pour vérifier les mots de passe hors du runtime
+use argon2::{Argon2, PasswordHash, PasswordVerifier}; // Importation de la vérification des hachages argon2
+use base64::engine::general_purpose::STANDARD; // Importation de l'encodage base64 des identifiants Basic
+use base64::Engine; // Importation du trait de décodage base64
+use hyper::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION}; // Importation des en-têtes HTTP
+use ring::digest; // Importation de SHA-256 pour indexer les clés d'API
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration
+use tokio::sync::Semaphore; // Importation du Semaphore bornant les vérifications de mots de passe
+use crate::client::{AuthMethod, Principal}; // Importation du client authentifié
+use crate::config::RouteConfig; // Importation de la configuration des routes
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::jwt::{JwtError, JwtValidator}; // Importation de la vérification des jetons JWT
+
+/// Nombre maximal de vérifications de mots de passe simultanées par route : bcrypt et argon2 sont
+/// coûteux, et des clients non authentifiés ne doivent pas pouvoir occuper tous les threads bloquants.
+const MAX_CONCURRENT_VERIFICATIONS: usize = 8;
+
+/// Authentification HTTP Basic d'une route, vérifiée dans un fichier htpasswd (hachages bcrypt ou argon2).
+#[derive(Debug, Clone, Deserialize)]
+pub struct BasicAuthConfig {
+    pub htpasswd_path: String, // Fichier htpasswd : une ligne `utilisateur:hachage` par utilisateur
+    #[serde(default = "default_realm")]
+    pub realm: String, // Domaine annoncé dans l'en-tête `WWW-Authenticate`
+}
+
+fn default_realm() -> String {
+    "Restricted".to_string()
+}
+
+/// Authentification d'une route par clé d'API, vérifiée dans un fichier de clés.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiKeyAuthConfig {
+    pub keys_path: String, // Fichier de clés : une ligne `nom:clé` par clé
+    #[serde(default = "default_api_key_header")]
+    pub header: String, // En-tête portant la clé
+}
+
+fn default_api_key_header() -> String {
+    "X-Api-Key".to_string()
+}
+
+/// Utilisateurs d'un fichier htpasswd.
+#[derive(Debug)]
+pub struct Htpasswd {
+    users: HashMap<String, String>, // Hachage du mot de passe de chaque utilisateur
+    dummy_hash: Option<String>, // Hachage vérifié pour les utilisateurs inconnus, au même coût que les autres
+}
+
+impl Htpasswd {
+    /// Lit un fichier htpasswd ; seuls les hachages bcrypt (`$2a$`, `$2b$`, `$2y$`) et argon2 sont acceptés.
+    pub fn load(path: &str) -> Result<Self, AppError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| AppError::ConfigError(format!("Cannot read htpasswd file {}: {}", path, e)))?;
+        let mut users: HashMap<String, String> = HashMap::new();
+        for line in content.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (user, hash) = line
+                .split_once(':')
+                .ok_or_else(|| AppError::ConfigError(format!("Invalid line in htpasswd file {}", path)))?;
+            let supported = match hash.get(..4) {
+                Some("$2a$" | "$2b$" | "$2y$" | "$2x$") => true,
+                _ => hash.starts_with("$argon2") && PasswordHash::new(hash).is_ok(),
+            };
+            if !supported {
+                return Err(AppError::ConfigError(format!(
+                    "Unsupported password hash for user {} in {}: use bcrypt or argon2",
+                    user, path
+                )));
+            }
+            users.insert(user.to_string(), hash.to_string());
+        }
+        let dummy_hash = users.values().next().cloned();
+        Ok(Self { users, dummy_hash })
+    }
+
+    /// Vérifie le mot de passe d'un utilisateur ; coûteux par construction, à appeler hors du runtime.
+    ///
+    /// Pour un utilisateur inconnu, un hachage existant est tout de même vérifié (et le résultat
+    /// ignoré), afin que la durée de la réponse ne révèle pas quels utilisateurs existent.
+    pub fn verify(&self, user: &str, password: &str) -> bool {
+        match (self.users.get(user), &self.dummy_hash) {
+            (Some(hash), _) => verify_hash(hash, password),
+            (None, Some(dummy_hash)) => {
+                verify_hash(dummy_hash, password);
+                false
+            }
+            (None, None) => false,
+        }
+    }
+}
+
+/// Vérifie un mot de passe contre un hachage bcrypt ou argon2.
+fn verify_hash(hash: &str, password: &str) -> bool {
+    if hash.starts_with("$argon2") {
+        PasswordHash::new(hash).is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+    } else {
+        bcrypt::verify(password, hash).unwrap_or(false)
+    }
+}
+
+/// Clés d'API d'un fichier de clés, indexées par leur empreinte SHA-256.
+#[derive(Debug)]
+pub struct ApiKeys {
+    keys: HashMap<Vec<u8>, String>, // Nom associé à l'empreinte de chaque clé
+}
+
+impl ApiKeys {
+    /// Lit un fichier de clés : une ligne `nom:clé` par clé.
+    pub fn load(path: &str) -> Result<Self, AppError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| AppError::ConfigError(format!("Cannot read API keys file {}: {}", path, e)))?;
+        let mut keys = HashMap::new();
+        for line in content.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            match line.split_once(':') {
+                Some((name, key)) if !key.trim().is_empty() => {
+                    keys.insert(fingerprint(key.trim()), name.trim().to_string());
+                }
+                _ => return Err(AppError::ConfigError(format!("Invalid line in API keys file {}", path))),
+            }
+        }
+        Ok(Self { keys })
+    }
+
+    /// Nom associé à une clé, si elle est connue.
+    pub fn lookup(&self, key: &str) -> Option<&str> {
+        self.keys.get(&fingerprint(key)).map(String::as_str)
+    }
+}
+
+/// Empreinte d'une clé d'API : la recherche ne compare pas les clés elles-mêmes.
+fn fingerprint(key: &str) -> Vec<u8> {
+    digest::digest(&digest::SHA256, key.as_bytes()).as_ref().to_vec()
+}
+
+/// Refus d'une requête par l'authentification de sa route.
+#[derive(Debug, Clone)]
+pub struct AuthRejection {
+    pub reason: String, // Raison du refus, pour la journalisation
+    pub challenges: Vec<HeaderValue>, // En-têtes `WWW-Authenticate` de la réponse 401
+}
+
+/// Authentification d'une route : jeton JWT, HTTP Basic ou clé d'API.
+///
+/// Lorsque plusieurs méthodes sont configurées, la requête est acceptée si les identifiants
+/// qu'elle présente sont valides pour l'une d'elles.
+pub struct RouteAuth {
+    jwt: Option<JwtValidator>, // Vérification des jetons JWT
+    basic: Option<(Arc<Htpasswd>, HeaderValue)>, // Utilisateurs HTTP Basic et défi `WWW-Authenticate`
+    api_keys: Option<(HeaderName, ApiKeys)>, // En-tête et clés d'API
+    verifications: Arc<Semaphore>, // Vérifications de mots de passe simultanées
+}
+
+impl RouteAuth {
+    /// Charge les méthodes d'authentification d'une route ; `None` si la route n'en configure aucune.
+    pub fn from_config(route: &RouteConfig) -> Result<Option<Self>, AppError> {
+        if route.jwt.is_none() && route.basic_auth.is_none() && route.api_key_auth.is_none() {
+            return Ok(None);
+        }
+        let jwt = route.jwt.clone().map(JwtValidator::new).transpose()?;
+        let basic = match &route.basic_auth {
+            Some(basic) => {
+                let challenge = HeaderValue::try_from(format!("Basic realm=\"{}\"", basic.realm.replace('"', "")))
+                    .map_err(|_| AppError::ConfigError(format!("Invalid realm for route {}", route.name)))?;
+                Some((Arc::new(Htpasswd::load(&basic.htpasswd_path)?), challenge))
+            }
+            None => None,
+        };
+        let api_keys = match &route.api_key_auth {
+            Some(api_keys) => {
+                let header = HeaderName::try_from(api_keys.header.as_str())
+                    .map_err(|_| AppError::ConfigError(format!("Invalid API key header for route {}", route.name)))?;
+                Some((header, ApiKeys::load(&api_keys.keys_path)?))
+            }
+            None => None,
+        };
+        let verifications = Arc::new(Semaphore::new(MAX_CONCURRENT_VERIFICATIONS));
+        Ok(Some(Self { jwt, basic, api_keys, verifications }))
+    }
+
+    /// Authentifie une requête d'après ses en-têtes.
+    ///
+    /// Retourne le client authentifié (`None` pour un jeton JWT sans `sub`), ou le refus à
+    /// rapporter par un 401. Les claims JWT transmises sont ajoutées aux en-têtes.
+    pub async fn authenticate(&self, headers: &mut HeaderMap) -> Result<Option<Principal>, AuthRejection> {
+        if let Some(jwt) = &self.jwt {
+            jwt.remove_forwarded_claims(headers);
+        }
+        let authorization = headers.get(AUTHORIZATION).and_then(|value| value.to_str().ok()).unwrap_or_default();
+
+        if let (Some((htpasswd, _)), Some(credentials)) = (&self.basic, authorization.strip_prefix("Basic ")) {
+            let (user, password) = decode_basic(credentials).ok_or_else(|| self.rejection("malformed basic credentials", None))?;
+            let htpasswd = htpasswd.clone();
+            let name = user.clone();
+            // bcrypt et argon2 sont lents par construction : la vérification ne bloque pas le runtime,
+            // et le nombre de vérifications simultanées est borné
+            let permit = self.verifications.clone().acquire_owned().await;
+            let valid = tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                htpasswd.verify(&name, &password)
+            })
+            .await
+            .unwrap_or(false);
+            if !valid {
+                return Err(self.rejection(&format!("invalid credentials for user {}", user), None));
+            }
+            return Ok(Some(Principal { name: user, method: AuthMethod::Basic }));
+        }
+
+        let bearer = authorization.starts_with("Bearer ") || authorization.starts_with("bearer ");
+        if let (Some(jwt), true) = (&self.jwt, bearer) {
+            return match jwt.authorize(headers) {
+                Ok(claims) => Ok(claims
+                    .get("sub")
+                    .and_then(|sub| sub.as_str())
+                    .map(|sub| Principal { name: sub.to_string(), method: AuthMethod::Jwt })),
+                Err(e) => Err(self.rejection(&e.to_string(), Some(&e))),
+            };
+        }
+
+        if let Some((header, api_keys)) = &self.api_keys {
+            if let Some(key) = headers.get(header) {
+                return match key.to_str().ok().and_then(|key| api_keys.lookup(key)) {
+                    Some(name) => Ok(Some(Principal { name: name.to_string(), method: AuthMethod::ApiKey })),
+                    None => Err(self.rejection("unknown API key", None)),
+                };
+            }
+        }
+        Err(self.rejection("missing credentials", None))
+    }
+
+    /// Retire les identifiants Basic et la clé d'API d'une requête authentifiée, pour qu'ils
+    /// n'atteignent pas les backends ; les jetons JWT sont conservés.
+    pub fn strip_credentials(&self, headers: &mut HeaderMap) {
+        let basic = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("Basic "));
+        if self.basic.is_some() && basic {
+            headers.remove(AUTHORIZATION);
+        }
+        if let Some((header, _)) = &self.api_keys {
+            headers.remove(header);
+        }
+    }
+
+    /// Refus accompagné des défis des méthodes configurées.
+    fn rejection(&self, reason: &str, jwt_error: Option<&JwtError>) -> AuthRejection {
+        let mut challenges = Vec::new();
+        if let Some((_, challenge)) = &self.basic {
+            challenges.push(challenge.clone());
+        }
+        if self.jwt.is_some() {
+            challenges.push(jwt_error.unwrap_or(&JwtError::Missing).challenge());
+        }
+        AuthRejection { reason: reason.to_string(), challenges }
+    }
+}
+
+/// Décode les identifiants `utilisateur:mot de passe` d'un en-tête Basic.
+fn decode_basic(credentials: &str) -> Option<(String, String)> {
+    let decoded = String::from_utf8(STANDARD.decode(credentials.trim()).ok()?).ok()?;
+    let (user, password) = decoded.split_once(':')?;
+    Some((user.to_string(), password.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use argon2::password_hash::{PasswordHasher, SaltString};
+    use hyper::{Body, Request, Response, StatusCode};
+    use crate::backend::BackendServer;
+    use crate::config::{BackendConfig, PoolConfig, SplitConfig};
+    use crate::load_balancer::RoundRobinLoadBalancer;
+    use crate::rate_limit::{RateLimitConfig, RateLimitKey};
+    use crate::request_handler::RequestHandler;
+    use crate::router::Router;
+    use crate::test_support::spawn_server;
+
+    /// Écrit un fichier temporaire.
+    fn temp_file(name: &str, content: &str) -> String {
+        let path = std::env::temp_dir().join(format!("auth-{}-{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Fichier htpasswd avec alice (bcrypt) et bob (argon2).
+    fn htpasswd_file(name: &str) -> String {
+        let bcrypt_hash = bcrypt::hash("alice-password", 4).unwrap();
+        let salt = SaltString::encode_b64(b"fixed test salt").unwrap();
+        let argon2_hash = Argon2::default().hash_password(b"bob-password", &salt).unwrap().to_string();
+        temp_file(name, &format!("# Utilisateurs\nalice:{}\nbob:{}\n", bcrypt_hash.replacen("$2b$", "$2y$", 1), argon2_hash))
+    }
+
+    /// Teste la vérification des mots de passe bcrypt et argon2 et la recherche des clés d'API.
+    #[test]
+    fn test_credentials_files() {
+        let htpasswd = Htpasswd::load(&htpasswd_file("users")).unwrap();
+        assert!(htpasswd.verify("alice", "alice-password"));
+        assert!(!htpasswd.verify("alice", "bob-password"));
+        assert!(htpasswd.verify("bob", "bob-password"));
+        assert!(!htpasswd.verify("carol", "alice-password"));
+        // Un utilisateur inconnu ne passe pas avec le mot de passe du hachage vérifié à sa place
+        assert!(!htpasswd.verify("carol", "bob-password"));
+        // Les hachages faibles (MD5 apr1, SHA-1) sont refusés au chargement
+        assert!(Htpasswd::load(&temp_file("weak", "carol:$apr1$salt$hash\n")).is_err());
+
+        let keys = ApiKeys::load(&temp_file("keys", "# Clés\nreporting: k-123\nbilling:k-456\n")).unwrap();
+        assert_eq!(keys.lookup("k-123"), Some("reporting"));
+        assert_eq!(keys.lookup("k-456"), Some("billing"));
+        assert_eq!(keys.lookup("k-789"), None);
+        assert!(ApiKeys::load(&temp_file("bad-keys", "no separator\n")).is_err());
+    }
+
+    /// Teste Basic et clé d'API sur une route, et la limite de débit par utilisateur authentifié.
+    #[tokio::test]
+    async fn test_route_authentication() {
+        // Le backend refuse toute requête qui lui transmettrait des identifiants
+        let port = spawn_server(|req: Request<Body>| async move {
+            let mut response = Response::new(Body::empty());
+            if req.headers().contains_key("authorization") || req.headers().contains_key("x-api-key") {
+                *response.status_mut() = StatusCode::BAD_REQUEST;
+            }
+            response
+        })
+        .await;
+        let pools = vec![PoolConfig {
+            name: "dashboards".to_string(),
+            backends: vec![BackendConfig { address: "127.0.0.1".to_string(), port, ..Default::default() }],
+            ..Default::default()
+        }];
+        let routes = vec![RouteConfig {
+            name: "dashboards".to_string(),
+            path_prefix: "/dashboards".to_string(),
+            split: vec![SplitConfig { pool: "dashboards".to_string(), weight: 1 }],
+            rate_limits: vec![RateLimitConfig { key: RateLimitKey::Principal, requests_per_second: 0.01, burst: 1 }],
+            basic_auth: Some(BasicAuthConfig { htpasswd_path: htpasswd_file("route-users"), realm: "Dashboards".to_string() }),
+            api_key_auth: Some(ApiKeyAuthConfig { keys_path: temp_file("route-keys", "reporting:k-123\n"), header: "X-Api-Key".to_string() }),
+            ..Default::default()
+        }];
+        let router = Router::new(&pools, &routes, "round_robin", None).unwrap();
+        let handler = RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(vec![BackendServer::new("127.0.0.1".to_string(), port)])))
+            .with_router(Arc::new(router));
+        let client: SocketAddr = "127.0.0.1:1000".parse().unwrap();
+        let send = |header: Option<(&str, String)>| {
+            let mut builder = Request::get("/dashboards/main");
+            if let Some((name, value)) = header {
+                builder = builder.header(name, value);
+            }
+            handler.handle_request(builder.body(Body::empty()).unwrap(), client)
+        };
+        let basic = |user: &str, password: &str| Some(("authorization", format!("Basic {}", STANDARD.encode(format!("{}:{}", user, password)))));
+
+        let missing = send(None).await.unwrap();
+        assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(missing.headers()["www-authenticate"], "Basic realm=\"Dashboards\"");
+        assert_eq!(send(basic("alice", "wrong")).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(send(Some(("x-api-key", "k-999".to_string()))).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+        assert_eq!(send(basic("alice", "alice-password")).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(send(basic("bob", "bob-password")).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(send(Some(("x-api-key", "k-123".to_string()))).await.unwrap().status(), StatusCode::OK);
+        // La limite porte sur l'utilisateur authentifié, quelle que soit l'adresse du client
+        assert_eq!(send(basic("alice", "alice-password")).await.unwrap().status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(send(Some(("x-api-key", "k-123".to_string()))).await.unwrap().status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+}
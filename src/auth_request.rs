@@ -0,0 +1,238 @@
+use std::net::SocketAddr; // Importation de SocketAddr pour l'adresse du client
+use std::sync::Arc; // Importation de Arc pour le service d'autorisation partagé
+use std::time::Duration; // Importation de Duration pour le délai de la sous-requête
+use hyper::header::{HeaderName, HeaderValue, HOST, WWW_AUTHENTICATE}; // Importation des en-têtes HTTP
+use hyper::{Body, Request, StatusCode}; // Importation des types de requêtes HTTP
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration
+use crate::backend::BackendServer; // Importation de la structure BackendServer pour joindre le service
+use crate::config::BackendConfig; // Importation de la configuration des serveurs
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::upstream::{self, UpstreamTlsConfig}; // Importation de la transmission des requêtes et de la configuration TLS
+
+/// Autorisation externe d'une route, à la manière de `auth_request` de nginx.
+///
+/// Avant la transmission, le proxy envoie au service une sous-requête sans corps, avec la méthode
+/// de la requête d'origine, les en-têtes de `request_headers` et `X-Original-Method` /
+/// `X-Original-URI`. Une réponse 2xx autorise la requête et les en-têtes de `response_headers`
+/// sont copiés sur la requête transmise ; un 401 ou un 403 est renvoyé au client, toute autre
+/// réponse (ou l'absence de réponse dans le délai) donne un 500.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthRequestConfig {
+    pub address: String, // Adresse du service d'autorisation
+    pub port: u16, // Port du service d'autorisation
+    #[serde(default = "default_path")]
+    pub path: String, // Chemin de la sous-requête
+    #[serde(default)]
+    pub tls: Option<UpstreamTlsConfig>, // Connexion en HTTPS vers le service
+    #[serde(default = "default_request_headers")]
+    pub request_headers: Vec<String>, // En-têtes de la requête d'origine copiés sur la sous-requête
+    #[serde(default)]
+    pub response_headers: Vec<String>, // En-têtes de la réponse du service copiés sur la requête transmise
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64, // Délai de réponse du service
+}
+
+fn default_path() -> String {
+    "/auth".to_string()
+}
+
+fn default_request_headers() -> Vec<String> {
+    vec!["authorization".to_string(), "cookie".to_string()]
+}
+
+fn default_timeout_ms() -> u64 {
+    1000
+}
+
+/// Refus d'une requête par l'autorisation externe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthRequestError {
+    Denied { status: StatusCode, challenge: Option<HeaderValue> }, // 401 ou 403 du service, renvoyé au client
+    Failed(String), // Service injoignable, trop lent ou réponse inattendue
+}
+
+impl std::fmt::Display for AuthRequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthRequestError::Denied { status, .. } => write!(f, "denied with {}", status),
+            AuthRequestError::Failed(reason) => write!(f, "authorization service failed: {}", reason),
+        }
+    }
+}
+
+/// Autorisation externe des requêtes d'une route.
+pub struct AuthRequest {
+    config: AuthRequestConfig, // Chemin et délai de la sous-requête
+    service: Arc<BackendServer>, // Service d'autorisation, joint comme un backend (pool de connexions, TLS)
+    request_headers: Vec<HeaderName>, // En-têtes copiés sur la sous-requête
+    response_headers: Vec<HeaderName>, // En-têtes copiés sur la requête transmise
+}
+
+impl AuthRequest {
+    /// Prépare l'autorisation externe ; échoue si un nom d'en-tête ou la configuration TLS est invalide.
+    pub fn new(config: AuthRequestConfig) -> Result<Self, AppError> {
+        let service = BackendServer::from_config(&BackendConfig {
+            address: config.address.clone(),
+            port: config.port,
+            tls: config.tls.clone(),
+            ..Default::default()
+        })?;
+        let header_names = |names: &[String]| {
+            names
+                .iter()
+                .map(|name| HeaderName::try_from(name.as_str()).map_err(|_| AppError::ConfigError(format!("Invalid header name {}", name))))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let request_headers = header_names(&config.request_headers)?;
+        let response_headers = header_names(&config.response_headers)?;
+        Ok(Self { config, service, request_headers, response_headers })
+    }
+
+    /// Soumet une requête au service d'autorisation et y copie les en-têtes désignés s'il l'accepte.
+    ///
+    /// Les en-têtes de `response_headers` envoyés par le client sont retirés dans tous les cas,
+    /// pour qu'un backend ne reçoive que des valeurs fournies par le service.
+    pub async fn authorize(&self, req: &mut Request<Body>, client_addr: SocketAddr) -> Result<(), AuthRequestError> {
+        for name in &self.response_headers {
+            req.headers_mut().remove(name);
+        }
+
+        let original_uri = req.uri().path_and_query().map(|p| p.as_str()).unwrap_or("/");
+        let mut subrequest = Request::builder()
+            .method(req.method().clone())
+            .uri(self.config.path.as_str())
+            .header(HOST, format!("{}:{}", self.service.address(), self.service.port()))
+            .header("x-original-method", req.method().as_str())
+            .header("x-original-uri", original_uri)
+            .body(Body::empty())
+            .map_err(|e| AuthRequestError::Failed(e.to_string()))?;
+        for name in &self.request_headers {
+            for value in req.headers().get_all(name) {
+                subrequest.headers_mut().append(name.clone(), value.clone());
+            }
+        }
+        let subrequest = upstream::prepare_request(subrequest, client_addr);
+
+        // La réponse est lue en entier pour que la connexion retourne au pool
+        let exchange = async {
+            let response = upstream::send_request(&self.service, subrequest).await.map_err(|e| e.to_string())?;
+            let (parts, body) = response.into_parts();
+            hyper::body::to_bytes(body).await.map_err(|e| e.to_string())?;
+            Ok::<_, String>(parts)
+        };
+        let response = match tokio::time::timeout(Duration::from_millis(self.config.timeout_ms), exchange).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => return Err(AuthRequestError::Failed(e)),
+            Err(_) => return Err(AuthRequestError::Failed("timed out".to_string())),
+        };
+
+        match response.status {
+            status if status.is_success() => {
+                for name in &self.response_headers {
+                    for value in response.headers.get_all(name) {
+                        req.headers_mut().append(name.clone(), value.clone());
+                    }
+                }
+                Ok(())
+            }
+            StatusCode::UNAUTHORIZED => Err(AuthRequestError::Denied {
+                status: StatusCode::UNAUTHORIZED,
+                challenge: response.headers.get(WWW_AUTHENTICATE).cloned(),
+            }),
+            StatusCode::FORBIDDEN => Err(AuthRequestError::Denied { status: StatusCode::FORBIDDEN, challenge: None }),
+            status => Err(AuthRequestError::Failed(format!("unexpected status {}", status))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::Response;
+    use crate::config::{PoolConfig, RouteConfig, SplitConfig};
+    use crate::load_balancer::RoundRobinLoadBalancer;
+    use crate::request_handler::RequestHandler;
+    use crate::router::Router;
+    use crate::test_support::spawn_server;
+
+    /// Service d'autorisation simulé : accepte le jeton `good` sauf sur les chemins `/admin`.
+    async fn auth_service(req: Request<Body>) -> Response<Body> {
+        let header = |name: &str| req.headers().get(name).and_then(|value| value.to_str().ok()).unwrap_or_default().to_string();
+        let builder = match header("authorization").as_str() {
+            _ if header("x-original-uri").starts_with("/app/admin") => Response::builder().status(StatusCode::FORBIDDEN),
+            "Bearer good" => Response::builder()
+                .header("x-auth-user", "alice")
+                .header("x-auth-request", format!("{} {}", header("x-original-method"), header("x-original-uri")))
+                .header("x-internal", "not copied"),
+            "Bearer crash" => Response::builder().status(StatusCode::INTERNAL_SERVER_ERROR),
+            "Bearer slow" => {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                Response::builder()
+            }
+            _ => Response::builder().status(StatusCode::UNAUTHORIZED).header(WWW_AUTHENTICATE, "Bearer realm=\"app\""),
+        };
+        builder.body(Body::from("ignored")).unwrap()
+    }
+
+    /// Backend simulé : renvoie les en-têtes reçus du service d'autorisation.
+    async fn backend(req: Request<Body>) -> Response<Body> {
+        let header = |name: &str| req.headers().get(name).and_then(|value| value.to_str().ok()).unwrap_or("-").to_string();
+        Response::new(Body::from(format!("{}|{}|{}", header("x-auth-user"), header("x-auth-request"), header("x-internal"))))
+    }
+
+    /// Teste l'autorisation externe d'une route : acceptation, refus 401 / 403 et défaillance du service.
+    #[tokio::test]
+    async fn test_auth_request() {
+        let auth_port = spawn_server(auth_service).await;
+        let backend_port = spawn_server(backend).await;
+        let pools = vec![PoolConfig {
+            name: "app".to_string(),
+            backends: vec![BackendConfig { address: "127.0.0.1".to_string(), port: backend_port, ..Default::default() }],
+            ..Default::default()
+        }];
+        let auth_request = AuthRequestConfig {
+            address: "127.0.0.1".to_string(),
+            port: auth_port,
+            path: default_path(),
+            tls: None,
+            request_headers: default_request_headers(),
+            response_headers: vec!["X-Auth-User".to_string(), "X-Auth-Request".to_string()],
+            timeout_ms: 200,
+        };
+        let routes = vec![RouteConfig {
+            name: "app".to_string(),
+            path_prefix: "/app".to_string(),
+            split: vec![SplitConfig { pool: "app".to_string(), weight: 1 }],
+            auth_request: Some(auth_request),
+            ..Default::default()
+        }];
+        let router = Router::new(&pools, &routes, "round_robin", None).unwrap();
+        let default_backend = crate::backend::BackendServer::new("127.0.0.1".to_string(), backend_port);
+        let handler = RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(vec![default_backend]))).with_router(Arc::new(router));
+        let client: SocketAddr = "127.0.0.1:1000".parse().unwrap();
+        let send = |method: &str, path: &str, token: &str| {
+            let req = Request::builder()
+                .method(method)
+                .uri(path)
+                .header("authorization", format!("Bearer {}", token))
+                .header("x-auth-user", "spoofed")
+                .body(Body::empty())
+                .unwrap();
+            handler.handle_request(req, client)
+        };
+
+        let response = send("POST", "/app/orders?id=7", "good").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "alice|POST /app/orders?id=7|-");
+        let unauthorized = send("GET", "/app/orders", "bad").await.unwrap();
+        assert_eq!(unauthorized.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(unauthorized.headers()[WWW_AUTHENTICATE], "Bearer realm=\"app\"");
+        assert_eq!(send("GET", "/app/admin", "good").await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(send("GET", "/app/orders", "crash").await.unwrap().status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(send("GET", "/app/orders", "slow").await.unwrap().status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        // Les routes sans autorisation externe transmettent la requête telle quelle
+        let public = send("GET", "/public", "bad").await.unwrap();
+        assert_eq!(hyper::body::to_bytes(public.into_body()).await.unwrap(), "spoofed|-|-");
+    }
+}
@@ -1,15 +1,85 @@
 use std::sync::Arc;
+use std::sync::Mutex; // Importation de Mutex pour protéger l'instant de début de montée en charge
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering}; // Importation des atomiques pour l'état de santé et les connexions actives
+use std::time::Instant; // Importation de Instant pour mesurer la durée de montée en charge
+use tokio::sync::Notify; // Importation de Notify pour réveiller les requêtes en attente de capacité
+use crate::config::BackendConfig; // Importation de la configuration d'un serveur backend
+use crate::connection_pool::ConnectionPool; // Importation du pool de connexions vers le backend
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::health::HealthCheckConfig; // Importation de la méthode de vérification de santé
+use crate::upstream::{UpstreamProtocol, UpstreamTls}; // Importation du connecteur TLS et du protocole vers les backends
 /// Représente un serveur backend dans le système de load balancing.
 /// Contient l'adresse et le port du serveur backend.
-
 pub struct BackendServer {
     address: String,  // Adresse IP ou nom d'hôte du serveur backend
     port: u16,       // Port sur lequel le serveur backend écoute
+    weight: u32,     // Poids nominal du serveur backend pour les stratégies pondérées
+    priority: u32,   // Niveau de priorité du serveur backend (0 = tier principal)
+    backup: bool,    // Indique si le serveur backend est un backend de secours
+    zone: Option<String>, // Zone dans laquelle tourne le serveur backend
+    tls: Option<UpstreamTls>, // Connecteur TLS si le backend n'accepte que HTTPS
+    protocol: UpstreamProtocol, // Protocole HTTP parlé avec le backend
+    health_check: HealthCheckConfig, // Méthode de vérification de santé (HTTP ou gRPC)
+    connection_pool: Arc<ConnectionPool>, // Connexions réutilisables vers le backend
+    healthy: AtomicBool, // Dernier état de santé connu du serveur backend
+    warmup_started: Mutex<Option<Instant>>, // Début de la période de montée en charge (slow start), le cas échéant
+    active_connections: AtomicUsize, // Requêtes en cours et connexions relayées (tunnels, TCP) vers le backend
+    max_connections: Option<usize>, // Capacité du backend en connexions simultanées ; illimitée si absente
+    released: Notify, // Signalé à chaque fin de connexion, pour les requêtes en file d'attente
 }
 
 impl BackendServer {
     pub fn new(address: String, port: u16) -> Arc<Self> {
-        Arc::new(Self { address, port })
+        Self::with_weight(address, port, 1)
+    }
+
+    /// Crée un serveur backend avec un poids nominal spécifique.
+    pub fn with_weight(address: String, port: u16, weight: u32) -> Arc<Self> {
+        Arc::new(Self::build(&BackendConfig { address, port, weight, ..Default::default() }, None))
+    }
+
+    /// Crée un serveur backend à partir de sa configuration.
+    ///
+    /// Retourne une erreur si la configuration TLS vers le backend ne peut pas être chargée, ou si
+    /// la vérification de santé gRPC est demandée sans HTTP/2 vers le backend.
+    pub fn from_config(config: &BackendConfig) -> Result<Arc<Self>, AppError> {
+        // gRPC n'existe qu'en HTTP/2 : « auto » ne le garantit qu'en TLS, où le backend peut le choisir par ALPN
+        let http2 = match config.protocol.unwrap_or_default() {
+            UpstreamProtocol::Http1 => false,
+            UpstreamProtocol::Http2 => true,
+            UpstreamProtocol::Auto => config.tls.is_some(),
+        };
+        if matches!(config.health_check, Some(HealthCheckConfig::Grpc { .. })) && !http2 {
+            return Err(AppError::ConfigError(format!(
+                "Backend {}:{} uses a gRPC health check and must use protocol = \"http2\" (or \"auto\" with TLS)",
+                config.address, config.port
+            )));
+        }
+        let tls = match &config.tls {
+            Some(tls) => Some(UpstreamTls::from_config(tls, &config.address, config.protocol.unwrap_or_default())?),
+            None => None,
+        };
+        Ok(Arc::new(Self::build(config, tls)))
+    }
+
+    fn build(config: &BackendConfig, tls: Option<UpstreamTls>) -> Self {
+        Self {
+            address: config.address.clone(),
+            port: config.port,
+            weight: config.weight,
+            priority: config.priority,
+            backup: config.backup,
+            zone: config.zone.clone(),
+            tls,
+            protocol: config.protocol.unwrap_or_default(),
+            health_check: config.health_check.clone().unwrap_or_default(),
+            connection_pool: Arc::new(ConnectionPool::new(config.connection_pool.clone().unwrap_or_default())),
+            healthy: AtomicBool::new(true), // Un backend est considéré comme sain jusqu'à preuve du contraire
+            warmup_started: Mutex::new(None), // Pas de montée en charge pour les backends présents au démarrage
+            active_connections: AtomicUsize::new(0),
+            max_connections: config.max_connections,
+            released: Notify::new(),
+        }
     }
 
     pub fn address(&self) -> &str {
@@ -19,4 +89,125 @@ impl BackendServer {
     pub fn port(&self) -> u16 {
         self.port
     }
+
+    /// Poids nominal du serveur backend.
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    /// Niveau de priorité du serveur backend (0 = tier principal).
+    pub fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    /// Indique si le serveur backend est un backend de secours.
+    pub fn is_backup(&self) -> bool {
+        self.backup
+    }
+
+    /// Zone dans laquelle tourne le serveur backend, si elle est connue.
+    pub fn zone(&self) -> Option<&str> {
+        self.zone.as_deref()
+    }
+
+    /// Connecteur TLS du serveur backend, s'il n'accepte que HTTPS.
+    pub fn tls(&self) -> Option<&UpstreamTls> {
+        self.tls.as_ref()
+    }
+
+    /// Protocole HTTP parlé avec le serveur backend.
+    pub fn protocol(&self) -> UpstreamProtocol {
+        self.protocol
+    }
+
+    /// Méthode de vérification de santé du serveur backend.
+    pub fn health_check(&self) -> &HealthCheckConfig {
+        &self.health_check
+    }
+
+    /// Pool de connexions vers le serveur backend.
+    pub fn connection_pool(&self) -> &Arc<ConnectionPool> {
+        &self.connection_pool
+    }
+
+    /// Indique si le serveur backend est actuellement considéré comme sain.
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::SeqCst)
+    }
+
+    /// Met à jour l'état de santé du serveur backend.
+    ///
+    /// Lorsqu'un backend redevient sain, sa période de montée en charge démarre afin
+    /// que les stratégies pondérées ne le saturent pas immédiatement.
+    pub fn set_healthy(&self, healthy: bool) {
+        let was_healthy = self.healthy.swap(healthy, Ordering::SeqCst);
+        if healthy && !was_healthy {
+            self.start_warmup(); // Le backend vient de récupérer : on démarre le slow start
+        }
+    }
+
+    /// Démarre la période de montée en charge du serveur backend.
+    /// À appeler lorsqu'un backend est ajouté à chaud au pool.
+    pub fn start_warmup(&self) {
+        *self.warmup_started.lock().unwrap() = Some(Instant::now());
+    }
+
+    /// Instant de début de la montée en charge en cours, s'il y en a une.
+    pub fn warmup_started(&self) -> Option<Instant> {
+        *self.warmup_started.lock().unwrap()
+    }
+
+    /// Nombre de requêtes et de connexions actuellement en cours vers le serveur backend.
+    pub fn active_connections(&self) -> usize {
+        self.active_connections.load(Ordering::SeqCst)
+    }
+
+    /// Nombre maximal de connexions simultanées vers le serveur backend, s'il est limité.
+    pub fn max_connections(&self) -> Option<usize> {
+        self.max_connections
+    }
+
+    /// Indique si le serveur backend a atteint sa capacité.
+    pub fn is_saturated(&self) -> bool {
+        self.max_connections.is_some_and(|max| self.active_connections() >= max)
+    }
+
+    /// Compte une connexion active vers le serveur backend jusqu'à la destruction du garde retourné.
+    ///
+    /// La capacité du backend n'est pas vérifiée : voir `try_track_connection`.
+    pub fn track_connection(self: &Arc<Self>) -> ConnectionGuard {
+        self.active_connections.fetch_add(1, Ordering::SeqCst);
+        ConnectionGuard { backend: self.clone() }
+    }
+
+    /// Compte une connexion active si le serveur backend n'a pas atteint sa capacité.
+    pub fn try_track_connection(self: &Arc<Self>) -> Option<ConnectionGuard> {
+        let max = match self.max_connections {
+            Some(max) => max,
+            None => return Some(self.track_connection()),
+        };
+        self.active_connections
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| (active < max).then_some(active + 1))
+            .ok()
+            .map(|_| ConnectionGuard { backend: self.clone() })
+    }
+
+    /// Attend la fin d'une connexion vers le serveur backend.
+    ///
+    /// Seules les fins de connexion survenant après l'activation du `Notified` retourné sont signalées.
+    pub fn released(&self) -> tokio::sync::futures::Notified<'_> {
+        self.released.notified()
+    }
+}
+
+/// Garde comptant une connexion active vers un serveur backend tant qu'il existe.
+pub struct ConnectionGuard {
+    backend: Arc<BackendServer>, // Serveur backend dont la connexion est comptée
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.backend.active_connections.fetch_sub(1, Ordering::SeqCst);
+        self.backend.released.notify_waiters(); // Une place se libère pour les requêtes en attente
+    }
 }
@@ -0,0 +1,143 @@
+use std::fs; // Importation de fs pour lire la date de modification des fichiers
+use std::sync::{Arc, Mutex, RwLock}; // Importation des primitives de synchronisation
+use std::time::{Duration, SystemTime}; // Importation des types de mesure du temps
+use rustls::crypto::CryptoProvider; // Importation du fournisseur cryptographique de rustls
+use rustls::server::{ClientHello, ResolvesServerCert}; // Importation de la sélection de certificat côté serveur
+use rustls::sign::CertifiedKey; // Importation de la paire certificat / clé de signature
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::tls::{CertificateConfig, SniResolver}; // Importation de la sélection de certificat par SNI
+
+/// Empreinte d'un fichier surveillé : date de modification et taille.
+type FileStamp = Option<(SystemTime, u64)>;
+
+/// Sélection de certificat rechargeable à chaud.
+///
+/// Les certificats sont relus lorsque leurs fichiers changent (ou sur SIGHUP) et remplacés
+/// atomiquement : les nouvelles poignées de main utilisent la nouvelle paire tandis que les
+/// connexions établies continuent avec l'ancienne. Si la nouvelle paire ne peut pas être
+/// chargée, l'erreur est journalisée et les certificats précédents restent en service.
+#[derive(Debug)]
+pub struct CertificateReloader {
+    certificates: Vec<CertificateConfig>, // Certificats surveillés
+    provider: Arc<CryptoProvider>, // Fournisseur utilisé pour charger les clés privées
+    current: RwLock<Arc<SniResolver>>, // Sélection de certificat actuellement en service
+    stamps: Mutex<Vec<FileStamp>>, // Empreintes des fichiers lors du dernier chargement
+}
+
+impl CertificateReloader {
+    /// Charge les certificats déclarés ; échoue si la configuration initiale est invalide.
+    pub fn new(certificates: Vec<CertificateConfig>, provider: Arc<CryptoProvider>) -> Result<Self, AppError> {
+        let stamps = file_stamps(&certificates);
+        let resolver = SniResolver::from_config(&certificates, &provider)?;
+        Ok(Self {
+            certificates,
+            provider,
+            current: RwLock::new(Arc::new(resolver)),
+            stamps: Mutex::new(stamps),
+        })
+    }
+
+    /// Recharge les certificats ; en cas d'échec, les certificats en service sont conservés.
+    pub fn reload(&self) -> Result<(), AppError> {
+        let stamps = file_stamps(&self.certificates);
+        *self.stamps.lock().unwrap() = stamps; // Une paire invalide n'est retentée qu'après une nouvelle modification
+        match SniResolver::from_config(&self.certificates, &self.provider) {
+            Ok(resolver) => {
+                *self.current.write().unwrap() = Arc::new(resolver);
+                log::info!("TLS certificates reloaded");
+                Ok(())
+            }
+            Err(e) => {
+                log::error!("Failed to reload TLS certificates, keeping the previous ones: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Recharge les certificats si l'un des fichiers a changé depuis le dernier chargement.
+    ///
+    /// Retourne `true` si de nouveaux certificats ont été mis en service.
+    pub fn reload_if_changed(&self) -> bool {
+        let changed = *self.stamps.lock().unwrap() != file_stamps(&self.certificates);
+        changed && self.reload().is_ok()
+    }
+
+    /// Surveille les fichiers de certificats toutes les `interval` et recharge sur SIGHUP.
+    pub fn watch(self: Arc<Self>, interval: Duration) {
+        let reloader = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                reloader.reload_if_changed();
+            }
+        });
+
+        #[cfg(unix)]
+        tokio::spawn(async move {
+            use tokio::signal::unix::{signal, SignalKind};
+            let mut hangup = match signal(SignalKind::hangup()) {
+                Ok(hangup) => hangup,
+                Err(e) => {
+                    log::warn!("Cannot listen for SIGHUP, certificates reload on file changes only: {}", e);
+                    return;
+                }
+            };
+            while hangup.recv().await.is_some() {
+                let _ = self.reload(); // L'échec est déjà journalisé
+            }
+        });
+    }
+}
+
+impl ResolvesServerCert for CertificateReloader {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        let resolver = self.current.read().unwrap().clone();
+        resolver.lookup(client_hello.server_name())
+    }
+}
+
+/// Empreintes des fichiers de certificats et de clés.
+fn file_stamps(certificates: &[CertificateConfig]) -> Vec<FileStamp> {
+    certificates
+        .iter()
+        .flat_map(|c| [&c.cert_path, &c.key_path])
+        .map(|path| fs::metadata(path).ok().and_then(|m| Some((m.modified().ok()?, m.len()))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::tests::{handshake, self_signed};
+    use crate::tls::{build_server_config, TlsConfig};
+
+    /// Teste le remplacement à chaud d'un certificat et la conservation de l'ancien en cas d'échec.
+    #[tokio::test]
+    async fn test_reload_keeps_previous_on_failure() {
+        let (certificate, old_der) = self_signed("reload-old", &["reload.example.com"]);
+        let config = TlsConfig {
+            certificates: vec![certificate.clone()],
+            cipher_suites: Vec::new(),
+            protocol_versions: Vec::new(),
+            alpn_protocols: Vec::new(),
+            reload_interval_secs: 30,
+        };
+        let (server_config, reloader) = build_server_config(&config).unwrap();
+        assert!(!reloader.reload_if_changed()); // Aucun fichier modifié
+
+        // Dépose une nouvelle paire aux mêmes emplacements
+        let (rotated, new_der) = self_signed("reload-new", &["reload.example.com"]);
+        fs::copy(&rotated.cert_path, &certificate.cert_path).unwrap();
+        fs::copy(&rotated.key_path, &certificate.key_path).unwrap();
+        reloader.reload().unwrap();
+        let (served, _) = handshake(server_config.clone(), &[old_der.clone(), new_der.clone()], "reload.example.com").await;
+        assert_eq!(served, new_der);
+
+        // Une paire illisible est rejetée et la précédente reste en service
+        fs::write(&certificate.key_path, "not a key").unwrap();
+        assert!(reloader.reload().is_err());
+        let (served, _) = handshake(server_config, &[old_der, new_der.clone()], "reload.example.com").await;
+        assert_eq!(served, new_der);
+    }
+}
@@ -1,6 +1,53 @@
+use std::net::IpAddr; // Importation de IpAddr pour identifier un client par son adresse
 use std::sync::Arc; // Importation de Arc pour le partage sécurisé d'objets entre threads
+use ring::digest; // Importation de SHA-256 pour masquer les clés d'API
 use crate::backend::BackendServer; // Importation de la structure BackendServer depuis le module backend
 
+/// Identité d'un client du proxy, à laquelle s'appliquent les limites de débit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClientIdentity {
+    Ip(IpAddr), // Adresse IP du client (celle du PROXY protocol le cas échéant)
+    ApiKey(String), // Clé d'API présentée par le client dans un en-tête
+    Route(String), // Tous les clients d'une route, qui partagent la même limite
+    Principal(String), // Utilisateur authentifié sur la route
+}
+
+impl std::fmt::Display for ClientIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientIdentity::Ip(ip) => write!(f, "ip:{}", ip),
+            // La clé elle-même n'apparaît ni dans les journaux ni dans les noms des compteurs partagés
+            ClientIdentity::ApiKey(key) => {
+                write!(f, "key:")?;
+                digest::digest(&digest::SHA256, key.as_bytes()).as_ref()[..16].iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+            ClientIdentity::Route(route) => write!(f, "route:{}", route),
+            ClientIdentity::Principal(name) => write!(f, "user:{}", name),
+        }
+    }
+}
+
+/// Méthode par laquelle un client s'est authentifié.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMethod {
+    Basic, // Identifiant et mot de passe HTTP Basic
+    ApiKey, // Clé d'API
+    Jwt, // Jeton JWT
+}
+
+/// Client authentifié, enregistré dans les extensions de la requête pour la journalisation et les limites de débit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub name: String, // Nom de l'utilisateur, de la clé d'API ou sujet (`sub`) du jeton
+    pub method: AuthMethod, // Méthode d'authentification
+}
+
+impl std::fmt::Display for Principal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
 /// Représente un client dans le système, avec un nom et une référence à un serveur backend.
 /// Le client interagit avec le serveur backend pour envoyer des requêtes ou recevoir des données.
 pub struct Client {
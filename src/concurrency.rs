@@ -0,0 +1,307 @@
+use std::sync::{Arc, Mutex}; // Importation de Arc et Mutex pour partager l'état du limiteur
+use std::time::{Duration, Instant}; // Importation de Duration et Instant pour mesurer la latence
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration
+
+/// Poids des mesures hors saturation dans la latence de référence lorsqu'elles la dépassent.
+const BASELINE_DRIFT: f64 = 0.01;
+/// Réduction minimale appliquée par une mesure de latence (la limite ne peut que diminuer de moitié).
+const MIN_GRADIENT: f64 = 0.5;
+
+/// Configuration de la limite de concurrence adaptative d'un pool.
+///
+/// La limite suit l'algorithme du gradient (à la manière des concurrency-limits de Netflix) :
+/// tant que la latence reste proche de la latence de référence, la limite augmente ; lorsque
+/// la latence dépasse `tolerance` fois la référence, elle diminue en proportion. Les requêtes
+/// au-delà de la limite sont refusées avec un 503 et un en-tête `Retry-After`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdaptiveConcurrencyConfig {
+    #[serde(default = "default_initial_limit")]
+    pub initial_limit: usize, // Limite au démarrage
+    #[serde(default = "default_min_limit")]
+    pub min_limit: usize, // Limite plancher
+    #[serde(default = "default_max_limit")]
+    pub max_limit: usize, // Limite plafond
+    #[serde(default = "default_tolerance")]
+    pub tolerance: f64, // Dépassement de la latence de référence toléré avant de réduire la limite
+    #[serde(default = "default_smoothing")]
+    pub smoothing: f64, // Part de chaque ajustement appliquée à la limite (entre 0 et 1)
+    #[serde(default = "default_backoff_ratio")]
+    pub backoff_ratio: f64, // Facteur appliqué à la limite lorsqu'un backend échoue
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64, // Valeur de l'en-tête `Retry-After` des requêtes refusées
+}
+
+fn default_initial_limit() -> usize {
+    20
+}
+
+fn default_min_limit() -> usize {
+    1
+}
+
+fn default_max_limit() -> usize {
+    1000
+}
+
+fn default_tolerance() -> f64 {
+    2.0
+}
+
+fn default_smoothing() -> f64 {
+    0.2
+}
+
+fn default_backoff_ratio() -> f64 {
+    0.9
+}
+
+fn default_retry_after_secs() -> u64 {
+    1
+}
+
+impl Default for AdaptiveConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            initial_limit: default_initial_limit(),
+            min_limit: default_min_limit(),
+            max_limit: default_max_limit(),
+            tolerance: default_tolerance(),
+            smoothing: default_smoothing(),
+            backoff_ratio: default_backoff_ratio(),
+            retry_after_secs: default_retry_after_secs(),
+        }
+    }
+}
+
+/// État du limiteur.
+struct LimiterState {
+    limit: f64, // Limite courante, fractionnaire pour des ajustements progressifs
+    in_flight: usize, // Requêtes en cours
+    baseline: Option<f64>, // Latence de référence (sans charge), en secondes
+}
+
+/// Limite de concurrence adaptative d'un pool de backends.
+pub struct ConcurrencyLimiter {
+    config: AdaptiveConcurrencyConfig, // Paramètres de l'algorithme
+    state: Mutex<LimiterState>, // Limite, requêtes en cours et latence de référence
+}
+
+impl ConcurrencyLimiter {
+    /// Crée un limiteur à sa limite initiale.
+    pub fn new(config: AdaptiveConcurrencyConfig) -> Self {
+        let limit = config.initial_limit.clamp(config.min_limit, config.max_limit) as f64;
+        Self { config, state: Mutex::new(LimiterState { limit, in_flight: 0, baseline: None }) }
+    }
+
+    /// Nombre de requêtes simultanées actuellement autorisées.
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit as usize
+    }
+
+    /// Nombre de requêtes en cours.
+    pub fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().in_flight
+    }
+
+    /// Délai conseillé aux clients dont la requête est refusée.
+    pub fn retry_after(&self) -> Duration {
+        Duration::from_secs(self.config.retry_after_secs)
+    }
+
+    /// Admet une requête si la limite n'est pas atteinte.
+    pub fn try_acquire(self: &Arc<Self>) -> Option<ConcurrencyPermit> {
+        let mut state = self.state.lock().unwrap();
+        if state.in_flight >= state.limit as usize {
+            return None;
+        }
+        state.in_flight += 1;
+        Some(ConcurrencyPermit { limiter: self.clone(), started: Instant::now(), in_flight: state.in_flight })
+    }
+
+    /// Ajuste la limite d'après la latence d'une requête admise alors que `in_flight` requêtes étaient en cours.
+    fn record(&self, latency: Duration, in_flight: usize, dropped: bool) {
+        let config = &self.config;
+        let (min_limit, max_limit) = (config.min_limit as f64, config.max_limit.max(config.min_limit) as f64);
+        let mut state = self.state.lock().unwrap();
+        if dropped {
+            // Un échec du backend est un signal de surcharge : réduction multiplicative
+            state.limit = (state.limit * config.backoff_ratio).clamp(min_limit, max_limit);
+            return;
+        }
+
+        // La référence suit immédiatement une baisse de latence ; une hausse n'est prise en compte,
+        // lentement, que hors saturation, pour ne pas prendre la latence de surcharge pour la normale
+        let rtt = latency.as_secs_f64().max(f64::EPSILON);
+        let saturated = in_flight as f64 > state.limit / 2.0;
+        let baseline = match state.baseline {
+            Some(baseline) if rtt > baseline && saturated => baseline,
+            Some(baseline) if rtt > baseline => baseline + (rtt - baseline) * BASELINE_DRIFT,
+            _ => rtt,
+        };
+        state.baseline = Some(baseline);
+
+        let gradient = (config.tolerance * baseline / rtt).clamp(MIN_GRADIENT, 1.0);
+        let queue_size = state.limit.sqrt(); // Marge laissant la limite explorer au-dessus de la charge actuelle
+        let mut target = state.limit * gradient + queue_size;
+        // Une limite largement inutilisée n'est pas augmentée : la latence n'en dit rien
+        if target > state.limit && !saturated {
+            target = state.limit;
+        }
+        let smoothing = config.smoothing.clamp(0.0, 1.0);
+        state.limit = (state.limit * (1.0 - smoothing) + target * smoothing).clamp(min_limit, max_limit);
+    }
+}
+
+/// Admission d'une requête par le limiteur, rendue à sa destruction.
+pub struct ConcurrencyPermit {
+    limiter: Arc<ConcurrencyLimiter>, // Limiteur d'origine
+    started: Instant, // Admission de la requête
+    in_flight: usize, // Requêtes en cours à l'admission, celle-ci comprise
+}
+
+impl ConcurrencyPermit {
+    /// Fait partir la mesure de latence de maintenant, une fois le backend obtenu :
+    /// le temps passé en file d'attente n'est pas celui du backend.
+    pub fn restart(&mut self) {
+        self.started = Instant::now();
+    }
+
+    /// Rapporte l'issue de la requête : sa latence ajuste la limite, un échec (`dropped`) la réduit.
+    pub fn record(self, dropped: bool) {
+        self.limiter.record(self.started.elapsed(), self.in_flight, dropped);
+    }
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        self.limiter.state.lock().unwrap().in_flight -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{Body, Request, Response, StatusCode};
+    use crate::backend::BackendServer;
+    use crate::config::{BackendConfig, PoolConfig, RouteConfig, SplitConfig};
+    use crate::load_balancer::RoundRobinLoadBalancer;
+    use crate::request_handler::RequestHandler;
+    use crate::router::Router;
+    use crate::test_support::spawn_server;
+
+    /// Backend simulé : 10 ms de latence, plus 1 ms par requête en cours.
+    fn simulated_latency(in_flight: usize) -> Duration {
+        Duration::from_millis(10 + in_flight as u64)
+    }
+
+    /// Envoie des vagues de `clients` requêtes simultanées et retourne le nombre de requêtes refusées.
+    fn run(limiter: &Arc<ConcurrencyLimiter>, clients: usize, waves: usize) -> usize {
+        let mut shed = 0;
+        for _ in 0..waves {
+            let permits: Vec<_> = (0..clients).filter_map(|_| limiter.try_acquire()).collect();
+            shed += clients - permits.len();
+            let latency = simulated_latency(permits.len());
+            for _ in &permits {
+                limiter.record(latency, permits.len(), false);
+            }
+        }
+        shed
+    }
+
+    /// Teste la convergence de la limite face à un backend dont la latence augmente avec la charge.
+    #[test]
+    fn test_converges_under_load() {
+        let limiter = Arc::new(ConcurrencyLimiter::new(AdaptiveConcurrencyConfig::default()));
+        assert_eq!(run(&limiter, 5, 20), 0); // Latence de référence observée sous faible charge : 15 ms
+        let shed = run(&limiter, 200, 300);
+        // Sans limite, 200 requêtes simultanées donneraient 210 ms ; la limite garde la latence près de 2x la référence
+        let limit = limiter.limit();
+        assert!((10..=40).contains(&limit), "limit {}", limit);
+        assert!(shed > 0);
+        assert_eq!(limiter.in_flight(), 0);
+
+        // Une faible charge laisse la limite en place
+        run(&limiter, 2, 100);
+        assert_eq!(limiter.limit(), limit);
+    }
+
+    /// Teste la croissance de la limite sous une latence stable et sa réduction aux échecs.
+    #[test]
+    fn test_growth_and_backoff() {
+        let config = AdaptiveConcurrencyConfig { initial_limit: 4, max_limit: 50, ..Default::default() };
+        let limiter = Arc::new(ConcurrencyLimiter::new(config));
+        for _ in 0..200 {
+            let permits: Vec<_> = (0..100).filter_map(|_| limiter.try_acquire()).collect();
+            for _ in &permits {
+                limiter.record(Duration::from_millis(10), permits.len(), false);
+            }
+        }
+        assert_eq!(limiter.limit(), 50);
+
+        let permit = limiter.try_acquire().unwrap();
+        permit.record(true);
+        assert_eq!(limiter.limit(), 45);
+    }
+
+    /// Teste le délestage par le gestionnaire de requêtes : 503 avec `Retry-After` au-delà de la limite.
+    #[tokio::test]
+    async fn test_shedding_response() {
+        let port = spawn_server(|_| async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Response::new(Body::empty())
+        })
+        .await;
+        let backend = BackendServer::new("127.0.0.1".to_string(), port);
+        let config = AdaptiveConcurrencyConfig { initial_limit: 1, max_limit: 1, retry_after_secs: 3, ..Default::default() };
+        let handler = Arc::new(
+            RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(vec![backend]))).with_adaptive_concurrency(config),
+        );
+        let client: std::net::SocketAddr = "127.0.0.1:1".parse().unwrap();
+
+        let admitted = {
+            let handler = handler.clone();
+            tokio::spawn(async move { handler.handle_request(Request::new(Body::empty()), client).await.unwrap().status() })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let shed = handler.handle_request(Request::new(Body::empty()), client).await.unwrap();
+        assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(shed.headers().get("retry-after").unwrap(), "3");
+        assert_eq!(admitted.await.unwrap(), StatusCode::OK);
+    }
+
+    /// Teste que le temps passé en file d'attente n'est pas compté dans la latence du backend.
+    #[tokio::test]
+    async fn test_queueing_not_counted_as_latency() {
+        let port = spawn_server(|_| async { Response::new(Body::empty()) }).await;
+        let pool = PoolConfig {
+            name: "api".to_string(),
+            backends: vec![BackendConfig { address: "127.0.0.1".to_string(), port, max_connections: Some(1), ..Default::default() }],
+            adaptive_concurrency: Some(AdaptiveConcurrencyConfig::default()),
+            ..Default::default()
+        };
+        let route = RouteConfig {
+            name: "api".to_string(),
+            path_prefix: "/".to_string(),
+            split: vec![SplitConfig { pool: "api".to_string(), weight: 1 }],
+            ..Default::default()
+        };
+        let router = Arc::new(Router::new(&[pool], &[route], "round_robin", None).unwrap());
+        let default_backend = BackendServer::new("127.0.0.1".to_string(), port);
+        let handler = Arc::new(RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(vec![default_backend]))).with_router(router.clone()));
+        let pool = router.pool("api").unwrap();
+        let client: std::net::SocketAddr = "127.0.0.1:1".parse().unwrap();
+
+        // La requête attend 300 ms qu'une connexion se libère
+        let held = pool.backends()[0].try_track_connection().unwrap();
+        let queued = {
+            let handler = handler.clone();
+            tokio::spawn(async move { handler.handle_request(Request::new(Body::empty()), client).await.unwrap().status() })
+        };
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        drop(held);
+        assert_eq!(queued.await.unwrap(), StatusCode::OK);
+
+        let baseline = pool.concurrency_limiter().unwrap().state.lock().unwrap().baseline.unwrap();
+        assert!(baseline < 0.2, "baseline {}", baseline);
+    }
+}
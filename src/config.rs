@@ -1,12 +1,129 @@
 use serde::Deserialize; // Importation de Deserialize pour la désérialisation des données depuis le format TOML
 use std::fs; // Importation de la bibliothèque pour les opérations sur le système de fichiers
+use crate::slow_start::SlowStart; // Importation de la configuration de montée en charge progressive
+use crate::zone::ZoneAwareConfig; // Importation de la configuration du routage par zone
+use crate::override_routing::OverrideConfig; // Importation de la configuration du forçage de routage
+use crate::tls::TlsConfig; // Importation de la configuration de la terminaison TLS
+use crate::upstream::{UpstreamProtocol, UpstreamTlsConfig}; // Importation de la configuration TLS et du protocole vers les backends
+use crate::passthrough::PassthroughConfig; // Importation de la configuration du mode TLS passthrough
+use crate::tcp::TcpProxyConfig; // Importation de la configuration des listeners TCP
+use crate::udp::UdpProxyConfig; // Importation de la configuration des listeners UDP
+use crate::health::HealthCheckConfig; // Importation de la configuration des vérifications de santé
+use crate::connection_pool::ConnectionPoolConfig; // Importation des limites de réutilisation des connexions
+use crate::queue::QueueConfig; // Importation de la configuration de la file d'attente des requêtes
+use crate::rate_limit::RateLimitConfig; // Importation de la configuration des limites de débit
+use crate::rate_limit_store::RateLimitStoreConfig; // Importation de la configuration du magasin partagé des limites
+use crate::concurrency::AdaptiveConcurrencyConfig; // Importation de la configuration de la limite de concurrence adaptative
+use crate::limits::LimitsConfig; // Importation des limites de taille des requêtes
+use crate::access::AccessListConfig; // Importation des listes d'accès par adresse cliente
+use crate::jwt::JwtConfig; // Importation de la configuration de l'authentification JWT
+use crate::auth::{ApiKeyAuthConfig, BasicAuthConfig}; // Importation de la configuration des authentifications Basic et par clé d'API
+use crate::auth_request::AuthRequestConfig; // Importation de la configuration de l'autorisation externe
 
 /// Représente la configuration d'un serveur backend.
 /// Cette structure est utilisée pour désérialiser les données du serveur backend depuis le fichier de configuration.
-#[derive(Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct BackendConfig {
     pub address: String, // Adresse IP ou nom d'hôte du serveur backend
     pub port: u16,       // Port sur lequel le serveur backend écoute
+    #[serde(default = "default_weight")]
+    pub weight: u32,     // Poids du serveur backend pour les stratégies pondérées
+    #[serde(default)]
+    pub priority: u32,   // Niveau de priorité (0 = tier principal, 1 = premier débordement, ...)
+    #[serde(default)]
+    pub backup: bool,    // Backend de secours, utilisé seulement lorsque les tiers principaux sont dégradés
+    #[serde(default)]
+    pub zone: Option<String>, // Zone (par exemple la zone de disponibilité) dans laquelle tourne le backend
+    #[serde(default)]
+    pub tls: Option<UpstreamTlsConfig>, // Connexion en HTTPS (éventuellement TLS mutuel) vers le backend
+    #[serde(default)]
+    pub protocol: Option<UpstreamProtocol>, // Protocole HTTP vers le backend ; celui du pool, sinon HTTP/1.1
+    #[serde(default)]
+    pub health_check: Option<HealthCheckConfig>, // Vérification de santé ; celle du pool, sinon GET /health
+    #[serde(default)]
+    pub connection_pool: Option<ConnectionPoolConfig>, // Réutilisation des connexions ; celle du pool, sinon les valeurs par défaut
+    #[serde(default)]
+    pub max_connections: Option<usize>, // Nombre maximal de requêtes et connexions simultanées ; illimité si absent
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            address: String::new(),
+            port: 0,
+            weight: default_weight(),
+            priority: 0,
+            backup: false,
+            zone: None,
+            tls: None,
+            protocol: None,
+            health_check: None,
+            connection_pool: None,
+            max_connections: None,
+        }
+    }
+}
+
+/// Représente un pool nommé de serveurs backend, servi par sa propre stratégie de répartition.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PoolConfig {
+    pub name: String,                    // Nom du pool (par exemple "stable" ou "canary")
+    #[serde(default)]
+    pub strategy: Option<String>,        // Stratégie propre au pool, sinon celle du load balancer global
+    pub backends: Vec<BackendConfig>,    // Serveurs backend du pool
+    #[serde(default)]
+    pub tls: Option<UpstreamTlsConfig>,  // Configuration TLS appliquée aux backends du pool qui n'en déclarent pas
+    #[serde(default)]
+    pub protocol: Option<UpstreamProtocol>, // Protocole HTTP appliqué aux backends du pool qui n'en déclarent pas
+    #[serde(default)]
+    pub health_check: Option<HealthCheckConfig>, // Vérification de santé appliquée aux backends du pool qui n'en déclarent pas
+    #[serde(default)]
+    pub connection_pool: Option<ConnectionPoolConfig>, // Réutilisation des connexions appliquée aux backends du pool qui n'en déclarent pas
+    #[serde(default)]
+    pub adaptive_concurrency: Option<AdaptiveConcurrencyConfig>, // Limite de concurrence adaptative du pool
+}
+
+/// Part du trafic d'une route attribuée à un pool.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SplitConfig {
+    pub pool: String, // Nom du pool destinataire
+    pub weight: u32,  // Poids relatif du pool (par exemple 95 pour stable, 5 pour canary)
+}
+
+/// Représente une route : un préfixe de chemin dont le trafic est réparti entre plusieurs pools.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RouteConfig {
+    pub name: String,                    // Nom de la route, utilisé pour l'ajuster à chaud
+    pub path_prefix: String,             // Préfixe des chemins servis par la route
+    pub split: Vec<SplitConfig>,         // Répartition du trafic entre les pools
+    #[serde(default)]
+    pub sticky_header: Option<String>,   // En-tête dont la valeur fixe le pool d'un utilisateur
+    #[serde(default)]
+    pub sticky_cookie: Option<String>,   // Cookie dont la valeur fixe le pool d'un utilisateur
+    #[serde(default)]
+    pub sticky_client_ip: bool,          // Fixe le pool d'un client par son adresse (celle du PROXY protocol le cas échéant)
+    #[serde(default)]
+    pub rate_limits: Vec<RateLimitConfig>, // Limites de débit des clients de la route
+    #[serde(default)]
+    pub access: Option<AccessListConfig>, // Adresses clientes autorisées et refusées sur la route
+    #[serde(default)]
+    pub jwt: Option<JwtConfig>,          // Authentification des requêtes de la route par jeton JWT
+    #[serde(default)]
+    pub basic_auth: Option<BasicAuthConfig>, // Authentification HTTP Basic (fichier htpasswd)
+    #[serde(default)]
+    pub api_key_auth: Option<ApiKeyAuthConfig>, // Authentification par clé d'API (fichier de clés)
+    #[serde(default)]
+    pub auth_request: Option<AuthRequestConfig>, // Autorisation des requêtes par un service externe
+}
+
+/// Configuration de l'interface d'administration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdminConfig {
+    pub token: String, // Jeton attendu dans l'en-tête `X-Admin-Token` des requêtes d'administration
 }
 
 /// Représente la configuration globale de l'application.
@@ -15,10 +132,60 @@ pub struct BackendConfig {
 pub struct Config {
     pub load_balancer: String,           // Type d'algorithme de load balancing (par exemple, RoundRobin)
     pub backend_servers: Vec<BackendConfig>, // Liste des serveurs backend à utiliser
+    #[serde(default)]
+    pub slow_start: Option<SlowStart>,   // Montée en charge progressive des backends ajoutés ou rétablis
+    #[serde(default = "default_health_check_interval_secs")]
+    pub health_check_interval_secs: u64, // Intervalle des vérifications de santé (HTTP ou gRPC) des backends
+    #[serde(default = "default_overprovisioning_factor")]
+    pub overprovisioning_factor: f64,    // Marge appliquée à la santé d'un tier avant de déborder sur le suivant
+    #[serde(default)]
+    pub zone_aware: Option<ZoneAwareConfig>, // Préférence pour les backends de la même zone que le proxy
+    #[serde(default)]
+    pub pools: Vec<PoolConfig>,          // Pools nommés de serveurs backend
+    #[serde(default)]
+    pub routes: Vec<RouteConfig>,        // Routes réparties entre les pools
+    #[serde(default)]
+    pub admin: Option<AdminConfig>,      // Interface d'administration (ajustement des répartitions à chaud)
+    #[serde(default)]
+    pub override_routing: Option<OverrideConfig>, // Forçage du routage par en-têtes pour les tests
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,          // Terminaison TLS sur le listener ; HTTP en clair si absente
+    #[serde(default)]
+    pub proxy_protocol: bool,            // Lit l'adresse du client dans l'en-tête PROXY protocol (v1 ou v2) des connexions
+    #[serde(default)]
+    pub passthrough: Vec<PassthroughConfig>, // Listeners TLS passthrough routant le TCP brut par SNI
+    #[serde(default)]
+    pub tcp: Vec<TcpProxyConfig>,        // Listeners TCP (couche 4), par exemple devant Postgres ou Redis
+    #[serde(default)]
+    pub udp: Vec<UdpProxyConfig>,        // Listeners UDP, par exemple devant des serveurs DNS ou syslog
+    #[serde(default)]
+    pub queue: Option<QueueConfig>,      // File d'attente des requêtes lorsque tous les backends sont saturés
+    #[serde(default)]
+    pub adaptive_concurrency: Option<AdaptiveConcurrencyConfig>, // Limite de concurrence adaptative des backends principaux
+    #[serde(default)]
+    pub rate_limit_store: Option<RateLimitStoreConfig>, // Magasin partagé rendant les limites de débit globales aux instances
+    #[serde(default)]
+    pub limits: LimitsConfig,            // Limites de taille des requêtes et délai de lecture des en-têtes
+    #[serde(default)]
+    pub access: Option<AccessListConfig>, // Adresses clientes autorisées et refusées sur toutes les requêtes
+    #[serde(default = "default_access_reload_interval_secs")]
+    pub access_reload_interval_secs: u64, // Intervalle de surveillance des fichiers des listes d'accès
 }
 
-/// Charge la configuration depuis un fichier TOML et retourne un objet `Config`.
+fn default_health_check_interval_secs() -> u64 {
+    10
+}
+
+fn default_access_reload_interval_secs() -> u64 {
+    30
+}
+
+fn default_overprovisioning_factor() -> f64 {
+    crate::priority::DEFAULT_OVERPROVISIONING_FACTOR
+}
 
+/// Charge la configuration depuis un fichier TOML et retourne un objet `Config`.
+///
 /// Cette fonction retourne une erreur si le fichier ne peut pas être lu ou si la désérialisation échoue.
 /// Elle retourne également une erreur si aucune configuration de serveur backend n'est spécifiée.
 pub fn load_config(filename: &str) -> Result<Config, Box<dyn std::error::Error>> {
@@ -32,7 +199,75 @@ pub fn load_config(filename: &str) -> Result<Config, Box<dyn std::error::Error>>
     if config.backend_servers.is_empty() {
         return Err("No backend servers specified".into()); // Retourne une erreur si aucun serveur backend n'est spécifié
     }
+
+    // Un intervalle nul ferait tourner la boucle de vérification de santé sans pause
+    if config.health_check_interval_secs == 0 {
+        return Err("health_check_interval_secs must be greater than 0".into());
+    }
+    if config.access_reload_interval_secs == 0 {
+        return Err("access_reload_interval_secs must be greater than 0".into());
+    }
+    // Un délai nul refuserait toutes les connexions avant la lecture des en-têtes
+    if config.limits.header_read_timeout_secs == 0 {
+        return Err("limits.header_read_timeout_secs must be greater than 0".into());
+    }
+    // Un débit nul ou négatif rendrait le remplissage des seaux infini
+    for route in &config.routes {
+        if route.rate_limits.iter().any(|limit| !(limit.requests_per_second.is_finite() && limit.requests_per_second > 0.0)) {
+            return Err(format!("Route {}: requests_per_second must be greater than 0", route.name).into());
+        }
+    }
+    if let Some(tcp) = config.tcp.iter().find(|tcp| tcp.health_check_interval_secs == 0) {
+        return Err(format!("TCP listener {}: health_check_interval_secs must be greater than 0", tcp.listen).into());
+    }
     
     // Retourne la configuration chargée si tout est correct
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Charge une configuration écrite dans un fichier temporaire.
+    fn load(name: &str, extra: &str) -> Result<Config, Box<dyn std::error::Error>> {
+        let path = std::env::temp_dir().join(format!("config-{}-{}.toml", name, std::process::id()));
+        let content = format!("load_balancer = \"round_robin\"\n{}\n[[backend_servers]]\naddress = \"127.0.0.1\"\nport = 8080\n", extra);
+        fs::write(&path, content).unwrap();
+        let config = load_config(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        config
+    }
+
+    /// Message d'erreur du chargement d'une configuration invalide.
+    fn error(name: &str, extra: &str) -> String {
+        load(name, extra).err().expect("configuration should be rejected").to_string()
+    }
+
+    /// Teste que le fichier de configuration fourni suit le schéma attendu.
+    #[test]
+    fn test_shipped_config() {
+        let config = load_config("config/config.toml").unwrap();
+        assert_eq!(config.load_balancer, "round_robin");
+        assert_eq!(config.backend_servers.len(), 3);
+    }
+
+    /// Teste le refus des intervalles nuls, qui feraient tourner les boucles de surveillance sans pause.
+    #[test]
+    fn test_rejects_zero_intervals() {
+        assert!(load("defaults", "").is_ok());
+        assert!(error("health", "health_check_interval_secs = 0").contains("health_check_interval_secs"));
+        let tcp = "[[tcp]]\nlisten = \"127.0.0.1:5432\"\nhealth_check_interval_secs = 0";
+        assert!(error("tcp", tcp).contains("TCP listener 127.0.0.1:5432"));
+        assert!(error("access", "access_reload_interval_secs = 0").contains("access_reload_interval_secs"));
+        assert!(error("limits", "[limits]\nheader_read_timeout_secs = 0").contains("header_read_timeout_secs"));
+    }
+
+    /// Teste le refus d'une limite de débit nulle.
+    #[test]
+    fn test_rejects_zero_rate() {
+        let route = "[[routes]]\nname = \"api\"\npath_prefix = \"/api\"\nsplit = []\n[[routes.rate_limits]]\nkey = \"client_ip\"\nburst = 1\nrequests_per_second = ";
+        assert!(error("rate", &format!("{}0.0", route)).contains("Route api: requests_per_second"));
+        assert!(load("rate-ok", &format!("{}0.5", route)).is_ok());
+    }
+}
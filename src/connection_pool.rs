@@ -0,0 +1,324 @@
+use std::collections::VecDeque; // Importation de VecDeque pour la pile des connexions inactives
+use std::future::Future; // Importation de Future pour l'ouverture paresseuse des connexions
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering}; // Importation des compteurs atomiques des statistiques
+use std::sync::{Arc, Mutex}; // Importation de Arc et Mutex pour partager le pool
+use std::task::{Context, Poll, Waker}; // Importation des types de polling pour sonder l'état des connexions
+use std::time::{Duration, Instant}; // Importation de Duration et Instant pour les limites de durée
+use hyper::client::conn::{ResponseFuture, SendRequest}; // Importation de l'émetteur de requêtes d'une connexion hyper
+use hyper::{Body, Request}; // Importation des types de requêtes HTTP
+use serde::{Deserialize, Serialize}; // Importation de la (dé)sérialisation de la configuration et des statistiques
+use crate::error::AppError; // Importation des erreurs de l'application
+
+/// Limites de réutilisation des connexions vers un backend (ou les backends d'un pool).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConnectionPoolConfig {
+    #[serde(default = "default_max_idle")]
+    pub max_idle: usize, // Nombre maximal de connexions inactives conservées
+    #[serde(default = "default_idle_timeout_secs")]
+    pub idle_timeout_secs: u64, // Fermeture des connexions inactives depuis plus longtemps
+    #[serde(default)]
+    pub max_requests_per_connection: Option<u32>, // Nombre de requêtes après lequel une connexion est fermée
+    #[serde(default)]
+    pub max_lifetime_secs: Option<u64>, // Durée de vie après laquelle une connexion n'est plus réutilisée
+}
+
+fn default_max_idle() -> usize {
+    16
+}
+
+fn default_idle_timeout_secs() -> u64 {
+    90
+}
+
+impl Default for ConnectionPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle: default_max_idle(),
+            idle_timeout_secs: default_idle_timeout_secs(),
+            max_requests_per_connection: None,
+            max_lifetime_secs: None,
+        }
+    }
+}
+
+/// Statistiques du pool de connexions d'un backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PoolStats {
+    pub idle: usize, // Connexions ouvertes en attente d'une requête
+    pub active: usize, // Connexions portant une requête (ou la connexion HTTP/2 partagée)
+    pub created: u64, // Connexions ouvertes depuis le démarrage
+    pub closed: u64, // Connexions fermées ou abandonnées depuis le démarrage
+}
+
+/// Émetteur de requêtes d'une connexion, partageable entre les requêtes multiplexées en HTTP/2.
+type SharedSender = Arc<Mutex<SendRequest<Body>>>;
+
+/// Sonde sans attendre l'état d'une connexion : prête, occupée (`Pending`) ou fermée (erreur).
+fn poll_state(sender: &SharedSender) -> Poll<Result<(), hyper::Error>> {
+    sender.lock().unwrap().poll_ready(&mut Context::from_waker(Waker::noop()))
+}
+
+/// Connexion ouverte vers un backend.
+struct PooledConnection {
+    sender: SharedSender, // Émetteur de requêtes de la connexion
+    created_at: Instant, // Ouverture de la connexion
+    idle_since: Instant, // Dernière remise dans le pool
+    requests: u32, // Requêtes transmises sur la connexion
+}
+
+/// Pool de connexions vers un backend.
+///
+/// Les connexions HTTP/1.1 sont prêtées à une requête à la fois et rendues au pool à la fin
+/// de la réponse ; une connexion HTTP/2 est partagée par toutes les requêtes, qui y sont
+/// multiplexées. Une connexion qui dépasse son nombre de requêtes ou sa durée de vie n'est
+/// plus réutilisée, et les connexions inactives trop longtemps sont fermées.
+pub struct ConnectionPool {
+    config: ConnectionPoolConfig, // Limites de réutilisation
+    idle: Mutex<VecDeque<PooledConnection>>, // Connexions HTTP/1.1 inactives, la plus récente à la fin
+    multiplexed: Mutex<Option<PooledConnection>>, // Connexion HTTP/2 partagée, le cas échéant
+    active: AtomicUsize, // Connexions prêtées ou partagées
+    created: AtomicU64, // Connexions ouvertes
+    closed: AtomicU64, // Connexions fermées
+}
+
+impl ConnectionPool {
+    /// Crée un pool vide.
+    pub fn new(config: ConnectionPoolConfig) -> Self {
+        Self {
+            config,
+            idle: Mutex::new(VecDeque::new()),
+            multiplexed: Mutex::new(None),
+            active: AtomicUsize::new(0),
+            created: AtomicU64::new(0),
+            closed: AtomicU64::new(0),
+        }
+    }
+
+    /// Statistiques actuelles du pool.
+    pub fn stats(&self) -> PoolStats {
+        self.prune();
+        PoolStats {
+            idle: self.idle.lock().unwrap().len(),
+            active: self.active.load(Ordering::SeqCst),
+            created: self.created.load(Ordering::SeqCst),
+            closed: self.closed.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Emprunte une connexion réutilisable, ou en ouvre une avec `open`.
+    ///
+    /// `open` retourne l'émetteur de la nouvelle connexion et indique si elle parle HTTP/2.
+    pub async fn checkout<F, Fut>(self: &Arc<Self>, open: F) -> Result<Lease, AppError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(SendRequest<Body>, bool), AppError>>,
+    {
+        if let Some(lease) = self.reuse() {
+            return Ok(lease);
+        }
+        self.open(open).await
+    }
+
+    /// Ouvre une nouvelle connexion avec `open` sans chercher à en réutiliser une.
+    pub async fn open<F, Fut>(self: &Arc<Self>, open: F) -> Result<Lease, AppError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(SendRequest<Body>, bool), AppError>>,
+    {
+        let (sender, http2) = open().await?;
+        let sender = Arc::new(Mutex::new(sender));
+        self.created.fetch_add(1, Ordering::SeqCst);
+        self.active.fetch_add(1, Ordering::SeqCst);
+        let now = Instant::now();
+        let connection = PooledConnection { sender, created_at: now, idle_since: now, requests: 1 };
+        if http2 {
+            // La connexion HTTP/2 remplace l'éventuelle connexion partagée précédente
+            let shared = PooledConnection { sender: connection.sender.clone(), ..connection };
+            if self.multiplexed.lock().unwrap().replace(shared).is_some() {
+                self.retire();
+            }
+            return Ok(Lease { pool: self.clone(), connection: None, sender: connection.sender, http2: true, reused: false });
+        }
+        let sender = connection.sender.clone();
+        Ok(Lease { pool: self.clone(), connection: Some(connection), sender, http2: false, reused: false })
+    }
+
+    /// Réutilise la connexion HTTP/2 partagée ou une connexion HTTP/1.1 inactive encore valable.
+    fn reuse(self: &Arc<Self>) -> Option<Lease> {
+        {
+            let mut multiplexed = self.multiplexed.lock().unwrap();
+            if let Some(connection) = multiplexed.as_mut() {
+                if self.reusable(connection) {
+                    connection.requests += 1;
+                    let sender = connection.sender.clone();
+                    return Some(Lease { pool: self.clone(), connection: None, sender, http2: true, reused: true });
+                }
+                // Les requêtes en cours gardent la connexion ouverte jusqu'à leur fin
+                multiplexed.take();
+                self.retire();
+            }
+        }
+
+        self.prune();
+        let mut idle = self.idle.lock().unwrap();
+        while let Some(mut connection) = idle.pop_back() {
+            if !matches!(poll_state(&connection.sender), Poll::Ready(Ok(()))) {
+                self.closed.fetch_add(1, Ordering::SeqCst);
+                continue;
+            }
+            connection.requests += 1;
+            self.active.fetch_add(1, Ordering::SeqCst);
+            let sender = connection.sender.clone();
+            return Some(Lease { pool: self.clone(), connection: Some(connection), sender, http2: false, reused: true });
+        }
+        None
+    }
+
+    /// Indique si une connexion peut porter une requête de plus.
+    fn reusable(&self, connection: &PooledConnection) -> bool {
+        let max_requests = self.config.max_requests_per_connection.is_some_and(|max| connection.requests >= max);
+        let max_lifetime = self
+            .config
+            .max_lifetime_secs
+            .is_some_and(|max| connection.created_at.elapsed() >= Duration::from_secs(max));
+        !matches!(poll_state(&connection.sender), Poll::Ready(Err(_))) && !max_requests && !max_lifetime
+    }
+
+    /// Ferme les connexions inactives depuis trop longtemps.
+    fn prune(&self) {
+        let idle_timeout = Duration::from_secs(self.config.idle_timeout_secs);
+        let mut idle = self.idle.lock().unwrap();
+        let before = idle.len();
+        idle.retain(|connection| {
+            connection.idle_since.elapsed() < idle_timeout && !matches!(poll_state(&connection.sender), Poll::Ready(Err(_)))
+        });
+        self.closed.fetch_add((before - idle.len()) as u64, Ordering::SeqCst);
+    }
+
+    /// Compte la fermeture d'une connexion prêtée ou partagée.
+    fn retire(&self) {
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        self.closed.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Rend au pool une connexion HTTP/1.1 dont l'échange est terminé.
+    fn release(&self, mut connection: PooledConnection) {
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        let mut idle = self.idle.lock().unwrap();
+        if self.reusable(&connection) && idle.len() < self.config.max_idle {
+            connection.idle_since = Instant::now();
+            idle.push_back(connection);
+        } else {
+            self.closed.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Connexion empruntée au pool pour une requête.
+///
+/// Une connexion HTTP/1.1 retourne au pool avec `release` ; détruite sans cela (échec,
+/// réponse interrompue, connexion mise à niveau), elle est comptée comme fermée.
+pub struct Lease {
+    pool: Arc<ConnectionPool>, // Pool d'origine
+    connection: Option<PooledConnection>, // Connexion HTTP/1.1 prêtée ; absente pour HTTP/2
+    sender: SharedSender, // Émetteur de requêtes de la connexion
+    http2: bool, // Connexion HTTP/2 partagée
+    reused: bool, // Connexion déjà ouverte avant cet emprunt
+}
+
+impl Lease {
+    /// Indique si la connexion parle HTTP/2.
+    pub fn is_http2(&self) -> bool {
+        self.http2
+    }
+
+    /// Indique si la connexion a déjà servi ; le backend a pu la fermer entre-temps.
+    pub fn is_reused(&self) -> bool {
+        self.reused
+    }
+
+    /// Envoie une requête sur la connexion.
+    pub fn send_request(&self, req: Request<Body>) -> ResponseFuture {
+        self.sender.lock().unwrap().send_request(req)
+    }
+
+    /// Attend que la connexion soit prête pour une nouvelle requête ; `false` si elle est fermée.
+    pub async fn ready(&self) -> bool {
+        std::future::poll_fn(|cx| self.sender.lock().unwrap().poll_ready(cx)).await.is_ok()
+    }
+
+    /// Rend la connexion au pool une fois la réponse entièrement lue.
+    pub fn release(mut self) {
+        if let Some(connection) = self.connection.take() {
+            self.pool.release(connection);
+        }
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        if self.connection.take().is_some() {
+            self.pool.retire();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::Response;
+    use tokio::net::TcpStream;
+    use crate::test_support::spawn_server;
+
+    /// Démarre un serveur HTTP/1.1 et retourne son adresse.
+    async fn server() -> std::net::SocketAddr {
+        let port = spawn_server(|_| async { Response::new(Body::from("ok")) }).await;
+        ([127, 0, 0, 1], port).into()
+    }
+
+    async fn open(addr: std::net::SocketAddr) -> Result<(SendRequest<Body>, bool), AppError> {
+        let stream = TcpStream::connect(addr).await?;
+        let (sender, connection) = hyper::client::conn::handshake(stream).await.unwrap();
+        tokio::spawn(connection);
+        Ok((sender, false))
+    }
+
+    async fn exchange(pool: &Arc<ConnectionPool>, addr: std::net::SocketAddr) {
+        let lease = pool.checkout(|| open(addr)).await.unwrap();
+        let response = lease.send_request(Request::new(Body::empty())).await.unwrap();
+        hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert!(lease.ready().await);
+        lease.release();
+    }
+
+    /// Teste la réutilisation des connexions et la limite de requêtes par connexion.
+    #[tokio::test]
+    async fn test_reuse_and_max_requests() {
+        let addr = server().await;
+        let config = ConnectionPoolConfig { max_requests_per_connection: Some(2), ..Default::default() };
+        let pool = Arc::new(ConnectionPool::new(config));
+
+        exchange(&pool, addr).await;
+        assert_eq!(pool.stats(), PoolStats { idle: 1, active: 0, created: 1, closed: 0 });
+        exchange(&pool, addr).await; // Deuxième et dernière requête de la connexion
+        assert_eq!(pool.stats(), PoolStats { idle: 0, active: 0, created: 1, closed: 1 });
+        exchange(&pool, addr).await;
+        assert_eq!(pool.stats(), PoolStats { idle: 1, active: 0, created: 2, closed: 1 });
+    }
+
+    /// Teste la fermeture des connexions inactives et des connexions abandonnées.
+    #[tokio::test]
+    async fn test_idle_timeout_and_abandoned_lease() {
+        let addr = server().await;
+        let config = ConnectionPoolConfig { idle_timeout_secs: 0, ..Default::default() };
+        let pool = Arc::new(ConnectionPool::new(config));
+
+        exchange(&pool, addr).await;
+        assert_eq!(pool.stats(), PoolStats { idle: 0, active: 0, created: 1, closed: 1 });
+
+        let lease = pool.checkout(|| open(addr)).await.unwrap();
+        assert_eq!(pool.stats().active, 1);
+        drop(lease);
+        assert_eq!(pool.stats(), PoolStats { idle: 0, active: 0, created: 2, closed: 2 });
+    }
+}
@@ -14,6 +14,9 @@ pub enum AppError {
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
+    #[error("TLS error: {0}")]
+    TlsError(String),
+
     #[error("I/O error: {0}")]
     IoError(#[from] io::Error),
 
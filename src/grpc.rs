@@ -0,0 +1,257 @@
+use hyper::header::{HeaderMap, HeaderValue, CONTENT_TYPE, TE}; // Importation des en-têtes HTTP
+use hyper::{Body, Request, Response, StatusCode}; // Importation des types de requêtes et réponses HTTP
+
+/// Codes de statut gRPC utilisés par le proxy.
+pub const GRPC_OK: u32 = 0;
+pub const GRPC_UNKNOWN: u32 = 2;
+pub const GRPC_PERMISSION_DENIED: u32 = 7;
+pub const GRPC_UNIMPLEMENTED: u32 = 12;
+pub const GRPC_INTERNAL: u32 = 13;
+pub const GRPC_UNAVAILABLE: u32 = 14;
+pub const GRPC_UNAUTHENTICATED: u32 = 16;
+
+/// Indique si une requête est un appel gRPC (`Content-Type: application/grpc[+proto|+json...]`).
+pub fn is_grpc(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/grpc"))
+}
+
+/// Code de statut gRPC correspondant à un statut HTTP, selon la table de correspondance de gRPC.
+pub fn status_from_http(status: StatusCode) -> u32 {
+    match status {
+        StatusCode::BAD_REQUEST => GRPC_INTERNAL,
+        StatusCode::UNAUTHORIZED => GRPC_UNAUTHENTICATED,
+        StatusCode::FORBIDDEN => GRPC_PERMISSION_DENIED,
+        StatusCode::NOT_FOUND => GRPC_UNIMPLEMENTED,
+        StatusCode::TOO_MANY_REQUESTS
+        | StatusCode::BAD_GATEWAY
+        | StatusCode::SERVICE_UNAVAILABLE
+        | StatusCode::GATEWAY_TIMEOUT => GRPC_UNAVAILABLE,
+        _ => GRPC_UNKNOWN,
+    }
+}
+
+/// Réponse gRPC « trailers only » portant l'erreur du proxy.
+///
+/// Les clients gRPC lisent le statut dans `grpc-status` et non dans le statut HTTP, qui reste 200.
+pub fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/grpc"));
+    headers.insert("grpc-status", HeaderValue::from(status_from_http(status)));
+    if let Ok(message) = HeaderValue::from_str(&percent_encode(message)) {
+        headers.insert("grpc-message", message);
+    }
+    response
+}
+
+/// Encode `grpc-message` : les octets hors de l'ASCII imprimable et `%` sont encodés en `%XX`.
+fn percent_encode(message: &str) -> String {
+    message
+        .bytes()
+        .map(|b| match b {
+            b' '..=b'~' if b != b'%' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Méthode du protocole de vérification de santé gRPC.
+pub const HEALTH_CHECK_PATH: &str = "/grpc.health.v1.Health/Check";
+/// Valeur `SERVING` de `HealthCheckResponse.ServingStatus`.
+pub const SERVING: u64 = 1;
+
+/// Construit l'appel `grpc.health.v1.Health/Check` pour le service donné (vide : le serveur entier).
+pub fn health_check_request(authority: &str, service: &str) -> Request<Body> {
+    // HealthCheckRequest { string service = 1; } : champ 1, type longueur délimitée
+    let mut message = Vec::new();
+    if !service.is_empty() {
+        message.push(0x0a);
+        encode_varint(service.len() as u64, &mut message);
+        message.extend_from_slice(service.as_bytes());
+    }
+    let mut request = Request::new(Body::from(frame(&message)));
+    *request.method_mut() = hyper::Method::POST;
+    *request.uri_mut() = HEALTH_CHECK_PATH.parse().expect("valid health check path");
+    let headers = request.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/grpc"));
+    headers.insert(TE, HeaderValue::from_static("trailers"));
+    if let Ok(host) = HeaderValue::from_str(authority) {
+        headers.insert(hyper::header::HOST, host);
+    }
+    request
+}
+
+/// Lit `HealthCheckResponse.status` dans le corps d'une réponse `Health/Check`.
+pub fn parse_health_response(body: &[u8]) -> Option<u64> {
+    // Message préfixé : indicateur de compression (non compressé), puis longueur sur 4 octets
+    if body.len() < 5 || body[0] != 0 {
+        return None;
+    }
+    let len = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+    let mut message = body.get(5..5 + len)?;
+    while !message.is_empty() {
+        let key = decode_varint(&mut message)?;
+        match (key >> 3, key & 7) {
+            (1, 0) => return decode_varint(&mut message), // status, énumération encodée en varint
+            (_, 0) => {
+                decode_varint(&mut message)?;
+            }
+            (_, 2) => {
+                let len = decode_varint(&mut message)? as usize;
+                message = message.get(len..)?;
+            }
+            _ => return None, // Types de champ absents de HealthCheckResponse
+        }
+    }
+    Some(0) // Champ absent : valeur par défaut UNKNOWN
+}
+
+/// Statut gRPC final d'une réponse, lu dans les trailers ou, pour une réponse « trailers only », dans les en-têtes.
+pub fn response_status(headers: &HeaderMap, trailers: Option<&HeaderMap>) -> Option<u32> {
+    trailers
+        .and_then(|trailers| trailers.get("grpc-status"))
+        .or_else(|| headers.get("grpc-status"))
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+/// Préfixe un message protobuf pour sa transmission gRPC.
+fn frame(message: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(5 + message.len());
+    framed.push(0);
+    framed.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    framed.extend_from_slice(message);
+    framed
+}
+
+fn encode_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn decode_varint(data: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for (i, byte) in data.iter().enumerate().take(10) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            *data = &data[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use hyper::body::HttpBody;
+    use hyper::server::conn::Http;
+    use hyper::service::service_fn;
+    use hyper::Request;
+    use tokio::net::{TcpListener, TcpStream};
+    use crate::backend::BackendServer;
+    use crate::config::BackendConfig;
+    use crate::load_balancer::RoundRobinLoadBalancer;
+    use crate::request_handler::RequestHandler;
+    use crate::test_support::spawn_h2_server;
+    use crate::upstream::UpstreamProtocol;
+
+    /// Backend gRPC en h2c répondant son nom dans le corps et `grpc-status: 0` en trailer.
+    async fn grpc_backend(name: &'static str) -> Arc<BackendServer> {
+        let port = spawn_h2_server(move |req: Request<Body>| async move {
+            assert_eq!(req.headers().get("te").unwrap(), "trailers");
+            let (mut sender, body) = Body::channel();
+            tokio::spawn(async move {
+                sender.send_data(name.into()).await.unwrap();
+                let mut trailers = HeaderMap::new();
+                trailers.insert("grpc-status", HeaderValue::from_static("0"));
+                sender.send_trailers(trailers).await.unwrap();
+            });
+            let mut response = Response::new(body);
+            response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/grpc"));
+            response
+        })
+        .await;
+        BackendServer::from_config(&BackendConfig {
+            address: "127.0.0.1".to_string(),
+            port,
+            protocol: Some(UpstreamProtocol::Http2),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn rpc() -> Request<Body> {
+        Request::post("http://example.com/helloworld.Greeter/SayHello")
+            .header(CONTENT_TYPE, "application/grpc")
+            .header("te", "trailers")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    /// Teste la transmission des trailers gRPC, la répartition par RPC et le statut gRPC des erreurs du proxy.
+    #[tokio::test]
+    async fn test_grpc_proxying() {
+        let backends = vec![grpc_backend("first").await, grpc_backend("second").await];
+        let handler = Arc::new(RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(backends.clone()))));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, client_addr): (TcpStream, SocketAddr) = listener.accept().await.unwrap();
+            let service = service_fn(move |req| {
+                let handler = handler.clone();
+                async move { handler.handle_request(req, client_addr).await }
+            });
+            let _ = Http::new().http2_only(true).serve_connection(stream, service).await;
+        });
+
+        // Deux RPC multiplexés sur une seule connexion cliente atteignent deux backends différents
+        let (mut sender, connection) = hyper::client::conn::Builder::new()
+            .http2_only(true)
+            .handshake(TcpStream::connect(addr).await.unwrap())
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+        let mut names = Vec::new();
+        for _ in 0..2 {
+            let mut body = sender.send_request(rpc()).await.unwrap().into_body();
+            let data = body.data().await.unwrap().unwrap();
+            names.push(String::from_utf8(data.to_vec()).unwrap());
+            let trailers = body.trailers().await.unwrap().unwrap();
+            assert_eq!(trailers.get("grpc-status").unwrap(), "0");
+        }
+        assert_ne!(names[0], names[1]);
+
+        // Une erreur du proxy est rapportée en statut gRPC
+        let down = BackendServer::new("127.0.0.1".to_string(), TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port());
+        let handler = RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(vec![down])));
+        let response = handler.handle_request(rpc(), addr).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("grpc-status").unwrap(), "14");
+    }
+
+    /// Teste l'encodage de l'appel de santé et le décodage de sa réponse.
+    #[tokio::test]
+    async fn test_health_messages() {
+        let body = hyper::body::to_bytes(health_check_request("greeter:50051", "greeter").into_body()).await.unwrap();
+        assert_eq!(body.to_vec(), [&[0, 0, 0, 0, 9, 0x0a, 7][..], b"greeter"].concat());
+        assert_eq!(parse_health_response(&frame(&[0x08, 0x01])), Some(SERVING));
+        assert_eq!(parse_health_response(&frame(&[])), Some(0));
+        assert_eq!(parse_health_response(&[1, 0, 0]), None);
+    }
+
+    /// Teste l'encodage de `grpc-message`.
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(percent_encode("Bad gateway: 100%"), "Bad gateway: 100%25");
+        assert_eq!(percent_encode("é"), "%C3%A9");
+    }
+}
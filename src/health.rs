@@ -1,5 +1,43 @@
 use std::sync::Arc; // Importation de Arc pour le partage sécurisé d'objets entre threads
+use std::time::Duration; // Importation de Duration pour le délai de connexion
+use futures::future::join_all; // Importation de join_all pour vérifier les backends en parallèle
+use tokio::net::TcpStream; // Importation de TcpStream pour les vérifications de niveau TCP
+use hyper::body::HttpBody; // Importation de HttpBody pour lire le corps et les trailers des réponses gRPC
+use hyper::{Body, Request}; // Importation des types de requêtes HTTP
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration des vérifications de santé
 use crate::backend::BackendServer; // Importation de la structure BackendServer depuis le module backend
+use crate::grpc; // Importation du protocole de vérification de santé gRPC
+use crate::upstream; // Importation des connexions vers les backends
+
+/// Délai maximal d'une vérification de santé HTTP ou gRPC.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Méthode de vérification de santé d'un backend (ou d'un pool).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum HealthCheckConfig {
+    /// Requête HTTP GET sur `path`, réussie pour une réponse 2xx.
+    Http {
+        #[serde(default = "default_health_path")]
+        path: String,
+    },
+    /// Appel `grpc.health.v1.Health/Check` pour `service` (vide : le serveur entier), réussi si `SERVING`.
+    /// Le backend doit parler HTTP/2 (`protocol = "http2"` ou `"auto"`).
+    Grpc {
+        #[serde(default)]
+        service: String,
+    },
+}
+
+fn default_health_path() -> String {
+    "/health".to_string()
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        HealthCheckConfig::Http { path: default_health_path() }
+    }
+}
 
 /// Représente un vérificateur de santé pour les serveurs backend.
 /// Ce module contient des méthodes pour vérifier si un serveur backend est en ligne et opérationnel.
@@ -7,16 +45,206 @@ pub struct HealthChecker;
 
 impl HealthChecker {
     /// Vérifie la santé d'un serveur backend en envoyant une requête HTTP à son endpoint de santé.
-   
+    ///
     /// La méthode retourne `false` en cas d'erreur de requête HTTP, ce qui inclut les erreurs de réseau ou les réponses
     /// d'erreur du serveur.
     pub async fn check_health(backend: Arc<BackendServer>) -> bool {
+        let path = match backend.health_check() {
+            HealthCheckConfig::Http { path } => path.clone(),
+            HealthCheckConfig::Grpc { service } => return Self::check_grpc(&backend, service).await,
+        };
+
+        // Les backends HTTPS sont vérifiés avec leur propre configuration TLS (CA, SNI, certificat client)
+        if backend.tls().is_some() {
+            let host = format!("{}:{}", backend.address(), backend.port());
+            let request = match Request::get(path.as_str()).header("host", host).body(Body::empty()) {
+                Ok(request) => request,
+                Err(_) => return false,
+            };
+            return match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, upstream::send_request(&backend, request)).await {
+                Ok(Ok(response)) => response.status().is_success(),
+                _ => false,
+            };
+        }
+
         // Crée l'URL de l'endpoint de santé du serveur backend en utilisant son adresse et son port
-        let url = format!("http://{}:{}/health", backend.address(), backend.port());
+        let url = format!("http://{}:{}{}", backend.address(), backend.port(), path);
 
         // Envoie une requête GET asynchrone à l'URL de l'endpoint de santé
         // Vérifie si la réponse est une réponse HTTP valide (status code 200-299)
-        reqwest::get(&url).await.is_ok()
+        match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, reqwest::get(&url)).await {
+            Ok(Ok(response)) => response.status().is_success(),
+            _ => false,
+        }
+    }
+
+    /// Vérifie la santé d'un backend gRPC avec le protocole `grpc.health.v1.Health/Check`.
+    ///
+    /// Le backend est sain si l'appel réussit (`grpc-status: 0`) et que le service est `SERVING`.
+    pub async fn check_grpc(backend: &BackendServer, service: &str) -> bool {
+        let authority = format!("{}:{}", backend.address(), backend.port());
+        let request = grpc::health_check_request(&authority, service);
+        let response = match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, upstream::send_request(backend, request)).await {
+            Ok(Ok(response)) if response.status().is_success() => response,
+            _ => return false,
+        };
+
+        // Lit le message de réponse puis le statut gRPC final dans les trailers
+        let (parts, mut body) = response.into_parts();
+        let mut message = Vec::new();
+        while let Some(chunk) = body.data().await {
+            match chunk {
+                Ok(chunk) => message.extend_from_slice(&chunk),
+                Err(_) => return false,
+            }
+        }
+        let trailers = body.trailers().await.ok().flatten();
+        grpc::response_status(&parts.headers, trailers.as_ref()) == Some(grpc::GRPC_OK)
+            && grpc::parse_health_response(&message) == Some(grpc::SERVING)
+    }
+
+    /// Vérifie qu'un serveur backend accepte les connexions TCP, pour les services qui ne parlent pas HTTP.
+    pub async fn check_tcp(backend: Arc<BackendServer>, timeout: Duration) -> bool {
+        let connect = TcpStream::connect((backend.address(), backend.port()));
+        matches!(tokio::time::timeout(timeout, connect).await, Ok(Ok(_)))
+    }
+
+    /// Vérifie la santé d'un serveur backend et met à jour son état.
+    ///
+    /// Un backend qui redevient sain démarre sa période de montée en charge (slow start).
+    pub async fn update_health(backend: Arc<BackendServer>) -> bool {
+        let healthy = Self::check_health(backend.clone()).await;
+        if healthy != backend.is_healthy() {
+            log::warn!(
+                "Backend {}:{} is now {}",
+                backend.address(),
+                backend.port(),
+                if healthy { "healthy" } else { "unhealthy" }
+            );
+        }
+        backend.set_healthy(healthy); // Enregistre le nouvel état de santé du backend
+        healthy
+    }
+
+    /// Lance en tâche de fond la vérification des backends toutes les `interval`, chacun avec
+    /// sa propre méthode (HTTP ou gRPC).
+    ///
+    /// Les backends sont vérifiés en parallèle : un backend qui ne répond pas ne retarde pas les autres.
+    pub fn watch(backends: Vec<Arc<BackendServer>>, interval: Duration) {
+        if backends.is_empty() {
+            return;
+        }
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                join_all(backends.iter().cloned().map(Self::update_health)).await;
+            }
+        });
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use hyper::header::{HeaderMap, HeaderValue};
+    use hyper::{Response, StatusCode};
+    use tokio::net::TcpListener;
+    use crate::config::BackendConfig;
+    use crate::slow_start::SlowStart;
+    use crate::test_support::{spawn_h2_server, spawn_server};
+    use crate::upstream::UpstreamProtocol;
+
+    /// Backend gRPC dont seul le service `greeter` est `SERVING`.
+    async fn grpc_backend() -> u16 {
+        spawn_h2_server(|req: Request<Body>| async move {
+            assert_eq!(req.uri().path(), grpc::HEALTH_CHECK_PATH);
+            let request = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            let status = if request.ends_with(b"greeter") { 1 } else { 2 }; // SERVING ou NOT_SERVING
+            let (mut sender, body) = Body::channel();
+            tokio::spawn(async move {
+                sender.send_data(vec![0, 0, 0, 0, 2, 0x08, status].into()).await.unwrap();
+                let mut trailers = HeaderMap::new();
+                trailers.insert("grpc-status", HeaderValue::from_static("0"));
+                sender.send_trailers(trailers).await.unwrap();
+            });
+            Response::new(body)
+        })
+        .await
+    }
+
+    fn backend(port: u16, service: &str) -> Arc<BackendServer> {
+        BackendServer::from_config(&BackendConfig {
+            address: "127.0.0.1".to_string(),
+            port,
+            protocol: Some(UpstreamProtocol::Http2),
+            health_check: Some(HealthCheckConfig::Grpc { service: service.to_string() }),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    /// Teste la vérification de santé gRPC d'un service servi, non servi, d'un backend arrêté, puis sans HTTP/2.
+    #[tokio::test]
+    async fn test_grpc_health_check() {
+        let port = grpc_backend().await;
+        assert!(HealthChecker::check_health(backend(port, "greeter")).await);
+        assert!(!HealthChecker::check_health(backend(port, "billing")).await);
+
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        assert!(!HealthChecker::update_health(backend(closed, "greeter")).await);
+
+        // Sans HTTP/2 vers le backend, la vérification gRPC est refusée à la configuration
+        let http1 = BackendConfig {
+            address: "127.0.0.1".to_string(),
+            port,
+            health_check: Some(HealthCheckConfig::Grpc { service: String::new() }),
+            ..Default::default()
+        };
+        assert!(BackendServer::from_config(&http1).is_err());
+        assert!(BackendServer::from_config(&BackendConfig { protocol: Some(UpstreamProtocol::Auto), ..http1 }).is_err());
+    }
+
+    /// Teste la boucle de vérification : un backend tombé puis rétabli démarre sa montée en charge.
+    #[tokio::test]
+    async fn test_watch_starts_slow_start_on_recovery() {
+        let up = Arc::new(AtomicBool::new(true));
+        let flag = up.clone();
+        let port = spawn_server(move |req: Request<Body>| {
+            let status = if flag.load(Ordering::SeqCst) { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+            async move {
+                assert_eq!(req.uri().path(), "/health");
+                Response::builder().status(status).body(Body::empty()).unwrap()
+            }
+        })
+        .await;
+        let backend = BackendServer::with_weight("127.0.0.1".to_string(), port, 10);
+        HealthChecker::watch(vec![backend.clone()], Duration::from_millis(20));
+        let wait_for = |healthy: bool| {
+            let backend = backend.clone();
+            async move {
+                for _ in 0..100 {
+                    if backend.is_healthy() == healthy {
+                        return;
+                    }
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                panic!("backend never became {}", if healthy { "healthy" } else { "unhealthy" });
+            }
+        };
+
+        // Une réponse 503 rend le backend indisponible
+        up.store(false, Ordering::SeqCst);
+        wait_for(false).await;
+        assert!(backend.warmup_started().is_none());
+
+        // Le rétablissement démarre la montée en charge : le poids remonte de 10 % à 100 % sur la fenêtre
+        up.store(true, Ordering::SeqCst);
+        wait_for(true).await;
+        let started = backend.warmup_started().unwrap();
+        let slow_start = SlowStart::new(Duration::from_secs(10));
+        assert!((slow_start.effective_weight(&backend, 10, started) - 1.0).abs() < 1e-9);
+        assert!((slow_start.effective_weight(&backend, 10, started + Duration::from_secs(5)) - 5.0).abs() < 1e-9);
+        assert_eq!(slow_start.effective_weight(&backend, 10, started + Duration::from_secs(10)), 10.0);
+    }
+}
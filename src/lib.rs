@@ -7,10 +7,47 @@ pub mod load_balancer;
 pub mod request_handler;
 pub mod health;
 pub mod error;
+pub mod slow_start;
+pub mod priority;
+pub mod zone;
+pub mod router;
+pub mod override_routing;
+pub mod tls;
+pub mod cert_reload;
+pub mod listener;
+pub mod upstream;
+pub mod passthrough;
+pub mod tcp;
+pub mod udp;
+pub mod shutdown;
+pub mod tunnel;
+pub mod grpc;
+pub mod proxy_protocol;
+pub mod connection_pool;
+pub mod queue;
+pub mod concurrency;
+pub mod rate_limit;
+pub mod rate_limit_store;
+pub mod limits;
+pub mod access;
+pub mod jwt;
+pub mod auth;
+pub mod auth_request;
+#[cfg(test)]
+mod test_support;
 pub use backend::BackendServer;
-pub use client::Client;
+pub use client::{AuthMethod, Client, ClientIdentity, Principal};
 pub use config::Config;
-pub use load_balancer::{LoadBalancer, RoundRobinLoadBalancer, WeightedRoundRobinLoadBalancer, LeastConnectionsLoadBalancer};
+pub use load_balancer::{build_load_balancer, SharedLoadBalancer, LoadBalancer, RoundRobinLoadBalancer, WeightedRoundRobinLoadBalancer, LeastConnectionsLoadBalancer};
 pub use request_handler::RequestHandler;
 pub use health::HealthChecker;
 pub use error::AppError;
+pub use slow_start::SlowStart;
+pub use priority::PriorityLoadBalancer;
+pub use zone::ZoneAwareLoadBalancer;
+pub use router::Router;
+pub use override_routing::OverrideRouting;
+pub use passthrough::PassthroughListener;
+pub use tcp::TcpProxy;
+pub use udp::UdpProxy;
+pub use shutdown::Shutdown;
@@ -0,0 +1,288 @@
+use std::io; // Importation des types d'entrée/sortie
+use std::future::Future; // Importation de Future pour interroger l'échéance
+use std::pin::Pin; // Importation de Pin pour implémenter les traits d'entrée/sortie
+use std::sync::atomic::{AtomicBool, Ordering}; // Importation des indicateurs partagés avec le service
+use std::sync::Arc; // Importation de Arc pour partager les indicateurs
+use std::task::{Context, Poll}; // Importation des types de polling asynchrone
+use std::time::Duration; // Importation de Duration pour le délai de lecture des en-têtes
+use futures::StreamExt; // Importation de StreamExt pour compter les octets du corps
+use hyper::body::HttpBody; // Importation de HttpBody pour détecter les corps vides
+use hyper::header::CONTENT_LENGTH; // Importation de l'en-tête de longueur du corps
+use hyper::{Body, Request, StatusCode}; // Importation des types de requêtes HTTP
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf}; // Importation des traits d'entrée/sortie asynchrones
+use tokio::time::Sleep; // Importation de Sleep pour l'échéance de lecture des en-têtes
+
+/// Réponse envoyée aux clients trop lents à envoyer les en-têtes de leur requête.
+const REQUEST_TIMEOUT_RESPONSE: &[u8] = b"HTTP/1.1 408 Request Timeout\r\nConnection: close\r\nContent-Length: 0\r\n\r\n";
+
+/// Limites de taille et de durée des requêtes reçues par les listeners HTTP.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LimitsConfig {
+    #[serde(default = "default_max_body_bytes", deserialize_with = "deserialize_max_body_bytes")]
+    pub max_body_bytes: Option<u64>, // Taille maximale du corps (413 au-delà) ; 10 Mio par défaut, illimitée si 0
+    #[serde(default = "default_max_header_count")]
+    pub max_header_count: usize, // Nombre maximal d'en-têtes (431 au-delà)
+    #[serde(default = "default_max_header_bytes")]
+    pub max_header_bytes: usize, // Taille cumulée maximale des en-têtes (431 au-delà)
+    #[serde(default = "default_max_uri_length")]
+    pub max_uri_length: usize, // Longueur maximale de l'URI (414 au-delà)
+    #[serde(default = "default_header_read_timeout_secs")]
+    pub header_read_timeout_secs: u64, // Délai de réception des en-têtes (408 au-delà)
+}
+
+fn default_max_body_bytes() -> Option<u64> {
+    Some(10 * 1024 * 1024)
+}
+
+/// Lit `max_body_bytes` : 0 désactive la limite (flux gRPC clients de longue durée, envois volumineux).
+fn deserialize_max_body_bytes<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    let max = u64::deserialize(deserializer)?;
+    Ok((max > 0).then_some(max))
+}
+
+fn default_max_header_count() -> usize {
+    100
+}
+
+fn default_max_header_bytes() -> usize {
+    32 * 1024
+}
+
+fn default_max_uri_length() -> usize {
+    8 * 1024
+}
+
+fn default_header_read_timeout_secs() -> u64 {
+    10
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: default_max_body_bytes(),
+            max_header_count: default_max_header_count(),
+            max_header_bytes: default_max_header_bytes(),
+            max_uri_length: default_max_uri_length(),
+            header_read_timeout_secs: default_header_read_timeout_secs(),
+        }
+    }
+}
+
+impl LimitsConfig {
+    /// Délai de réception des en-têtes.
+    pub fn header_read_timeout(&self) -> Duration {
+        Duration::from_secs(self.header_read_timeout_secs)
+    }
+
+    /// Taille du tampon de lecture de hyper : une requête qui le dépasse est refusée par hyper (431 ou 414).
+    pub fn max_buf_size(&self) -> usize {
+        // hyper exige au moins 8 Kio ; la marge couvre la ligne de requête et les séparateurs
+        (self.max_uri_length + self.max_header_bytes + 1024).max(8192)
+    }
+
+    /// Vérifie la ligne de requête et les en-têtes ; retourne le statut d'erreur et son message en cas de dépassement.
+    pub fn check_head(&self, req: &Request<Body>) -> Result<(), (StatusCode, &'static str)> {
+        if req.uri().to_string().len() > self.max_uri_length {
+            return Err((StatusCode::URI_TOO_LONG, "URI too long"));
+        }
+        let header_bytes: usize = req.headers().iter().map(|(name, value)| name.as_str().len() + value.len()).sum();
+        if req.headers().len() > self.max_header_count || header_bytes > self.max_header_bytes {
+            return Err((StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE, "Request header fields too large"));
+        }
+        let content_length = req
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        if let (Some(max), Some(length)) = (self.max_body_bytes, content_length) {
+            if length > max {
+                return Err((StatusCode::PAYLOAD_TOO_LARGE, "Payload too large"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Borne le corps d'une requête sans `Content-Length` (transfert en morceaux, HTTP/2).
+    ///
+    /// Le corps retourné échoue dès que `max_body_bytes` est dépassé, et `exceeded` est alors levé
+    /// pour que l'échec de la transmission soit rapporté en 413.
+    pub fn limit_body(&self, req: Request<Body>, exceeded: Arc<AtomicBool>) -> Request<Body> {
+        let max = match self.max_body_bytes {
+            Some(max) if !req.headers().contains_key(CONTENT_LENGTH) && !req.body().is_end_stream() => max,
+            _ => return req, // hyper vérifie lui-même la longueur annoncée
+        };
+        let (parts, body) = req.into_parts();
+        let mut received = 0u64;
+        let body = body.map(move |chunk| {
+            let chunk = chunk?;
+            received += chunk.len() as u64;
+            if received > max {
+                exceeded.store(true, Ordering::SeqCst);
+                return Err(Box::<dyn std::error::Error + Send + Sync>::from("request body too large"));
+            }
+            Ok(chunk)
+        });
+        Request::from_parts(parts, Body::wrap_stream(body))
+    }
+}
+
+/// Flux d'une connexion cliente qui répond 408 et se ferme si les en-têtes de la première requête
+/// n'arrivent pas avant l'échéance (protection contre slowloris).
+///
+/// `headers_received` est levé par le service à la réception de la première requête ; le délai
+/// des requêtes suivantes sur la même connexion est appliqué par hyper, qui ferme la connexion.
+pub struct HeaderDeadline<S> {
+    inner: S, // Flux de la connexion
+    deadline: Pin<Box<Sleep>>, // Échéance de réception des en-têtes
+    headers_received: Arc<AtomicBool>, // Levé par le service à la réception des en-têtes
+    timeout_written: Option<usize>, // Octets de la réponse 408 déjà écrits, une fois l'échéance passée
+}
+
+impl<S> HeaderDeadline<S> {
+    /// Enveloppe le flux d'une connexion qui vient d'être acceptée.
+    pub fn new(inner: S, timeout: Duration, headers_received: Arc<AtomicBool>) -> Self {
+        Self { inner, deadline: Box::pin(tokio::time::sleep(timeout)), headers_received, timeout_written: None }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for HeaderDeadline<S> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        if this.timeout_written.is_none() && !this.headers_received.load(Ordering::SeqCst) && this.deadline.as_mut().poll(cx).is_ready() {
+            this.timeout_written = Some(0);
+        }
+        let written = match this.timeout_written.as_mut() {
+            Some(written) => written,
+            None => return Pin::new(&mut this.inner).poll_read(cx, buf),
+        };
+        // Échéance passée : la réponse 408 est écrite, puis la connexion est vue comme fermée
+        while *written < REQUEST_TIMEOUT_RESPONSE.len() {
+            match Pin::new(&mut this.inner).poll_write(cx, &REQUEST_TIMEOUT_RESPONSE[*written..]) {
+                Poll::Ready(Ok(0)) => return Poll::Ready(Ok(())),
+                Poll::Ready(Ok(len)) => *written += len,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        match Pin::new(&mut this.inner).poll_shutdown(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(_) => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for HeaderDeadline<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use crate::backend::BackendServer;
+    use crate::listener::serve_http;
+    use crate::load_balancer::RoundRobinLoadBalancer;
+    use crate::request_handler::RequestHandler;
+    use crate::shutdown::Shutdown;
+    use crate::test_support::spawn_server;
+
+    /// Lance un backend qui lit le corps des requêtes avant de répondre 200.
+    async fn backend() -> u16 {
+        spawn_server(|req: Request<Body>| async {
+            let _ = hyper::body::to_bytes(req.into_body()).await;
+            hyper::Response::new(Body::empty())
+        })
+        .await
+    }
+
+    /// Lance le listener HTTP avec les limites données devant un backend.
+    async fn proxy(limits: LimitsConfig) -> SocketAddr {
+        proxy_with(limits, false).await
+    }
+
+    /// Lance le listener HTTP, attendant éventuellement un en-tête PROXY protocol.
+    async fn proxy_with(limits: LimitsConfig, proxy_protocol: bool) -> SocketAddr {
+        let addr = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let backend = BackendServer::new("127.0.0.1".to_string(), backend().await);
+        let handler = RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(vec![backend]))).with_limits(limits);
+        tokio::spawn(serve_http(addr, Arc::new(handler), Shutdown::new(), proxy_protocol));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        addr
+    }
+
+    /// Envoie une requête brute et retourne la ligne de statut de la réponse.
+    async fn status_line(addr: SocketAddr, request: &[u8]) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request).await.unwrap();
+        let mut response = Vec::new();
+        let _ = tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut response)).await;
+        String::from_utf8_lossy(&response).lines().next().unwrap_or_default().to_string()
+    }
+
+    /// Teste les réponses 414, 431 et 413, avant toute transmission au backend.
+    #[tokio::test]
+    async fn test_size_limits() {
+        let limits = LimitsConfig { max_body_bytes: Some(16), max_header_count: 5, max_uri_length: 64, ..Default::default() };
+        let addr = proxy(limits).await;
+
+        let long_uri = format!("GET /{} HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n", "a".repeat(100));
+        assert!(status_line(addr, long_uri.as_bytes()).await.contains("414"));
+        let headers: String = (0..10).map(|i| format!("X-Header-{}: {}\r\n", i, i)).collect();
+        let many_headers = format!("GET / HTTP/1.1\r\nHost: a\r\n{}Connection: close\r\n\r\n", headers);
+        assert!(status_line(addr, many_headers.as_bytes()).await.contains("431"));
+        let declared = b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 17\r\nConnection: close\r\n\r\n";
+        assert!(status_line(addr, declared).await.contains("413"));
+        let chunked = b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n20\r\n0123456789abcdef0123456789abcdef\r\n0\r\n\r\n";
+        assert!(status_line(addr, chunked).await.contains("413"));
+        // Les requêtes dans les limites atteignent le backend
+        let small = b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 4\r\nConnection: close\r\n\r\ntest";
+        assert!(status_line(addr, small).await.contains("200"));
+        let small_chunked = b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n4\r\ntest\r\n0\r\n\r\n";
+        assert!(status_line(addr, small_chunked).await.contains("200"));
+    }
+
+    /// Teste la taille du corps par défaut et sa désactivation explicite.
+    #[test]
+    fn test_max_body_bytes_config() {
+        let limits: LimitsConfig = toml::from_str("").unwrap();
+        assert_eq!(limits.max_body_bytes, Some(10 * 1024 * 1024));
+        let limits: LimitsConfig = toml::from_str("max_body_bytes = 0").unwrap();
+        assert_eq!(limits.max_body_bytes, None);
+        let limits: LimitsConfig = toml::from_str("max_body_bytes = 1024").unwrap();
+        assert_eq!(limits.max_body_bytes, Some(1024));
+    }
+
+    /// Teste la réponse 408 d'un client qui n'envoie pas ses en-têtes à temps (slowloris).
+    #[tokio::test]
+    async fn test_header_read_timeout() {
+        let addr = proxy(LimitsConfig { header_read_timeout_secs: 1, ..Default::default() }).await;
+        let started = std::time::Instant::now();
+        assert!(status_line(addr, b"GET / HTTP/1.1\r\nHost: a\r\n").await.contains("408"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    /// Teste la fermeture d'une connexion dont l'en-tête PROXY protocol n'arrive pas à temps.
+    #[tokio::test]
+    async fn test_proxy_header_timeout() {
+        let addr = proxy_with(LimitsConfig { header_read_timeout_secs: 1, ..Default::default() }, true).await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"PROXY TCP4 ").await.unwrap();
+        let mut response = Vec::new();
+        let closed = tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut response)).await;
+        assert!(closed.is_ok());
+        assert!(response.is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+use std::net::SocketAddr; // Importation de SocketAddr pour l'adresse d'écoute et celle du client
+use std::sync::Arc; // Importation de Arc pour le partage sécurisé entre threads
+use std::time::Duration; // Importation de Duration pour le délai de lecture des en-têtes
+use std::sync::atomic::{AtomicBool, Ordering}; // Importation de l'indicateur de réception des en-têtes
+use hyper::server::conn::Http; // Importation du service de connexions HTTP de hyper
+use hyper::service::service_fn; // Importation de la fonction pour créer des services HTTP
+use rustls::ServerConfig; // Importation de la configuration TLS du serveur
+use tokio::io::{AsyncRead, AsyncWrite}; // Importation des traits d'entrée/sortie asynchrones
+use tokio::net::{TcpListener, TcpStream}; // Importation de TcpListener pour accepter les connexions
+use tokio_rustls::TlsAcceptor; // Importation de TlsAcceptor pour la poignée de main TLS
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::limits::HeaderDeadline; // Importation de l'échéance de réception des en-têtes
+use crate::proxy_protocol; // Importation de la lecture des en-têtes PROXY protocol
+use crate::request_handler::RequestHandler; // Importation du gestionnaire de requêtes
+use crate::shutdown::Shutdown; // Importation du signal d'arrêt
+
+/// Marge ajoutée au délai de lecture des en-têtes appliqué par hyper aux requêtes suivant la première.
+const KEEP_ALIVE_HEADER_GRACE: Duration = Duration::from_secs(1);
+
+/// Sert les requêtes HTTPS reçues sur `addr` en terminant TLS.
+///
+/// Le certificat est choisi par SNI et le protocole (h2 ou HTTP/1.1) par ALPN ; chaque connexion
+/// est traitée dans sa propre tâche afin qu'une poignée de main lente ne bloque pas les autres.
+/// Le listener cesse d'accepter des connexions au déclenchement de `shutdown`.
+pub async fn serve_tls(
+    addr: SocketAddr,
+    tls_config: Arc<ServerConfig>,
+    request_handler: Arc<RequestHandler>,
+    shutdown: Shutdown,
+    proxy_protocol: bool,
+) -> Result<(), AppError> {
+    let listener = TcpListener::bind(addr).await?;
+    let acceptor = TlsAcceptor::from(tls_config);
+
+    loop {
+        let (stream, peer_addr) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = shutdown.wait() => return Ok(()),
+        };
+        let acceptor = acceptor.clone();
+        let request_handler = request_handler.clone();
+
+        tokio::spawn(async move {
+            let timeout = request_handler.limits().header_read_timeout();
+            let (stream, client_addr) = match accept_client(stream, peer_addr, proxy_protocol, timeout).await {
+                Some(accepted) => accepted,
+                None => return,
+            };
+            // Poignée de main TLS avec le client, bornée comme la réception des en-têtes
+            let stream = match tokio::time::timeout(timeout, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => {
+                    log::debug!("TLS handshake with {} failed: {}", client_addr, e);
+                    return;
+                }
+                Err(_) => {
+                    log::debug!("TLS handshake with {} timed out", client_addr);
+                    return;
+                }
+            };
+            serve_connection(stream, client_addr, request_handler).await;
+        });
+    }
+}
+
+/// Sert les requêtes HTTP en clair reçues sur `addr`.
+///
+/// Si `proxy_protocol` est activé, les connexions commencent par un en-tête PROXY protocol,
+/// lu avant la première requête. Le listener cesse d'accepter des connexions au déclenchement de `shutdown`.
+pub async fn serve_http(
+    addr: SocketAddr,
+    request_handler: Arc<RequestHandler>,
+    shutdown: Shutdown,
+    proxy_protocol: bool,
+) -> Result<(), AppError> {
+    let listener = TcpListener::bind(addr).await?;
+    loop {
+        let (stream, peer_addr) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = shutdown.wait() => return Ok(()),
+        };
+        let request_handler = request_handler.clone();
+        tokio::spawn(async move {
+            let timeout = request_handler.limits().header_read_timeout();
+            if let Some((stream, client_addr)) = accept_client(stream, peer_addr, proxy_protocol, timeout).await {
+                serve_connection(stream, client_addr, request_handler).await;
+            }
+        });
+    }
+}
+
+/// Détermine l'adresse du client d'une connexion acceptée.
+///
+/// Derrière un répartiteur TCP, l'adresse d'origine est lue dans l'en-tête PROXY protocol ;
+/// les connexions sans en-tête valide, ou dont l'en-tête n'arrive pas avant `timeout`, sont refusées.
+async fn accept_client(
+    mut stream: TcpStream,
+    peer_addr: SocketAddr,
+    proxy_protocol: bool,
+    timeout: Duration,
+) -> Option<(TcpStream, SocketAddr)> {
+    if !proxy_protocol {
+        return Some((stream, peer_addr));
+    }
+    match tokio::time::timeout(timeout, proxy_protocol::read_header(&mut stream)).await {
+        Ok(Ok(Some(addresses))) => Some((stream, addresses.source)),
+        Ok(Ok(None)) => Some((stream, peer_addr)), // Connexion du répartiteur lui-même
+        Ok(Err(e)) => {
+            log::warn!("Rejecting connection from {}: {}", peer_addr, e);
+            None
+        }
+        Err(_) => {
+            log::debug!("PROXY protocol header from {} timed out", peer_addr);
+            None
+        }
+    }
+}
+
+/// Sert les requêtes d'une connexion ; hyper détecte HTTP/2 grâce à la préface du client.
+///
+/// Un client qui n'a pas envoyé les en-têtes de sa première requête dans le délai configuré
+/// reçoit un 408 ; les requêtes dépassant le tampon de lecture sont refusées par hyper (431, 414).
+async fn serve_connection<S>(stream: S, client_addr: SocketAddr, request_handler: Arc<RequestHandler>)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let limits = request_handler.limits().clone();
+    let headers_received = Arc::new(AtomicBool::new(false));
+    let stream = HeaderDeadline::new(stream, limits.header_read_timeout(), headers_received.clone());
+    let service = service_fn(move |req| {
+        headers_received.store(true, Ordering::SeqCst);
+        let request_handler = request_handler.clone();
+        async move { request_handler.handle_request(req, client_addr).await }
+    });
+    let mut http = Http::new();
+    http.max_buf_size(limits.max_buf_size())
+        // Requêtes suivantes : hyper ferme la connexion ; son délai, vérifié avant chaque lecture,
+        // est allongé pour que la première requête reçoive le 408 de `HeaderDeadline`
+        .http1_header_read_timeout(limits.header_read_timeout() + KEEP_ALIVE_HEADER_GRACE)
+        .http2_max_header_list_size(limits.max_header_bytes as u32);
+    if let Err(e) = http.serve_connection(stream, service).with_upgrades().await {
+        log::debug!("Connection with {} closed with error: {}", client_addr, e);
+    }
+}
@@ -1,15 +1,95 @@
 use std::sync::{Arc, Mutex}; // Importation de Arc pour le partage sécurisé entre threads et Mutex pour la synchronisation
 use std::sync::atomic::{AtomicUsize, Ordering}; // Importation de AtomicUsize pour les opérations atomiques sur les indices
+use std::time::Instant; // Importation de Instant pour le calcul des poids effectifs
 use crate::backend::BackendServer; // Importation de la structure BackendServer depuis le module backend
+use crate::slow_start::SlowStart; // Importation de la configuration de montée en charge progressive
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::priority::PriorityLoadBalancer; // Importation de la répartition par tiers de priorité
+use crate::zone::{ZoneAwareConfig, ZoneAwareLoadBalancer}; // Importation du routage par zone
+
+/// Serveurs backend partagés, chacun accompagné d'une valeur propre à la stratégie (poids, connexions initiales).
+type SharedBackends<T> = Arc<Mutex<Vec<(Arc<BackendServer>, T)>>>;
 
 /// Trait pour les algorithmes de répartition de charge.
 /// Définit une interface commune pour sélectionner un serveur backend.
 pub trait LoadBalancer {
     /// Sélectionne un serveur backend parmi ceux disponibles.
     /// Une référence partagée au serveur backend sélectionné.
+    ///
+    /// Les backends hors service (`BackendServer::is_healthy`) sont évités tant qu'un autre est sain,
+    /// puis les backends saturés (`BackendServer::is_saturated`) tant qu'un autre a de la capacité.
     fn select_backend(&self) -> Arc<BackendServer>;
 }
 
+/// Disponibilité d'un backend, de la meilleure à la pire : sain avec de la capacité, sain mais saturé, hors service.
+fn availability(backend: &BackendServer) -> u8 {
+    match (backend.is_healthy(), backend.is_saturated()) {
+        (true, false) => 0,
+        (true, true) => 1,
+        (false, _) => 2,
+    }
+}
+
+/// Load balancer partagé entre les tâches du serveur.
+pub type SharedLoadBalancer = Arc<dyn LoadBalancer + Send + Sync>;
+
+/// Construit le load balancer correspondant à la stratégie nommée dans la configuration.
+///
+/// Retourne une erreur de configuration si la stratégie est inconnue.
+pub fn build_load_balancer(
+    strategy: &str,
+    backends: Vec<Arc<BackendServer>>,
+    slow_start: Option<SlowStart>,
+) -> Result<SharedLoadBalancer, AppError> {
+    let load_balancer: SharedLoadBalancer = match strategy {
+        "round_robin" => Arc::new(RoundRobinLoadBalancer::new(backends)), // Utilise le Round Robin si spécifié
+        "weighted_round_robin" => {
+            // Crée des paires de serveurs et de poids pour le Weighted Round Robin
+            let weighted_backends = backends.iter().map(|b| (b.clone(), b.weight())).collect();
+            let load_balancer = WeightedRoundRobinLoadBalancer::new(weighted_backends);
+            match slow_start {
+                Some(slow_start) => Arc::new(load_balancer.with_slow_start(slow_start)), // Montée en charge progressive
+                None => Arc::new(load_balancer),
+            }
+        }
+        "least_connections" => {
+            // Crée des paires de serveurs et de connexions initiales pour le Least Connections
+            let least_connections_backends = backends.iter().map(|b| (b.clone(), 0)).collect();
+            let load_balancer = LeastConnectionsLoadBalancer::new(least_connections_backends);
+            match slow_start {
+                Some(slow_start) => Arc::new(load_balancer.with_slow_start(slow_start)), // Montée en charge progressive
+                None => Arc::new(load_balancer),
+            }
+        }
+        _ => return Err(AppError::ConfigError(format!("Unknown load balancer strategy: {}", strategy))),
+    };
+    Ok(load_balancer)
+}
+
+/// Construit la stratégie d'un groupe de backends en tenant compte de leur topologie.
+///
+/// Si des tiers de priorité ou des backends de secours sont déclarés, la stratégie est appliquée
+/// par tier ; si la zone du proxy est connue, les backends de la même zone sont préférés.
+pub fn build_topology_load_balancer(
+    strategy: &str,
+    backends: Vec<Arc<BackendServer>>,
+    slow_start: Option<SlowStart>,
+    overprovisioning_factor: f64,
+    zone_aware: Option<&ZoneAwareConfig>,
+) -> Result<SharedLoadBalancer, AppError> {
+    let build = |backends: Vec<Arc<BackendServer>>| -> Result<SharedLoadBalancer, AppError> {
+        if backends.iter().any(|b| b.priority() > 0 || b.is_backup()) {
+            Ok(Arc::new(PriorityLoadBalancer::new(backends, strategy, slow_start, overprovisioning_factor)?))
+        } else {
+            build_load_balancer(strategy, backends, slow_start)
+        }
+    };
+    match zone_aware {
+        Some(zone_aware) => Ok(Arc::new(ZoneAwareLoadBalancer::new(zone_aware, backends, build)?)),
+        None => build(backends),
+    }
+}
+
 /// Répartition de charge Round Robin.
 /// Cet algorithme sélectionne les serveurs backend de manière circulaire.
 pub struct RoundRobinLoadBalancer {
@@ -30,77 +110,143 @@ impl RoundRobinLoadBalancer {
 
 impl LoadBalancer for RoundRobinLoadBalancer {
     /// Sélectionne un serveur backend en utilisant l'algorithme Round Robin.
-
+    ///
     /// Une référence partagée au serveur backend sélectionné.
     fn select_backend(&self) -> Arc<BackendServer> {
         // Récupère l'indice du serveur backend à sélectionner
         let index = self.current.fetch_add(1, Ordering::SeqCst) % self.backends.len();
-        // Retourne une copie du serveur backend à l'indice sélectionné
-        self.backends[index].clone()
+        // Passe aux suivants si le backend est hors service ou saturé
+        let available = (0..self.backends.len())
+            .map(|offset| &self.backends[(index + offset) % self.backends.len()])
+            .min_by_key(|backend| availability(backend))
+            .unwrap();
+        // Retourne une copie du serveur backend sélectionné
+        available.clone()
+    }
+}
+
+/// Calcule le poids effectif d'un backend, réduit pendant sa montée en charge si le slow start est activé.
+fn effective_weight(slow_start: Option<&SlowStart>, backend: &BackendServer, weight: u32, now: Instant) -> f64 {
+    match slow_start {
+        Some(slow_start) => slow_start.effective_weight(backend, weight, now),
+        None => weight as f64,
     }
 }
 
 /// Répartition de charge Round Robin pondéré.
 /// Cet algorithme sélectionne les serveurs backend en fonction de poids attribués à chaque serveur.
+/// La sélection est "lissée" : les backends lourds sont entrecoupés des autres plutôt que servis en rafale.
 pub struct WeightedRoundRobinLoadBalancer {
-    backends: Arc<Mutex<Vec<(Arc<BackendServer>, u32)>>>, // Liste des serveurs backend avec leurs poids respectifs
-    current: AtomicUsize, // Indice actuel pour la sélection du serveur backend
+    backends: SharedBackends<u32>, // Liste des serveurs backend avec leurs poids respectifs
+    current_weights: Mutex<Vec<f64>>, // Poids courants utilisés par l'algorithme de Round Robin lissé
+    slow_start: Option<SlowStart>, // Montée en charge progressive des backends rétablis, si configurée
 }
 
 impl WeightedRoundRobinLoadBalancer {
     /// Crée une nouvelle instance de `WeightedRoundRobinLoadBalancer`.
     /// Une nouvelle instance de `WeightedRoundRobinLoadBalancer`.
     pub fn new(backends: Vec<(Arc<BackendServer>, u32)>) -> Self {
+        let current_weights = vec![0.0; backends.len()]; // Tous les poids courants démarrent à 0
         Self {
             backends: Arc::new(Mutex::new(backends)), // Enveloppe la liste des backends dans un Mutex pour la synchronisation
-            current: AtomicUsize::new(0), // Initialise l'indice courant à 0
+            current_weights: Mutex::new(current_weights),
+            slow_start: None,
         }
     }
+
+    /// Active la montée en charge progressive des backends ajoutés ou rétablis.
+    pub fn with_slow_start(mut self, slow_start: SlowStart) -> Self {
+        self.slow_start = Some(slow_start);
+        self
+    }
 }
 
 impl LoadBalancer for WeightedRoundRobinLoadBalancer {
     /// Sélectionne un serveur backend en utilisant l'algorithme Round Robin pondéré.
-    
+    ///
     /// Une référence partagée au serveur backend sélectionné.
     fn select_backend(&self) -> Arc<BackendServer> {
         // Verrouille l'accès à la liste des serveurs backend pour une lecture sécurisée
         let backends = self.backends.lock().unwrap();
-        // Récupère l'indice du serveur backend à sélectionner
-        let index = self.current.fetch_add(1, Ordering::SeqCst) % backends.len();
+        let mut current_weights = self.current_weights.lock().unwrap();
+        let now = Instant::now();
+
+        // Chaque backend gagne son poids effectif, le plus "en avance" est sélectionné ;
+        // seuls participent les backends de la meilleure disponibilité présente
+        let best = backends.iter().map(|(backend, _)| availability(backend)).min().unwrap_or(0);
+        let mut total = 0.0;
+        let mut selected = None;
+        for (index, (backend, weight)) in backends.iter().enumerate() {
+            if availability(backend) > best {
+                continue;
+            }
+            let weight = effective_weight(self.slow_start.as_ref(), backend, *weight, now);
+            current_weights[index] += weight;
+            total += weight;
+            if selected.is_none_or(|selected| current_weights[index] > current_weights[selected]) {
+                selected = Some(index);
+            }
+        }
+        let selected = selected.unwrap_or(0);
+        // Le backend sélectionné rend le total distribué pour laisser passer les autres
+        current_weights[selected] -= total;
+
         // Retourne une copie du serveur backend à l'indice sélectionné
-        backends[index].0.clone()
+        backends[selected].0.clone()
     }
 }
 
 /// Répartition de charge basée sur le nombre de connexions.
 /// Cet algorithme sélectionne le serveur backend avec le moins de connexions actuelles.
+/// Le nombre de connexions est rapporté au poids effectif de chaque backend.
+/// Les connexions sont celles comptées par `BackendServer::track_connection`, y compris les
+/// tunnels de longue durée, auxquelles s'ajoute le nombre initial donné à la construction.
 pub struct LeastConnectionsLoadBalancer {
-    backends: Arc<Mutex<Vec<(Arc<BackendServer>, usize)>>>, // Liste des serveurs backend avec le nombre actuel de connexions
+    backends: SharedBackends<usize>, // Liste des serveurs backend avec leur nombre initial de connexions
+    slow_start: Option<SlowStart>, // Montée en charge progressive des backends rétablis, si configurée
 }
 
 impl LeastConnectionsLoadBalancer {
     /// Crée une nouvelle instance de `LeastConnectionsLoadBalancer`.
-    
+    ///
     /// Une nouvelle instance de `LeastConnectionsLoadBalancer`.
     pub fn new(backends: Vec<(Arc<BackendServer>, usize)>) -> Self {
         Self {
             backends: Arc::new(Mutex::new(backends)), // Enveloppe la liste des backends dans un Mutex pour la synchronisation
+            slow_start: None,
         }
     }
+
+    /// Active la montée en charge progressive des backends ajoutés ou rétablis.
+    pub fn with_slow_start(mut self, slow_start: SlowStart) -> Self {
+        self.slow_start = Some(slow_start);
+        self
+    }
 }
 
 impl LoadBalancer for LeastConnectionsLoadBalancer {
     /// Sélectionne un serveur backend en utilisant l'algorithme de la moindre connexion.
-    
+    ///
     /// Une référence partagée au serveur backend sélectionné.
     fn select_backend(&self) -> Arc<BackendServer> {
         // Verrouille l'accès à la liste des serveurs backend pour une lecture sécurisée
-        let mut backends = self.backends.lock().unwrap();
-        // Trouve le serveur backend avec le moins de connexions
-        let (backend, connections) = backends.iter_mut().min_by_key(|(_, connections)| *connections).unwrap();
-        // Incrémente le nombre de connexions pour le serveur sélectionné
-        *connections += 1;
-        // Retourne une copie du serveur backend sélectionné
+        let backends = self.backends.lock().unwrap();
+        let now = Instant::now();
+        // Trouve le serveur backend avec le moins de connexions par unité de poids effectif
+        let load = |backend: &BackendServer, connections: usize| {
+            let weight = effective_weight(self.slow_start.as_ref(), backend, backend.weight(), now);
+            if weight > 0.0 { (connections + 1) as f64 / weight } else { f64::INFINITY }
+        };
+        let (backend, _) = backends
+            .iter()
+            .min_by(|(a, a_initial), (b, b_initial)| {
+                // Un backend hors service ou saturé n'est choisi que si aucun autre n'est disponible
+                availability(a)
+                    .cmp(&availability(b))
+                    .then(load(a, a_initial + a.active_connections()).total_cmp(&load(b, b_initial + b.active_connections())))
+            })
+            .unwrap();
+        // Retourne une copie du serveur backend sélectionné ; l'appelant compte la connexion qu'il ouvre
         backend.clone()
     }
 }
@@ -1,19 +1,23 @@
-use hyper::{Body, Request, Response, Server}; // Importation des composants de la bibliothèque hyper pour le serveur HTTP
-use hyper::service::{make_service_fn, service_fn}; // Importation des fonctions pour créer des services HTTP
+use std::net::SocketAddr; // Importation de SocketAddr pour l'adresse d'écoute
+use std::time::Duration; // Importation de Duration pour les intervalles de surveillance
 use std::sync::Arc; // Importation de Arc pour la gestion des références partagées entre threads
-use tokio::sync::Mutex; // Importation de Mutex pour la synchronisation dans le contexte de Tokio
 use std::error::Error; // Importation du trait Error pour le traitement des erreurs
-use crate::config::load_config; // Importation de la fonction pour charger la configuration
-use crate::backend::BackendServer; // Importation de la structure BackendServer pour représenter les serveurs backend
-use crate::load_balancer::{LoadBalancer, RoundRobinLoadBalancer, WeightedRoundRobinLoadBalancer, LeastConnectionsLoadBalancer}; // Importation des algorithmes de répartition de charge
-use crate::request_handler::RequestHandler; // Importation du gestionnaire de requêtes
-
-/// Fonction pour gérer les requêtes HTTP entrantes.
-/// Utilise un gestionnaire de requêtes pour traiter la requête.
-async fn handle_request(req: Request<Body>, request_handler: Arc<RequestHandler>) -> Result<Response<Body>, hyper::Error> {
-    // Appelle la méthode handle_request du RequestHandler pour traiter la requête
-    request_handler.handle_request().await
-}
+use exam::config::load_config; // Importation de la fonction pour charger la configuration
+use exam::backend::BackendServer; // Importation de la structure BackendServer pour représenter les serveurs backend
+use exam::load_balancer::{build_topology_load_balancer, SharedLoadBalancer}; // Importation de la construction des algorithmes de répartition de charge
+use exam::error::AppError; // Importation des erreurs de l'application
+use exam::request_handler::RequestHandler; // Importation du gestionnaire de requêtes
+use exam::router::Router; // Importation du routeur vers les pools de backends
+use exam::rate_limit_store::{MemoryStore, RateLimitStore}; // Importation du magasin partagé des limites de débit
+use exam::access::AccessList; // Importation des listes d'accès par adresse cliente
+use exam::override_routing::OverrideRouting; // Importation du forçage de routage par en-têtes
+use exam::tls::build_server_config; // Importation de la construction de la configuration TLS
+use exam::listener::{serve_http, serve_tls}; // Importation des listeners HTTP(S)
+use exam::passthrough::PassthroughListener; // Importation du listener TLS passthrough
+use exam::tcp::TcpProxy; // Importation de la répartition de charge TCP
+use exam::udp::UdpProxy; // Importation de la répartition de charge UDP
+use exam::shutdown::Shutdown; // Importation du signal d'arrêt
+use exam::health::HealthChecker; // Importation des vérifications de santé des backends
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -21,50 +25,202 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let config = load_config("config/config.toml")?;
 
     // Créer les serveurs backends à partir des informations de la configuration
-    let backends: Vec<_> = config.backend_servers.iter()
-        .map(|b| {
-            BackendServer::new(b.address.clone(), b.port)
-        })
-        .collect();
+    let backends = config.backend_servers.iter()
+        .map(BackendServer::from_config)
+        .collect::<Result<Vec<_>, AppError>>()?;
 
-    // Initialiser le load balancer en fonction de la stratégie spécifiée dans la configuration
-    let load_balancer: Arc<dyn LoadBalancer + Send + Sync> = match config.load_balancer.as_str() {
-        "round_robin" => Arc::new(RoundRobinLoadBalancer::new(backends.clone())), // Utilise le Round Robin si spécifié
-        "weighted_round_robin" => {
-            // Crée des paires de serveurs et de poids pour le Weighted Round Robin
-            let weighted_backends = backends.iter().map(|b| (b.clone(), 1)).collect();
-            Arc::new(WeightedRoundRobinLoadBalancer::new(weighted_backends))
-        },
-        "least_connections" => {
-            // Crée des paires de serveurs et de connexions initiales pour le Least Connections
-            let least_connections_backends = backends.iter().map(|b| (b.clone(), 0)).collect();
-            Arc::new(LeastConnectionsLoadBalancer::new(least_connections_backends))
-        },
-        _ => panic!("Unknown load balancer strategy"), // Panique si la stratégie de load balancer est inconnue
-    };
+    // Initialiser le load balancer en fonction de la stratégie spécifiée dans la configuration,
+    // par tier de priorité et en préférant la zone du proxy si elle est connue
+    let load_balancer: SharedLoadBalancer = build_topology_load_balancer(
+        &config.load_balancer,
+        backends.clone(),
+        config.slow_start,
+        config.overprovisioning_factor,
+        config.zone_aware.as_ref(),
+    )?;
+
+    // Ctrl-C (ou SIGTERM) arrête les listeners et ferme les tunnels WebSocket en cours
+    let shutdown = Shutdown::new();
+    let signal_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            match signal(SignalKind::terminate()) {
+                Ok(mut terminate) => tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                },
+                Err(e) => {
+                    // Sans SIGTERM, seul Ctrl-C déclenche l'arrêt propre
+                    eprintln!("Cannot listen for SIGTERM, only Ctrl-C will shut down: {}", e);
+                    let _ = tokio::signal::ctrl_c().await;
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = tokio::signal::ctrl_c().await;
+        signal_shutdown.trigger();
+    });
 
     // Crée un gestionnaire de requêtes en passant le load balancer
-    let request_handler = Arc::new(RequestHandler::new(load_balancer));
+    let mut request_handler = RequestHandler::new(load_balancer.clone())
+        .with_backends(backends.clone())
+        .with_shutdown(shutdown.clone());
+    let router = if config.pools.is_empty() {
+        None
+    } else {
+        let mut router = Router::new(&config.pools, &config.routes, &config.load_balancer, config.slow_start)?
+            .with_topology(config.overprovisioning_factor, config.zone_aware.as_ref())?; // Tiers et zones dans chaque pool
+        if let Some(store) = &config.rate_limit_store {
+            // Les compteurs des limites de débit sont partagés entre les instances du proxy
+            if store.serve {
+                let addr = store.address;
+                println!("Rate limit store listening on {}", addr);
+                tokio::spawn(async move {
+                    if let Err(e) = Arc::new(MemoryStore::new()).serve(addr).await {
+                        eprintln!("Rate limit store error on {}: {}", addr, e);
+                    }
+                });
+            }
+            router = router.with_rate_limit_store(Arc::new(RateLimitStore::new(store.clone())));
+        }
+        if let Some(queue) = &config.queue {
+            router = router.with_queue(queue.clone()); // Chaque pool a sa propre file d'attente
+        }
+        Some(Arc::new(router))
+    };
+    if let (Some(router), false) = (&router, config.routes.is_empty()) {
+        // Les routes répartissent leur trafic entre les pools nommés
+        request_handler = request_handler.with_router(router.clone());
+    }
+    // Les fichiers des listes d'accès sont relus lorsqu'ils changent, sans redémarrage
+    let access_reload_interval = Duration::from_secs(config.access_reload_interval_secs);
+    if let Some(access) = &config.access {
+        let access_list = Arc::new(AccessList::new(access.clone())?);
+        access_list.clone().watch(access_reload_interval);
+        request_handler = request_handler.with_access_list(access_list);
+    }
+    for access_list in router.iter().flat_map(|router| router.access_lists()) {
+        access_list.clone().watch(access_reload_interval);
+    }
 
-    // Crée un service HTTP avec hyper
-    let make_svc = make_service_fn(|_conn| {
-        let request_handler = request_handler.clone();
-        // Crée un service pour chaque connexion entrante, en utilisant le gestionnaire de requêtes
-        async move {
-            Ok::<_, hyper::Error>(service_fn(move |req| handle_request(req, request_handler.clone())))
+    // Les backends HTTP (principaux et pools) sont vérifiés périodiquement, chacun avec sa méthode (HTTP ou gRPC) ;
+    // ceux des listeners TCP, UDP et passthrough, qui ne parlent pas HTTP, en sont exclus
+    let layer4_pools: Vec<Option<&str>> = config.tcp.iter().map(|tcp| tcp.pool.as_deref())
+        .chain(config.udp.iter().map(|udp| udp.pool.as_deref()))
+        .chain(config.passthrough.iter().flat_map(|p| p.routes.iter().map(|r| r.pool.as_str()).chain(p.default_pool.as_deref())).map(Some))
+        .collect();
+    let mut monitored = if layer4_pools.contains(&None) { Vec::new() } else { backends.clone() };
+    for pool in router.iter().flat_map(|router| router.pools()) {
+        if !layer4_pools.contains(&Some(pool.name())) {
+            monitored.extend(pool.backends().iter().cloned());
         }
-    });
+    }
+    HealthChecker::watch(monitored, Duration::from_secs(config.health_check_interval_secs));
+
+    // Les listeners passthrough relaient le TCP brut vers le pool désigné par le SNI, sans déchiffrer
+    for passthrough in &config.passthrough {
+        let router = router.clone().ok_or_else(|| AppError::ConfigError("Passthrough requires pools".to_string()))?;
+        let listener = Arc::new(PassthroughListener::new(passthrough, router)?);
+        listener.clone().watch_health(Duration::from_secs(config.health_check_interval_secs)); // Santé par connexion TCP
+        let addr = passthrough.listen;
+        println!("Passthrough listening on {}", addr);
+        tokio::spawn(async move {
+            if let Err(e) = listener.serve(addr).await {
+                eprintln!("Passthrough error on {}: {}", addr, e);
+            }
+        });
+    }
+    if let Some(admin) = &config.admin {
+        request_handler = request_handler.with_admin_token(admin.token.clone()); // Permet d'ajuster les répartitions à chaud
+    }
+    if let Some(queue) = &config.queue {
+        request_handler = request_handler.with_queue(queue.clone()); // Attente lorsque tous les backends sont saturés
+    }
+    if let Some(adaptive_concurrency) = &config.adaptive_concurrency {
+        request_handler = request_handler.with_adaptive_concurrency(adaptive_concurrency.clone()); // Délestage d'après la latence
+    }
+    request_handler = request_handler.with_limits(config.limits.clone()); // Limites de taille des requêtes (413, 414, 431) et 408
+    if let Some(override_config) = &config.override_routing {
+        // Permet aux tests de forcer un pool ou un backend par en-têtes
+        request_handler = request_handler.with_override_routing(OverrideRouting::new(override_config.clone(), backends.clone()));
+    }
+    let request_handler = Arc::new(request_handler);
+
+    // Les listeners TCP relaient les connexions brutes vers leur pool, ou vers les backends principaux
+    for tcp in &config.tcp {
+        let (load_balancer, backends) = match &tcp.pool {
+            Some(name) => {
+                let pool = router.as_ref().and_then(|router| router.pool(name))
+                    .ok_or_else(|| AppError::ConfigError(format!("TCP listener references unknown pool {}", name)))?;
+                (pool.load_balancer(), pool.backends().to_vec())
+            }
+            None => (load_balancer.clone(), backends.clone()),
+        };
+        let proxy = Arc::new(
+            TcpProxy::new(load_balancer, backends)
+                .with_idle_timeout(Duration::from_secs(tcp.idle_timeout_secs))
+                .with_connect_timeout(Duration::from_secs(tcp.connect_timeout_secs))
+                .with_accept_proxy_protocol(tcp.accept_proxy_protocol)
+                .with_send_proxy_protocol(tcp.send_proxy_protocol),
+        );
+        proxy.clone().watch_health(Duration::from_secs(tcp.health_check_interval_secs));
+        let addr = tcp.listen;
+        println!("TCP listening on {}", addr);
+        tokio::spawn(async move {
+            if let Err(e) = proxy.serve(addr).await {
+                eprintln!("TCP listener error on {}: {}", addr, e);
+            }
+        });
+    }
+
+    // Les listeners UDP suivent les sessions par client, ou répartissent sans état par hachage
+    for udp in &config.udp {
+        let (load_balancer, backends) = match &udp.pool {
+            Some(name) => {
+                let pool = router.as_ref().and_then(|router| router.pool(name))
+                    .ok_or_else(|| AppError::ConfigError(format!("UDP listener references unknown pool {}", name)))?;
+                (pool.load_balancer(), pool.backends().to_vec())
+            }
+            None => (load_balancer.clone(), backends.clone()),
+        };
+        let proxy = Arc::new(
+            UdpProxy::new(load_balancer, backends)
+                .with_mode(udp.mode)
+                .with_session_timeout(Duration::from_secs(udp.session_timeout_secs))
+                .with_max_sessions(udp.max_sessions),
+        );
+        let addr = udp.listen;
+        println!("UDP listening on {}", addr);
+        tokio::spawn(async move {
+            if let Err(e) = proxy.serve(addr).await {
+                eprintln!("UDP listener error on {}: {}", addr, e);
+            }
+        });
+    }
 
     // Configure l'adresse du serveur
-    let addr = ([127, 0, 0, 1], 3000).into(); // Adresse locale et port 3000
-    let server = Server::bind(&addr).serve(make_svc); // Crée le serveur HTTP en liant l'adresse et en servant le service
+    let addr: SocketAddr = ([127, 0, 0, 1], 3000).into(); // Adresse locale et port 3000
 
-    println!("Listening on http://{}", addr); // Affiche l'adresse sur laquelle le serveur écoute
+    // Si la terminaison TLS est configurée, le listener ne sert que du HTTPS
+    if let Some(tls) = &config.tls {
+        let (tls_config, reloader) = build_server_config(tls)?;
+        // Les certificats renouvelés sont pris en compte sans redémarrage (fichiers surveillés ou SIGHUP)
+        reloader.watch(Duration::from_secs(tls.reload_interval_secs));
+        println!("Listening on https://{}", addr);
+        serve_tls(addr, tls_config, request_handler, shutdown, config.proxy_protocol).await?;
+        return Ok(());
+    }
 
-    // Démarre le serveur et attend les requêtes
-    if let Err(e) = server.await {
-        eprintln!("Server error: {}", e); // Affiche une erreur si le serveur rencontre un problème
+    // Le listener applique les limites de taille et le délai de lecture des en-têtes (408) ;
+    // derrière un répartiteur TCP, l'adresse du client est lue dans l'en-tête PROXY protocol
+    if config.proxy_protocol {
+        println!("Listening on http://{} (PROXY protocol)", addr);
+    } else {
+        println!("Listening on http://{}", addr); // Affiche l'adresse sur laquelle le serveur écoute
     }
+    serve_http(addr, request_handler, shutdown, config.proxy_protocol).await?;
 
     Ok(())
 }
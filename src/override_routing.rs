@@ -0,0 +1,235 @@
+use std::net::IpAddr; // Importation de IpAddr pour l'adresse du client
+use std::sync::Arc; // Importation de Arc pour le partage sécurisé entre threads
+use hyper::header::HeaderMap; // Importation des en-têtes HTTP
+use ipnet::IpNet; // Importation de IpNet pour les plages d'adresses de confiance
+use ring::constant_time::verify_slices_are_equal; // Importation de la comparaison en temps constant du secret
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration
+use crate::backend::BackendServer; // Importation de la structure BackendServer depuis le module backend
+use crate::router::Router; // Importation du routeur vers les pools de backends
+
+/// En-tête forçant le pool de destination d'une requête.
+pub const POOL_OVERRIDE_HEADER: &str = "X-Backend-Pool";
+/// En-tête forçant le serveur backend de destination d'une requête (`adresse:port`).
+pub const BACKEND_OVERRIDE_HEADER: &str = "X-Debug-Backend";
+/// En-tête portant le secret partagé autorisant les forçages.
+pub const OVERRIDE_SECRET_HEADER: &str = "X-Override-Secret";
+
+/// Configuration du forçage de routage par en-têtes, destiné aux tests.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OverrideConfig {
+    #[serde(default)]
+    pub secret: Option<String>, // Secret attendu dans l'en-tête `X-Override-Secret`
+    #[serde(default)]
+    pub trusted_cidrs: Vec<IpNet>, // Plages d'adresses clientes autorisées à forcer le routage
+}
+
+/// Retire les en-têtes de forçage d'une requête, pour qu'ils (et le secret) n'atteignent pas les backends.
+pub fn strip_headers(headers: &mut HeaderMap) {
+    for name in [POOL_OVERRIDE_HEADER, BACKEND_OVERRIDE_HEADER, OVERRIDE_SECRET_HEADER] {
+        headers.remove(name);
+    }
+}
+
+/// Résultat de l'examen des en-têtes de forçage d'une requête.
+pub enum Override {
+    /// Aucun forçage demandé (ou demandé sans autorisation) : le routage normal s'applique.
+    None,
+    /// Le forçage désigne ce serveur backend sain.
+    Backend(Arc<BackendServer>),
+    /// Le forçage est autorisé mais ne peut pas être satisfait (cible inconnue ou en mauvaise santé).
+    Unavailable(String),
+}
+
+/// Forçage du routage par en-têtes.
+///
+/// `X-Backend-Pool: canary` force le pool, `X-Debug-Backend: 192.168.1.2:8081` force le serveur
+/// backend. Le forçage n'est pris en compte que si la requête porte le secret partagé ou provient
+/// d'une plage d'adresses de confiance, et il contourne le load balancer sans ignorer l'état de santé.
+pub struct OverrideRouting {
+    config: OverrideConfig, // Conditions d'autorisation des forçages
+    backends: Vec<Arc<BackendServer>>, // Serveurs backend pouvant être désignés directement
+}
+
+impl OverrideRouting {
+    /// Crée une nouvelle instance de `OverrideRouting` sur les serveurs backend connus.
+    pub fn new(config: OverrideConfig, backends: Vec<Arc<BackendServer>>) -> Self {
+        Self { config, backends }
+    }
+
+    /// Indique si la requête est autorisée à forcer le routage.
+    fn is_authorized(&self, headers: &HeaderMap, client_ip: IpAddr) -> bool {
+        let secret_matches = match &self.config.secret {
+            Some(secret) => headers
+                .get(OVERRIDE_SECRET_HEADER)
+                // Comparaison en temps constant : la durée ne révèle pas le préfixe correct du secret
+                .is_some_and(|value| verify_slices_are_equal(value.as_bytes(), secret.as_bytes()).is_ok()),
+            None => false,
+        };
+        secret_matches || self.config.trusted_cidrs.iter().any(|cidr| cidr.contains(&client_ip))
+    }
+
+    /// Examine les en-têtes de forçage d'une requête.
+    pub fn resolve(&self, headers: &HeaderMap, client_ip: IpAddr, router: Option<&Router>) -> Override {
+        let backend_override = headers.get(BACKEND_OVERRIDE_HEADER).and_then(|value| value.to_str().ok());
+        let pool_override = headers.get(POOL_OVERRIDE_HEADER).and_then(|value| value.to_str().ok());
+        if backend_override.is_none() && pool_override.is_none() {
+            return Override::None;
+        }
+        if !self.is_authorized(headers, client_ip) {
+            log::warn!("Ignoring unauthorized routing override from {}", client_ip);
+            return Override::None;
+        }
+
+        // Le forçage du serveur backend est prioritaire sur celui du pool
+        if let Some(target) = backend_override {
+            let backend = router
+                .into_iter()
+                .flat_map(|router| router.pools())
+                .flat_map(|pool| pool.backends().iter())
+                .chain(self.backends.iter())
+                .find(|b| format!("{}:{}", b.address(), b.port()) == target.trim());
+            return match backend {
+                Some(backend) if backend.is_healthy() => {
+                    log::info!("Routing override from {}: backend {}", client_ip, target);
+                    Override::Backend(backend.clone())
+                }
+                Some(_) => Override::Unavailable(format!("Backend {} is unhealthy", target)),
+                None => Override::Unavailable(format!("Unknown backend {}", target)),
+            };
+        }
+
+        let name = pool_override.unwrap_or_default().trim();
+        let pool = match router.and_then(|router| router.pool(name)) {
+            Some(pool) => pool,
+            None => return Override::Unavailable(format!("Unknown pool {}", name)),
+        };
+        // La stratégie du pool ne retourne un backend hors service que si aucun n'est sain
+        let backend = pool.select_backend();
+        if !backend.is_healthy() {
+            return Override::Unavailable(format!("No healthy backend in pool {}", name));
+        }
+        log::info!("Routing override from {}: pool {} -> {}:{}", client_ip, name, backend.address(), backend.port());
+        Override::Backend(backend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{Body, Request, Response, StatusCode};
+    use crate::config::{BackendConfig, PoolConfig};
+    use crate::load_balancer::RoundRobinLoadBalancer;
+    use crate::request_handler::RequestHandler;
+    use crate::test_support::spawn_server;
+
+    fn routing() -> (OverrideRouting, Router) {
+        let config = OverrideConfig {
+            secret: Some("s3cret".to_string()),
+            trusted_cidrs: vec!["10.0.0.0/8".parse().unwrap()],
+        };
+        let backends = vec![
+            BackendServer::new("192.168.1.1".to_string(), 8080),
+            BackendServer::new("192.168.1.2".to_string(), 8081),
+        ];
+        let pools = vec![PoolConfig {
+            name: "canary".to_string(),
+            backends: vec![BackendConfig { address: "192.168.1.10".to_string(), port: 8080, ..Default::default() }],
+            ..Default::default()
+        }];
+        let router = Router::new(&pools, &[], "round_robin", None).unwrap();
+        (OverrideRouting::new(config, backends), router)
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    /// Teste le forçage d'un serveur backend avec le secret partagé.
+    #[test]
+    fn test_backend_override_with_secret() {
+        let (routing, router) = routing();
+        let headers = headers(&[(BACKEND_OVERRIDE_HEADER, "192.168.1.2:8081"), (OVERRIDE_SECRET_HEADER, "s3cret")]);
+        match routing.resolve(&headers, "203.0.113.7".parse().unwrap(), Some(&router)) {
+            Override::Backend(backend) => assert_eq!(backend.port(), 8081),
+            _ => panic!("expected a backend override"),
+        }
+    }
+
+    /// Teste le forçage d'un pool depuis une plage d'adresses de confiance.
+    #[test]
+    fn test_pool_override_from_trusted_cidr() {
+        let (routing, router) = routing();
+        let headers = headers(&[(POOL_OVERRIDE_HEADER, "canary")]);
+        match routing.resolve(&headers, "10.1.2.3".parse().unwrap(), Some(&router)) {
+            Override::Backend(backend) => assert_eq!(backend.address(), "192.168.1.10"),
+            _ => panic!("expected a pool override"),
+        }
+    }
+
+    /// Teste que le forçage d'un pool en moindre connexion évite son backend hors service.
+    #[test]
+    fn test_pool_override_skips_unhealthy() {
+        let (routing, _) = routing();
+        let pools = vec![PoolConfig {
+            name: "canary".to_string(),
+            backends: vec![
+                BackendConfig { address: "192.168.1.10".to_string(), port: 8080, ..Default::default() },
+                BackendConfig { address: "192.168.1.11".to_string(), port: 8080, ..Default::default() },
+            ],
+            ..Default::default()
+        }];
+        let router = Router::new(&pools, &[], "least_connections", None).unwrap();
+        let pool = router.pool("canary").unwrap();
+        pool.backends()[0].set_healthy(false);
+        let _connection = pool.backends()[1].track_connection(); // Le backend sain est le plus chargé
+
+        let headers = headers(&[(POOL_OVERRIDE_HEADER, "canary")]);
+        match routing.resolve(&headers, "10.1.2.3".parse().unwrap(), Some(&router)) {
+            Override::Backend(backend) => assert_eq!(backend.address(), "192.168.1.11"),
+            _ => panic!("expected a pool override"),
+        }
+        pool.backends()[1].set_healthy(false);
+        assert!(matches!(routing.resolve(&headers, "10.1.2.3".parse().unwrap(), Some(&router)), Override::Unavailable(_)));
+    }
+
+    /// Teste que les en-têtes de forçage, dont le secret, ne sont pas transmis au backend.
+    #[tokio::test]
+    async fn test_headers_not_forwarded() {
+        let port = spawn_server(|req: Request<Body>| async move {
+            let leaked: Vec<&str> = [POOL_OVERRIDE_HEADER, BACKEND_OVERRIDE_HEADER, OVERRIDE_SECRET_HEADER]
+                .into_iter()
+                .filter(|name| req.headers().contains_key(*name))
+                .collect();
+            Response::new(Body::from(leaked.join(",")))
+        })
+        .await;
+        let backend = BackendServer::new("127.0.0.1".to_string(), port);
+        let config = OverrideConfig { secret: Some("s3cret".to_string()), trusted_cidrs: Vec::new() };
+        let handler = RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(vec![backend.clone()])))
+            .with_override_routing(OverrideRouting::new(config, vec![backend]));
+        let request = Request::builder()
+            .header(BACKEND_OVERRIDE_HEADER, format!("127.0.0.1:{}", port))
+            .header(POOL_OVERRIDE_HEADER, "canary")
+            .header(OVERRIDE_SECRET_HEADER, "s3cret")
+            .body(Body::empty())
+            .unwrap();
+        let response = handler.handle_request(request, "203.0.113.7:1000".parse().unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "");
+    }
+
+    /// Teste que les forçages non autorisés sont ignorés et que l'état de santé est respecté.
+    #[test]
+    fn test_unauthorized_and_unhealthy() {
+        let (routing, router) = routing();
+        let forced = headers(&[(BACKEND_OVERRIDE_HEADER, "192.168.1.1:8080")]);
+        assert!(matches!(routing.resolve(&forced, "203.0.113.7".parse().unwrap(), Some(&router)), Override::None));
+
+        routing.backends[0].set_healthy(false);
+        assert!(matches!(routing.resolve(&forced, "10.0.0.1".parse().unwrap(), Some(&router)), Override::Unavailable(_)));
+    }
+}
@@ -0,0 +1,421 @@
+use std::net::SocketAddr; // Importation de SocketAddr pour les adresses d'écoute et des clients
+use std::sync::Arc; // Importation de Arc pour le partage sécurisé entre threads
+use std::time::Duration; // Importation de Duration pour les délais de lecture du ClientHello et de santé
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration du mode passthrough
+use tokio::io::{AsyncReadExt, AsyncWriteExt}; // Importation de la lecture et du rejeu du ClientHello
+use tokio::net::{TcpListener, TcpStream}; // Importation des sockets TCP
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::health::HealthChecker; // Importation des vérifications de santé par connexion TCP
+use crate::router::{Pool, Router}; // Importation des pools de backends
+use crate::tcp::{pipe, DEFAULT_CONNECT_TIMEOUT, DEFAULT_IDLE_TIMEOUT}; // Importation du relais TCP
+
+/// Taille maximale d'un ClientHello examiné, enregistrements TLS et leurs en-têtes compris
+/// (les partages de clés post-quantiques le répartissent souvent sur plusieurs enregistrements).
+const MAX_CLIENT_HELLO_LEN: usize = 64 * 1024;
+/// Délai maximal pour recevoir le ClientHello complet.
+const CLIENT_HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Association d'un nom de serveur (SNI) à un pool.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SniRouteConfig {
+    pub server_name: String, // Nom exact ou joker (`*.example.com`)
+    pub pool: String,        // Pool destinataire des connexions
+}
+
+/// Configuration d'un listener en mode TLS passthrough.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PassthroughConfig {
+    pub listen: SocketAddr, // Adresse d'écoute
+    #[serde(default)]
+    pub routes: Vec<SniRouteConfig>, // Pools choisis selon le SNI
+    #[serde(default)]
+    pub default_pool: Option<String>, // Pool des connexions sans SNI ou sans correspondance
+}
+
+/// Résultat de l'examen du début d'un flux TLS.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ClientHello {
+    /// Le ClientHello n'est pas encore complet.
+    Incomplete,
+    /// ClientHello complet, avec le nom de serveur annoncé s'il y en a un.
+    Complete(Option<String>),
+    /// Les données ne forment pas un ClientHello TLS.
+    Invalid,
+}
+
+/// Lecteur d'octets borné utilisé pour décoder le ClientHello.
+struct Reader<'a> {
+    data: &'a [u8], // Octets restant à lire
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<usize> {
+        self.take(1).map(|b| b[0] as usize)
+    }
+
+    fn u16(&mut self) -> Option<usize> {
+        self.take(2).map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+    }
+
+    fn u24(&mut self) -> Option<usize> {
+        self.take(3).map(|b| ((b[0] as usize) << 16) | ((b[1] as usize) << 8) | b[2] as usize)
+    }
+
+    /// Lit un bloc préfixé par sa longueur sur `len_bytes` octets.
+    fn block(&mut self, len_bytes: usize) -> Option<Reader<'a>> {
+        let len = match len_bytes {
+            1 => self.u8()?,
+            2 => self.u16()?,
+            _ => self.u24()?,
+        };
+        self.take(len).map(|data| Reader { data })
+    }
+}
+
+/// Examine le début d'un flux TLS et en extrait le nom de serveur (SNI) sans rien déchiffrer.
+///
+/// Le ClientHello peut être fragmenté sur plusieurs enregistrements TLS : leurs contenus sont
+/// réassemblés jusqu'à obtenir le message complet.
+pub fn parse_client_hello(data: &[u8]) -> ClientHello {
+    let mut handshake = Vec::new();
+    let mut records = data;
+    loop {
+        // En-tête d'enregistrement TLS : type (22 = handshake), version, longueur
+        if records.len() < 5 {
+            return ClientHello::Incomplete;
+        }
+        if records[0] != 22 || records[1] != 3 {
+            return ClientHello::Invalid;
+        }
+        let record_len = u16::from_be_bytes([records[3], records[4]]) as usize;
+        if records.len() < 5 + record_len {
+            return ClientHello::Incomplete;
+        }
+        handshake.extend_from_slice(&records[5..5 + record_len]);
+        records = &records[5 + record_len..];
+
+        // En-tête du message : type puis longueur sur 3 octets
+        if handshake.len() >= 4 {
+            let message_len = 4 + (((handshake[1] as usize) << 16) | ((handshake[2] as usize) << 8) | handshake[3] as usize);
+            if handshake.len() >= message_len {
+                let mut message = Reader { data: &handshake[..message_len] };
+                return match parse_handshake(&mut message) {
+                    Some(server_name) => ClientHello::Complete(server_name),
+                    None => ClientHello::Invalid,
+                };
+            }
+        }
+    }
+}
+
+/// Décode le message ClientHello et retourne le nom de serveur annoncé.
+fn parse_handshake(message: &mut Reader) -> Option<Option<String>> {
+    if message.u8()? != 1 {
+        return None; // Le premier message doit être un ClientHello
+    }
+    let mut hello = message.block(3)?;
+    hello.take(2 + 32)?; // Version et aléa du client
+    hello.block(1)?; // Identifiant de session
+    hello.block(2)?; // Suites de chiffrement
+    hello.block(1)?; // Méthodes de compression
+    if hello.data.is_empty() {
+        return Some(None); // Aucune extension
+    }
+
+    let mut extensions = hello.block(2)?;
+    while !extensions.data.is_empty() {
+        let kind = extensions.u16()?;
+        let mut extension = extensions.block(2)?;
+        if kind != 0 {
+            continue; // Seule l'extension server_name nous intéresse
+        }
+        let mut names = extension.block(2)?;
+        while !names.data.is_empty() {
+            let name_type = names.u8()?;
+            let name = names.block(2)?;
+            if name_type == 0 {
+                return Some(std::str::from_utf8(name.data).ok().map(|n| n.to_ascii_lowercase()));
+            }
+        }
+    }
+    Some(None)
+}
+
+/// Listener en mode TLS passthrough.
+///
+/// Les connexions ne sont pas déchiffrées : le ClientHello est seulement examiné pour en
+/// extraire le SNI, qui désigne le pool ; le flux TCP brut est ensuite relayé vers un backend
+/// sain choisi par la stratégie de répartition du pool, le ClientHello lu lui étant rejoué.
+pub struct PassthroughListener {
+    routes: Vec<SniRouteConfig>, // Pools choisis selon le SNI
+    default_pool: Option<String>, // Pool des connexions sans correspondance
+    router: Arc<Router>, // Pools de backends
+}
+
+impl PassthroughListener {
+    /// Crée un listener passthrough ; échoue si un pool référencé n'existe pas.
+    pub fn new(config: &PassthroughConfig, router: Arc<Router>) -> Result<Self, AppError> {
+        let pools = config.routes.iter().map(|r| &r.pool).chain(config.default_pool.iter());
+        for pool in pools {
+            if router.pool(pool).is_none() {
+                return Err(AppError::ConfigError(format!("Passthrough references unknown pool {}", pool)));
+            }
+        }
+        Ok(Self { routes: config.routes.clone(), default_pool: config.default_pool.clone(), router })
+    }
+
+    /// Pool correspondant au nom de serveur annoncé.
+    pub fn pool_for(&self, server_name: Option<&str>) -> Option<&Pool> {
+        let route = server_name.and_then(|name| {
+            self.routes.iter().find(|r| r.server_name.eq_ignore_ascii_case(name)).or_else(|| {
+                let (_, parent) = name.split_once('.')?;
+                self.routes.iter().find(|r| r.server_name.eq_ignore_ascii_case(&format!("*.{}", parent)))
+            })
+        });
+        let pool = route.map(|r| &r.pool).or(self.default_pool.as_ref())?;
+        self.router.pool(pool)
+    }
+
+    /// Accepte les connexions sur `addr` et les relaie vers les backends.
+    pub async fn serve(self: Arc<Self>, addr: SocketAddr) -> Result<(), AppError> {
+        let listener = TcpListener::bind(addr).await?;
+        loop {
+            let (stream, client_addr) = listener.accept().await?;
+            let this = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = this.handle_connection(stream).await {
+                    log::debug!("Passthrough connection from {} failed: {}", client_addr, e);
+                }
+            });
+        }
+    }
+
+    /// Lance en tâche de fond les vérifications de santé par connexion TCP des backends des pools servis.
+    pub fn watch_health(self: Arc<Self>, interval: Duration) {
+        let mut pools: Vec<&str> = self.routes.iter().map(|r| r.pool.as_str()).chain(self.default_pool.as_deref()).collect();
+        pools.sort_unstable();
+        pools.dedup();
+        let backends: Vec<_> = pools
+            .into_iter()
+            .filter_map(|name| self.router.pool(name))
+            .flat_map(|pool| pool.backends().iter().cloned())
+            .collect();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                for backend in &backends {
+                    backend.set_healthy(HealthChecker::check_tcp(backend.clone(), DEFAULT_CONNECT_TIMEOUT).await);
+                }
+            }
+        });
+    }
+
+    /// Examine le ClientHello d'une connexion puis la relaie vers un backend du pool choisi.
+    async fn handle_connection(&self, mut client: TcpStream) -> Result<(), AppError> {
+        let (server_name, client_hello) = tokio::time::timeout(CLIENT_HELLO_TIMEOUT, read_client_hello(&mut client))
+            .await
+            .map_err(|_| AppError::NetworkError("Timed out waiting for ClientHello".to_string()))??;
+        let pool = self
+            .pool_for(server_name.as_deref())
+            .ok_or_else(|| AppError::ConfigError(format!("No pool for server name {:?}", server_name)))?;
+
+        // Le ClientHello déjà lu est rejoué au backend avant le relais du reste du flux
+        let mut upstream = connect_backend(pool).await?;
+        upstream.write_all(&client_hello).await?;
+        pipe(client, upstream, DEFAULT_IDLE_TIMEOUT).await?;
+        Ok(())
+    }
+}
+
+/// Ouvre une connexion vers un backend sain du pool, en essayant les suivants en cas d'échec.
+async fn connect_backend(pool: &Pool) -> Result<TcpStream, AppError> {
+    for _ in 0..pool.backends().len() {
+        // La stratégie ne retourne un backend hors service que si aucun n'est sain
+        let backend = pool.select_backend();
+        if !backend.is_healthy() {
+            break;
+        }
+        let connect = TcpStream::connect((backend.address(), backend.port()));
+        match tokio::time::timeout(DEFAULT_CONNECT_TIMEOUT, connect).await {
+            Ok(Ok(stream)) => return Ok(stream),
+            Ok(Err(e)) => log::warn!("Backend {}:{} refused connection: {}", backend.address(), backend.port(), e),
+            Err(_) => log::warn!("Connection to backend {}:{} timed out", backend.address(), backend.port()),
+        }
+        backend.set_healthy(false); // Retiré jusqu'à la prochaine vérification de santé réussie
+    }
+    Err(AppError::BackendServerError(format!("No healthy backend in pool {} accepted the connection", pool.name())))
+}
+
+/// Lit les premiers octets d'une connexion jusqu'à obtenir le ClientHello complet.
+///
+/// Retourne le nom de serveur annoncé et les octets lus, à rejouer au backend.
+async fn read_client_hello(stream: &mut TcpStream) -> Result<(Option<String>, Vec<u8>), AppError> {
+    let mut buf = Vec::with_capacity(4096);
+    loop {
+        if buf.len() >= MAX_CLIENT_HELLO_LEN {
+            return Err(AppError::NetworkError("ClientHello too large".to_string()));
+        }
+        if stream.read_buf(&mut buf).await? == 0 {
+            return Err(AppError::NetworkError("Connection closed before the ClientHello".to_string()));
+        }
+        match parse_client_hello(&buf) {
+            ClientHello::Complete(server_name) => return Ok((server_name, buf)),
+            ClientHello::Invalid => return Err(AppError::NetworkError("Not a TLS ClientHello".to_string())),
+            ClientHello::Incomplete => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BackendConfig, PoolConfig};
+    use crate::tls::tests::self_signed;
+    use rustls::pki_types::ServerName;
+    use rustls::{ClientConfig, ClientConnection, RootCertStore};
+
+    /// Produit le ClientHello qu'enverrait un client rustls pour le nom donné.
+    fn client_hello(server_name: &str) -> Vec<u8> {
+        let config = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(RootCertStore::empty())
+            .with_no_client_auth();
+        let name = ServerName::try_from(server_name.to_string()).unwrap();
+        let mut connection = ClientConnection::new(Arc::new(config), name).unwrap();
+        let mut bytes = Vec::new();
+        connection.write_tls(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Teste l'extraction du SNI d'un ClientHello, complet ou tronqué.
+    #[test]
+    fn test_parse_client_hello() {
+        let hello = client_hello("DB.example.com");
+        assert_eq!(parse_client_hello(&hello), ClientHello::Complete(Some("db.example.com".to_string())));
+        assert_eq!(parse_client_hello(&hello[..hello.len() / 2]), ClientHello::Incomplete);
+        assert_eq!(parse_client_hello(b"GET / HTTP/1.1\r\n"), ClientHello::Invalid);
+
+        // Une adresse IP n'est pas annoncée par SNI
+        assert_eq!(parse_client_hello(&client_hello("127.0.0.1")), ClientHello::Complete(None));
+
+        // Le même ClientHello fragmenté sur trois enregistrements
+        let message = &hello[5..];
+        let mut fragmented = Vec::new();
+        for fragment in [&message[..40], &message[40..100], &message[100..]] {
+            fragmented.extend_from_slice(&[22, 3, 1]);
+            fragmented.extend_from_slice(&(fragment.len() as u16).to_be_bytes());
+            fragmented.extend_from_slice(fragment);
+        }
+        assert_eq!(parse_client_hello(&fragmented[..60]), ClientHello::Incomplete);
+        assert_eq!(parse_client_hello(&fragmented), ClientHello::Complete(Some("db.example.com".to_string())));
+    }
+
+    /// Teste le relais d'une connexion TLS de bout en bout vers le pool choisi par SNI.
+    #[tokio::test]
+    async fn test_passthrough_routes_by_sni() {
+        // Backend TLS qui termine lui-même la connexion
+        let (certificate, der) = self_signed("passthrough", &["db.example.com"]);
+        let tls = crate::tls::TlsConfig {
+            certificates: vec![certificate],
+            cipher_suites: Vec::new(),
+            protocol_versions: Vec::new(),
+            alpn_protocols: Vec::new(),
+            reload_interval_secs: 30,
+        };
+        let (server_config, _) = crate::tls::build_server_config(&tls).unwrap();
+        let backend = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_port = backend.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let acceptor = tokio_rustls::TlsAcceptor::from(server_config);
+            loop {
+                let (stream, _) = backend.accept().await.unwrap();
+                let _ = acceptor.accept(stream).await;
+            }
+        });
+
+        // Le premier backend, en mauvaise santé, est évité
+        let dead_port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        let pools = vec![PoolConfig {
+            name: "databases".to_string(),
+            backends: vec![
+                BackendConfig { address: "127.0.0.1".to_string(), port: dead_port, ..Default::default() },
+                BackendConfig { address: "127.0.0.1".to_string(), port: backend_port, ..Default::default() },
+            ],
+            ..Default::default()
+        }];
+        let router = Arc::new(Router::new(&pools, &[], "round_robin", None).unwrap());
+        router.pool("databases").unwrap().backends()[0].set_healthy(false);
+        let config = PassthroughConfig {
+            listen: "127.0.0.1:0".parse().unwrap(),
+            routes: vec![SniRouteConfig { server_name: "*.example.com".to_string(), pool: "databases".to_string() }],
+            default_pool: None,
+        };
+        let passthrough = Arc::new(PassthroughListener::new(&config, router).unwrap());
+        assert!(passthrough.pool_for(Some("other.test")).is_none());
+
+        // Le client voit le certificat du backend : la connexion n'a pas été déchiffrée en chemin
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            passthrough.handle_connection(stream).await.unwrap();
+        });
+        let stream = TcpStream::connect(addr).await.unwrap();
+        assert_eq!(handshake_over(stream, &der, "db.example.com").await, der);
+    }
+
+    /// Effectue une poignée de main TLS cliente sur un socket existant.
+    async fn handshake_over(
+        stream: TcpStream,
+        trusted: &rustls::pki_types::CertificateDer<'static>,
+        server_name: &str,
+    ) -> rustls::pki_types::CertificateDer<'static> {
+        let mut roots = RootCertStore::empty();
+        roots.add(trusted.clone()).unwrap();
+        let config = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let stream = tokio_rustls::TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from(server_name.to_string()).unwrap(), stream)
+            .await
+            .unwrap();
+        stream.get_ref().1.peer_certificates().unwrap()[0].clone()
+    }
+
+    /// Teste qu'un backend arrêté listé en premier, retiré dès son échec, n'est plus choisi
+    /// par un pool en moindre connexion.
+    #[tokio::test]
+    async fn test_least_connections_skips_dead_backend() {
+        let dead_port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        let live = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let live_port = live.local_addr().unwrap().port();
+        let pools = vec![PoolConfig {
+            name: "databases".to_string(),
+            backends: vec![
+                BackendConfig { address: "127.0.0.1".to_string(), port: dead_port, ..Default::default() },
+                BackendConfig { address: "127.0.0.1".to_string(), port: live_port, ..Default::default() },
+            ],
+            ..Default::default()
+        }];
+        let router = Router::new(&pools, &[], "least_connections", None).unwrap();
+        let pool = router.pool("databases").unwrap();
+
+        for _ in 0..3 {
+            let stream = connect_backend(pool).await.unwrap();
+            assert_eq!(stream.peer_addr().unwrap().port(), live_port);
+        }
+        assert!(!pool.backends()[0].is_healthy());
+    }
+}
@@ -0,0 +1,251 @@
+use std::sync::Arc; // Importation de Arc pour le partage sécurisé entre threads
+use rand::Rng; // Importation de Rng pour le tirage aléatoire du tier
+use crate::backend::BackendServer; // Importation de la structure BackendServer depuis le module backend
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::load_balancer::{build_load_balancer, LoadBalancer, SharedLoadBalancer}; // Importation des stratégies de répartition
+use crate::slow_start::SlowStart; // Importation de la configuration de montée en charge progressive
+
+/// Marge appliquée par défaut à la santé d'un tier (72 % de backends sains suffisent).
+pub const DEFAULT_OVERPROVISIONING_FACTOR: f64 = 1.4;
+
+/// Un tier de priorité : un groupe de backends servi par sa propre instance de la stratégie configurée.
+struct PriorityTier {
+    backends: Vec<Arc<BackendServer>>, // Serveurs backend appartenant au tier
+    load_balancer: SharedLoadBalancer, // Stratégie de répartition appliquée à l'intérieur du tier
+}
+
+impl PriorityTier {
+    /// Pourcentage de santé du tier (0.0 à 100.0), majoré par le facteur de surprovisionnement.
+    fn health(&self, overprovisioning_factor: f64) -> f64 {
+        let healthy = self.backends.iter().filter(|b| b.is_healthy()).count();
+        let ratio = healthy as f64 / self.backends.len() as f64;
+        (ratio * overprovisioning_factor * 100.0).min(100.0)
+    }
+}
+
+/// Répartition de charge par tiers de priorité avec backends de secours.
+///
+/// Tout le trafic va au tier 0 tant qu'il est suffisamment sain. Lorsque sa santé baisse,
+/// la part de trafic qu'il ne peut plus absorber déborde proportionnellement sur le tier
+/// suivant, et ainsi de suite. Les backends de secours forment les derniers tiers et ne
+/// reçoivent du trafic que lorsque tous les tiers principaux sont dégradés.
+pub struct PriorityLoadBalancer {
+    tiers: Vec<PriorityTier>, // Tiers ordonnés du plus prioritaire au moins prioritaire
+    overprovisioning_factor: f64, // Marge appliquée à la santé d'un tier (1.4 : 72 % de backends sains suffisent)
+}
+
+impl PriorityLoadBalancer {
+    /// Crée une nouvelle instance de `PriorityLoadBalancer`.
+    ///
+    /// Les backends sont regroupés par niveau de priorité, les backends de secours après tous les autres,
+    /// et chaque tier reçoit sa propre instance de la stratégie `strategy`.
+    pub fn new(
+        backends: Vec<Arc<BackendServer>>,
+        strategy: &str,
+        slow_start: Option<SlowStart>,
+        overprovisioning_factor: f64,
+    ) -> Result<Self, AppError> {
+        // Trie les backends : principaux avant secours, puis par niveau de priorité croissant
+        let mut backends = backends;
+        backends.sort_by_key(|b| (b.is_backup(), b.priority()));
+
+        let mut groups: Vec<Vec<Arc<BackendServer>>> = Vec::new();
+        for backend in backends {
+            match groups.last_mut() {
+                Some(group) if group[0].is_backup() == backend.is_backup() && group[0].priority() == backend.priority() => {
+                    group.push(backend)
+                }
+                _ => groups.push(vec![backend]), // Nouveau tier
+            }
+        }
+        if groups.is_empty() {
+            return Err(AppError::ConfigError("No backend servers specified".to_string()));
+        }
+
+        let tiers = groups
+            .into_iter()
+            .map(|backends| {
+                let load_balancer = build_load_balancer(strategy, backends.clone(), slow_start)?;
+                Ok(PriorityTier { backends, load_balancer })
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+
+        Ok(Self { tiers, overprovisioning_factor })
+    }
+
+    /// Part du trafic (somme égale à 1.0) attribuée à chaque tier selon la santé actuelle des backends.
+    pub fn load_distribution(&self) -> Vec<f64> {
+        let health: Vec<f64> = self.tiers.iter().map(|t| t.health(self.overprovisioning_factor)).collect();
+        let total_health: f64 = health.iter().sum();
+
+        if total_health <= 0.0 {
+            // Aucun backend sain : tout le trafic reste sur le tier principal
+            let mut distribution = vec![0.0; self.tiers.len()];
+            distribution[0] = 1.0;
+            return distribution;
+        }
+        if total_health < 100.0 {
+            // Santé globale insuffisante : répartition proportionnelle à la santé de chaque tier
+            return health.iter().map(|h| h / total_health).collect();
+        }
+
+        // Chaque tier absorbe ce qu'il peut, le reste déborde sur le tier suivant
+        let mut remaining = 100.0;
+        health
+            .iter()
+            .map(|h| {
+                let share = h.min(remaining);
+                remaining -= share;
+                share / 100.0
+            })
+            .collect()
+    }
+}
+
+impl LoadBalancer for PriorityLoadBalancer {
+    /// Sélectionne un tier selon la répartition courante, puis un serveur backend sain dans ce tier.
+    ///
+    /// Une référence partagée au serveur backend sélectionné.
+    fn select_backend(&self) -> Arc<BackendServer> {
+        let distribution = self.load_distribution();
+        let draw: f64 = rand::thread_rng().gen();
+
+        // Trouve le tier correspondant au tirage dans la répartition cumulée
+        let mut cumulative = 0.0;
+        let index = distribution
+            .iter()
+            .position(|share| {
+                cumulative += share;
+                draw < cumulative
+            })
+            .unwrap_or_else(|| distribution.iter().rposition(|share| *share > 0.0).unwrap_or(0));
+        // La stratégie du tier évite d'elle-même ses backends hors service
+        self.tiers[index].load_balancer.select_backend()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use hyper::{Body, Request, Response, StatusCode};
+    use tokio::net::TcpListener;
+    use crate::config::BackendConfig;
+    use crate::health::HealthChecker;
+    use crate::request_handler::RequestHandler;
+    use crate::test_support::spawn_server;
+
+    fn backend(port: u16, priority: u32, backup: bool) -> Arc<BackendServer> {
+        BackendServer::from_config(&BackendConfig {
+            address: "127.0.0.1".to_string(),
+            port,
+            priority,
+            backup,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    /// Teste que tout le trafic reste sur le tier 0 tant qu'il est sain.
+    #[test]
+    fn test_primary_tier_only() {
+        let backends = vec![backend(8080, 0, false), backend(8081, 0, false), backend(9090, 0, true)];
+        let lb = PriorityLoadBalancer::new(backends, "round_robin", None, 1.4).unwrap();
+
+        assert_eq!(lb.load_distribution(), vec![1.0, 0.0]);
+        for _ in 0..10 {
+            assert_ne!(lb.select_backend().port(), 9090);
+        }
+    }
+
+    /// Teste le débordement proportionnel vers le tier suivant lorsque le tier 0 se dégrade.
+    #[test]
+    fn test_spill_over() {
+        let primary = vec![backend(8080, 0, false), backend(8081, 0, false)];
+        let secondary = backend(8082, 1, false);
+        let mut backends = primary.clone();
+        backends.push(secondary);
+        let lb = PriorityLoadBalancer::new(backends, "round_robin", None, 1.4).unwrap();
+
+        primary[0].set_healthy(false); // Tier 0 à 50 % de santé, soit 70 % du trafic
+        let distribution = lb.load_distribution();
+        assert!((distribution[0] - 0.7).abs() < 1e-9);
+        assert!((distribution[1] - 0.3).abs() < 1e-9);
+
+        primary[1].set_healthy(false); // Tier 0 complètement indisponible
+        assert_eq!(lb.load_distribution(), vec![0.0, 1.0]);
+        assert_eq!(lb.select_backend().port(), 8082);
+    }
+
+    /// Teste le basculement au sein d'un tier et vers le suivant avec la stratégie de moindre connexion,
+    /// qui préférerait sinon toujours le backend hors service (aucune connexion active).
+    #[test]
+    fn test_least_connections_failover() {
+        let primary = vec![backend(8080, 0, false), backend(8081, 0, false)];
+        let secondary = backend(8082, 1, false);
+        let mut backends = primary.clone();
+        backends.push(secondary.clone());
+        let lb = PriorityLoadBalancer::new(backends, "least_connections", None, 1.0).unwrap();
+
+        primary[0].set_healthy(false);
+        let _connection = primary[1].track_connection(); // Le backend sain est plus chargé que le backend hors service
+        for _ in 0..20 {
+            assert_ne!(lb.select_backend().port(), 8080);
+        }
+
+        primary[1].set_healthy(false);
+        for _ in 0..5 {
+            assert_eq!(lb.select_backend().port(), 8082);
+        }
+    }
+
+    /// Teste que les backends de secours ne reçoivent du trafic qu'en dernier recours.
+    #[test]
+    fn test_backup_after_all_priorities() {
+        let primary = backend(8080, 0, false);
+        let secondary = backend(8081, 1, false);
+        let backup = backend(9090, 0, true);
+        let lb = PriorityLoadBalancer::new(
+            vec![backup.clone(), secondary.clone(), primary.clone()],
+            "round_robin",
+            None,
+            1.4,
+        )
+        .unwrap();
+
+        primary.set_healthy(false);
+        assert_eq!(lb.select_backend().port(), 8081);
+        secondary.set_healthy(false);
+        assert_eq!(lb.select_backend().port(), 9090);
+    }
+
+    /// Teste de bout en bout le basculement vers le tier 1 lorsque la vérification de santé
+    /// détecte que le backend du tier 0 est injoignable.
+    #[tokio::test]
+    async fn test_failover_from_unreachable_tier() {
+        let unreachable = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        let secondary = spawn_server(|_| async { Response::new(Body::from("secondary")) }).await;
+        let backends = vec![backend(unreachable, 0, false), backend(secondary, 1, false)];
+        let lb = PriorityLoadBalancer::new(backends.clone(), "round_robin", None, 1.4).unwrap();
+        let handler = RequestHandler::new(Arc::new(lb));
+        let client: std::net::SocketAddr = "127.0.0.1:1000".parse().unwrap();
+
+        // Tant que le tier 0 est réputé sain, les requêtes y vont et échouent
+        let response = handler.handle_request(Request::new(Body::empty()), client).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+
+        HealthChecker::watch(backends.clone(), Duration::from_millis(20));
+        for _ in 0..100 {
+            if !backends[0].is_healthy() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(!backends[0].is_healthy());
+        for _ in 0..3 {
+            let response = handler.handle_request(Request::new(Body::empty()), client).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "secondary");
+        }
+    }
+}
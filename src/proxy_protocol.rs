@@ -0,0 +1,211 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr}; // Importation des types d'adresses
+use serde::Deserialize; // Importation de Deserialize pour lire la version émise vers les backends
+use tokio::io::{AsyncRead, AsyncReadExt}; // Importation de la lecture asynchrone
+use crate::error::AppError; // Importation des erreurs de l'application
+
+/// Signature d'un en-tête PROXY protocol v2.
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+/// Longueur maximale d'un en-tête PROXY protocol v1, CRLF compris.
+const V1_MAX_LEN: usize = 107;
+
+/// Version du PROXY protocol émise vers les backends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyProtocolVersion {
+    V1, // Format texte
+    V2, // Format binaire
+}
+
+/// Adresses d'origine d'une connexion transmises par le PROXY protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProxyAddresses {
+    pub source: SocketAddr, // Adresse du client d'origine
+    pub destination: SocketAddr, // Adresse à laquelle le client s'est connecté
+}
+
+/// Lit l'en-tête PROXY protocol (v1 ou v2) en tête d'une connexion.
+///
+/// Seul l'en-tête est consommé : le flux est ensuite prêt pour le protocole transporté.
+/// Retourne `None` pour les connexions sans adresse d'origine (`LOCAL`, `UNKNOWN`, familles non IP).
+pub async fn read_header<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Option<ProxyAddresses>, AppError> {
+    // 12 octets suffisent à distinguer les deux versions, l'en-tête v1 le plus court en faisant 15
+    let mut prefix = [0; 12];
+    stream.read_exact(&mut prefix).await?;
+    if prefix == V2_SIGNATURE {
+        let mut header = [0; 4];
+        stream.read_exact(&mut header).await?;
+        let len = u16::from_be_bytes([header[2], header[3]]) as usize;
+        let mut payload = vec![0; len];
+        stream.read_exact(&mut payload).await?;
+        return parse_v2(header[0], header[1], &payload);
+    }
+    if !prefix.starts_with(b"PROXY ") {
+        return Err(invalid("missing PROXY protocol header"));
+    }
+
+    // En-tête v1 : lu octet par octet pour ne rien consommer au-delà du CRLF
+    let mut line = prefix.to_vec();
+    while !line.ends_with(b"\r\n") {
+        if line.len() >= V1_MAX_LEN {
+            return Err(invalid("PROXY protocol v1 header too long"));
+        }
+        line.push(stream.read_u8().await?);
+    }
+    parse_v1(&line[..line.len() - 2])
+}
+
+/// Décode la ligne d'un en-tête v1 (`PROXY TCP4 203.0.113.7 10.0.0.1 51234 443`), sans le CRLF.
+fn parse_v1(line: &[u8]) -> Result<Option<ProxyAddresses>, AppError> {
+    let line = std::str::from_utf8(line).map_err(|_| invalid("PROXY protocol v1 header is not ASCII"))?;
+    let fields: Vec<&str> = line.split(' ').collect();
+    match fields.as_slice() {
+        ["PROXY", "UNKNOWN", ..] => Ok(None),
+        ["PROXY", "TCP4" | "TCP6", source, destination, source_port, destination_port] => {
+            let address = |ip: &str, port: &str| -> Result<SocketAddr, AppError> {
+                let ip: IpAddr = ip.parse().map_err(|_| invalid("invalid address in PROXY protocol v1 header"))?;
+                let port: u16 = port.parse().map_err(|_| invalid("invalid port in PROXY protocol v1 header"))?;
+                Ok(SocketAddr::new(ip, port))
+            };
+            Ok(Some(ProxyAddresses {
+                source: address(source, source_port)?,
+                destination: address(destination, destination_port)?,
+            }))
+        }
+        _ => Err(invalid("malformed PROXY protocol v1 header")),
+    }
+}
+
+/// Décode la charge d'un en-tête v2 à partir de ses octets de version/commande et de famille.
+fn parse_v2(version_command: u8, family: u8, payload: &[u8]) -> Result<Option<ProxyAddresses>, AppError> {
+    if version_command >> 4 != 2 {
+        return Err(invalid("unsupported PROXY protocol version"));
+    }
+    match version_command & 0x0f {
+        0 => return Ok(None), // LOCAL : connexion du répartiteur lui-même (vérification de santé)
+        1 => {}
+        _ => return Err(invalid("unsupported PROXY protocol command")),
+    }
+    // Les TLV éventuels suivent les adresses et sont ignorés
+    match family >> 4 {
+        1 if payload.len() >= 12 => {
+            let ip = |at: usize| IpAddr::V4(Ipv4Addr::new(payload[at], payload[at + 1], payload[at + 2], payload[at + 3]));
+            let port = |at: usize| u16::from_be_bytes([payload[at], payload[at + 1]]);
+            Ok(Some(ProxyAddresses {
+                source: SocketAddr::new(ip(0), port(8)),
+                destination: SocketAddr::new(ip(4), port(10)),
+            }))
+        }
+        2 if payload.len() >= 36 => {
+            let ip = |at: usize| {
+                let octets: [u8; 16] = payload[at..at + 16].try_into().expect("16 bytes");
+                IpAddr::V6(Ipv6Addr::from(octets))
+            };
+            let port = |at: usize| u16::from_be_bytes([payload[at], payload[at + 1]]);
+            Ok(Some(ProxyAddresses {
+                source: SocketAddr::new(ip(0), port(32)),
+                destination: SocketAddr::new(ip(16), port(34)),
+            }))
+        }
+        1 | 2 => Err(invalid("truncated PROXY protocol v2 addresses")),
+        _ => Ok(None), // AF_UNSPEC ou AF_UNIX : pas d'adresse IP d'origine
+    }
+}
+
+/// Encode un en-tête PROXY protocol annonçant les adresses d'origine au backend.
+pub fn encode_header(version: ProxyProtocolVersion, addresses: ProxyAddresses) -> Vec<u8> {
+    // Les deux adresses doivent être de la même famille : IPv4 convertie en IPv6 au besoin
+    let (source, destination) = match (addresses.source, addresses.destination) {
+        (SocketAddr::V4(_), SocketAddr::V4(_)) | (SocketAddr::V6(_), SocketAddr::V6(_)) => (addresses.source, addresses.destination),
+        (source, destination) => (to_v6(source), to_v6(destination)),
+    };
+    match version {
+        ProxyProtocolVersion::V1 => {
+            let family = if source.is_ipv4() { "TCP4" } else { "TCP6" };
+            format!(
+                "PROXY {} {} {} {} {}\r\n",
+                family,
+                source.ip(),
+                destination.ip(),
+                source.port(),
+                destination.port()
+            )
+            .into_bytes()
+        }
+        ProxyProtocolVersion::V2 => {
+            let mut header = V2_SIGNATURE.to_vec();
+            header.push(0x21); // Version 2, commande PROXY
+            let mut payload = Vec::new();
+            match (source.ip(), destination.ip()) {
+                (IpAddr::V4(src), IpAddr::V4(dst)) => {
+                    header.push(0x11); // AF_INET, STREAM
+                    payload.extend_from_slice(&src.octets());
+                    payload.extend_from_slice(&dst.octets());
+                }
+                (src, dst) => {
+                    header.push(0x21); // AF_INET6, STREAM
+                    payload.extend_from_slice(&ipv6(src).octets());
+                    payload.extend_from_slice(&ipv6(dst).octets());
+                }
+            }
+            payload.extend_from_slice(&source.port().to_be_bytes());
+            payload.extend_from_slice(&destination.port().to_be_bytes());
+            header.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+            header.extend_from_slice(&payload);
+            header
+        }
+    }
+}
+
+fn to_v6(addr: SocketAddr) -> SocketAddr {
+    SocketAddr::new(IpAddr::V6(ipv6(addr.ip())), addr.port())
+}
+
+fn ipv6(ip: IpAddr) -> Ipv6Addr {
+    match ip {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    }
+}
+
+fn invalid(message: &str) -> AppError {
+    AppError::NetworkError(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addresses(source: &str, destination: &str) -> ProxyAddresses {
+        ProxyAddresses { source: source.parse().unwrap(), destination: destination.parse().unwrap() }
+    }
+
+    /// Teste l'aller-retour des deux versions, en IPv4 et en IPv6, sans consommer les données suivantes.
+    #[tokio::test]
+    async fn test_round_trip() {
+        for version in [ProxyProtocolVersion::V1, ProxyProtocolVersion::V2] {
+            for expected in [addresses("203.0.113.7:51234", "10.0.0.1:443"), addresses("[2001:db8::7]:51234", "[2001:db8::1]:443")] {
+                let mut data = encode_header(version, expected);
+                data.extend_from_slice(b"GET / HTTP/1.1\r\n");
+                let mut stream = data.as_slice();
+                assert_eq!(read_header(&mut stream).await.unwrap(), Some(expected));
+                assert_eq!(stream, b"GET / HTTP/1.1\r\n");
+            }
+        }
+    }
+
+    /// Teste les en-têtes sans adresse d'origine et les en-têtes invalides.
+    #[tokio::test]
+    async fn test_local_and_invalid() {
+        let mut unknown: &[u8] = b"PROXY UNKNOWN\r\n";
+        assert_eq!(read_header(&mut unknown).await.unwrap(), None);
+
+        let mut local = V2_SIGNATURE.to_vec();
+        local.extend_from_slice(&[0x20, 0x00, 0x00, 0x00]);
+        assert_eq!(read_header(&mut local.as_slice()).await.unwrap(), None);
+
+        let mut missing: &[u8] = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        assert!(read_header(&mut missing).await.is_err());
+        let mut malformed: &[u8] = b"PROXY TCP4 not-an-ip 10.0.0.1 1 2\r\n";
+        assert!(read_header(&mut malformed).await.is_err());
+    }
+}
@@ -0,0 +1,321 @@
+use std::pin::Pin; // Importation de Pin pour activer les notifications avant de réessayer
+use std::sync::atomic::{AtomicUsize, Ordering}; // Importation du compteur des requêtes en attente
+use std::sync::Arc; // Importation de Arc pour le partage sécurisé entre threads
+use std::time::Duration; // Importation de Duration pour le délai d'attente
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration
+use tokio::sync::Mutex; // Importation du Mutex équitable de tokio, qui sert les attentes dans l'ordre
+use crate::backend::{BackendServer, ConnectionGuard}; // Importation des backends et du comptage des connexions
+
+/// Intervalle de nouvelle tentative, pour les backends dont les fins de connexion ne sont pas suivies.
+const RECHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Configuration de la file d'attente des requêtes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueueConfig {
+    #[serde(default = "default_max_size")]
+    pub max_size: usize, // Nombre maximal de requêtes en attente
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64, // Durée d'attente maximale d'une requête
+}
+
+fn default_max_size() -> usize {
+    100
+}
+
+fn default_timeout_ms() -> u64 {
+    1000
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self { max_size: default_max_size(), timeout_ms: default_timeout_ms() }
+    }
+}
+
+/// Raison du refus d'une requête par la file d'attente.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueRejection {
+    Full, // La file d'attente est pleine
+    Timeout, // Aucun backend ne s'est libéré à temps
+}
+
+impl QueueRejection {
+    /// Message retourné au client.
+    pub fn message(&self) -> &'static str {
+        match self {
+            QueueRejection::Full => "All backends are saturated and the request queue is full",
+            QueueRejection::Timeout => "Timed out waiting for a backend",
+        }
+    }
+}
+
+/// File d'attente FIFO bornée des requêtes arrivant lorsque tous les backends sont saturés.
+///
+/// La requête en tête de file retente la sélection à chaque fin de connexion vers l'un des
+/// backends candidats ; les suivantes attendent leur tour dans l'ordre d'arrivée.
+pub struct RequestQueue {
+    config: QueueConfig, // Taille et délai de la file
+    turn: Mutex<()>, // Tête de file ; le Mutex de tokio est servi dans l'ordre d'arrivée
+    waiting: AtomicUsize, // Requêtes en attente
+}
+
+impl RequestQueue {
+    /// Crée une file d'attente vide.
+    pub fn new(config: QueueConfig) -> Self {
+        Self { config, turn: Mutex::new(()), waiting: AtomicUsize::new(0) }
+    }
+
+    /// Nombre de requêtes en attente.
+    pub fn len(&self) -> usize {
+        self.waiting.load(Ordering::SeqCst)
+    }
+
+    /// Indique si aucune requête n'est en attente.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Attend qu'un backend se libère, puis retourne celui choisi par `acquire`.
+    ///
+    /// `acquire` sélectionne un backend et y réserve une connexion, ou retourne `None` si tous
+    /// sont saturés ; il est rappelé à chaque fin de connexion vers l'un des `backends`.
+    pub async fn wait<F>(&self, backends: &[Arc<BackendServer>], mut acquire: F) -> Result<(Arc<BackendServer>, ConnectionGuard), QueueRejection>
+    where
+        F: FnMut() -> Option<(Arc<BackendServer>, ConnectionGuard)>,
+    {
+        if self.waiting.fetch_add(1, Ordering::SeqCst) >= self.config.max_size {
+            self.waiting.fetch_sub(1, Ordering::SeqCst);
+            return Err(QueueRejection::Full);
+        }
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        let result = tokio::time::timeout(timeout, async {
+            let _turn = self.turn.lock().await;
+            loop {
+                // Les notifications sont activées avant la tentative pour ne manquer aucune libération
+                let mut released: Vec<Pin<Box<_>>> = backends.iter().map(|backend| Box::pin(backend.released())).collect();
+                for notified in &mut released {
+                    notified.as_mut().enable();
+                }
+                if let Some(acquired) = acquire() {
+                    return acquired;
+                }
+                let any_released = async {
+                    if released.is_empty() {
+                        std::future::pending::<()>().await;
+                    } else {
+                        futures::future::select_all(released).await;
+                    }
+                };
+                tokio::select! {
+                    _ = any_released => {}
+                    _ = tokio::time::sleep(RECHECK_INTERVAL) => {}
+                }
+            }
+        })
+        .await;
+        self.waiting.fetch_sub(1, Ordering::SeqCst);
+        result.map_err(|_| QueueRejection::Timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{Body, Request, Response, StatusCode};
+    use crate::config::{BackendConfig, PoolConfig, RouteConfig, SplitConfig};
+    use crate::load_balancer::{LoadBalancer, RoundRobinLoadBalancer};
+    use crate::request_handler::RequestHandler;
+    use crate::router::Router;
+    use crate::test_support::spawn_server;
+
+    fn backend(max_connections: usize) -> Arc<BackendServer> {
+        backend_on(8080, max_connections)
+    }
+
+    fn backend_on(port: u16, max_connections: usize) -> Arc<BackendServer> {
+        BackendServer::from_config(&BackendConfig {
+            address: "127.0.0.1".to_string(),
+            port,
+            max_connections: Some(max_connections),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn acquire(backend: &Arc<BackendServer>) -> Option<(Arc<BackendServer>, ConnectionGuard)> {
+        backend.try_track_connection().map(|guard| (backend.clone(), guard))
+    }
+
+    /// Teste le réveil des requêtes en attente dans l'ordre d'arrivée à la libération d'une connexion.
+    #[tokio::test]
+    async fn test_fifo_wakeup() {
+        let backend = backend(1);
+        let queue = Arc::new(RequestQueue::new(QueueConfig { max_size: 10, timeout_ms: 5000 }));
+        let held = backend.try_track_connection().unwrap();
+        assert!(backend.is_saturated());
+        assert!(backend.try_track_connection().is_none());
+
+        let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+        for id in 0..3 {
+            let (waiting, backend, order_tx) = (queue.clone(), backend.clone(), order_tx.clone());
+            tokio::spawn(async move {
+                let (_, guard) = waiting.wait(std::slice::from_ref(&backend), || acquire(&backend)).await.unwrap();
+                order_tx.send(id).unwrap();
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                drop(guard);
+            });
+            // Laisse chaque requête rejoindre la file avant la suivante
+            while queue.len() < id + 1 {
+                tokio::task::yield_now().await;
+            }
+        }
+        drop(held);
+        for expected in 0..3 {
+            assert_eq!(order_rx.recv().await, Some(expected));
+        }
+    }
+
+    /// Teste le refus des requêtes lorsque la file est pleine ou que le délai est dépassé.
+    #[tokio::test]
+    async fn test_full_and_timeout() {
+        let backend = backend(1);
+        let _held = backend.try_track_connection().unwrap();
+        let queue = Arc::new(RequestQueue::new(QueueConfig { max_size: 1, timeout_ms: 100 }));
+
+        let waiting = {
+            let (queue, backend) = (queue.clone(), backend.clone());
+            tokio::spawn(async move { queue.wait(std::slice::from_ref(&backend), || acquire(&backend)).await.map(|_| ()) })
+        };
+        while queue.is_empty() {
+            tokio::task::yield_now().await;
+        }
+        let full = queue.wait(std::slice::from_ref(&backend), || acquire(&backend)).await;
+        assert_eq!(full.err(), Some(QueueRejection::Full));
+        assert_eq!(waiting.await.unwrap(), Err(QueueRejection::Timeout));
+        assert!(queue.is_empty());
+    }
+
+    /// Backend répondant après 200 ms.
+    async fn slow_backend(max_connections: usize) -> Arc<BackendServer> {
+        let port = spawn_server(|_| async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Response::new(Body::from("ok"))
+        })
+        .await;
+        backend_on(port, max_connections)
+    }
+
+    /// Teste l'évitement des backends saturés, puis la mise en attente et le refus (503) des requêtes en trop.
+    #[tokio::test]
+    async fn test_saturated_backends() {
+        let backends = vec![slow_backend(1).await, slow_backend(1).await];
+        let handler = Arc::new(
+            RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(backends.clone())))
+                .with_backends(backends.clone())
+                .with_queue(QueueConfig { max_size: 1, timeout_ms: 1000 }),
+        );
+        let client: std::net::SocketAddr = "127.0.0.1:1".parse().unwrap();
+        let send = |handler: Arc<RequestHandler>| {
+            tokio::spawn(async move { handler.handle_request(Request::new(Body::empty()), client).await.unwrap().status() })
+        };
+
+        // Un backend saturé est évité tant que l'autre a de la capacité
+        let held = backends[0].try_track_connection().unwrap();
+        let load_balancer = RoundRobinLoadBalancer::new(backends.clone());
+        for _ in 0..2 {
+            assert_eq!(load_balancer.select_backend().port(), backends[1].port());
+        }
+        drop(held);
+
+        // Deux requêtes simultanées occupent les deux backends
+        let first = send(handler.clone());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let second = send(handler.clone());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(backends.iter().all(|backend| backend.active_connections() == 1));
+
+        // Tous saturés : une requête attend son tour, la suivante est refusée
+        let queued = send(handler.clone());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let rejected = send(handler.clone()).await.unwrap();
+        assert_eq!(rejected, StatusCode::SERVICE_UNAVAILABLE);
+        for response in [first, second, queued] {
+            assert_eq!(response.await.unwrap(), StatusCode::OK);
+        }
+    }
+
+    /// Teste qu'un pool saturé ne fait pas attendre les requêtes destinées à un autre pool.
+    #[tokio::test]
+    async fn test_queue_per_pool() {
+        let pool = |name: &str, port: u16| PoolConfig {
+            name: name.to_string(),
+            backends: vec![BackendConfig { address: "127.0.0.1".to_string(), port, max_connections: Some(1), ..Default::default() }],
+            ..Default::default()
+        };
+        let route = |name: &str| RouteConfig {
+            name: name.to_string(),
+            path_prefix: format!("/{}", name),
+            split: vec![SplitConfig { pool: name.to_string(), weight: 1 }],
+            ..Default::default()
+        };
+        let port = spawn_server(|_| async { Response::new(Body::from("ok")) }).await;
+        let router = Router::new(&[pool("busy", port), pool("free", port)], &[route("busy"), route("free")], "round_robin", None)
+            .unwrap()
+            .with_queue(QueueConfig { max_size: 10, timeout_ms: 2000 });
+        let held = router.pool("busy").unwrap().backends()[0].try_track_connection().unwrap();
+        let handler = Arc::new(RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(vec![backend_on(port, 10)]))).with_router(Arc::new(router)));
+        let client: std::net::SocketAddr = "127.0.0.1:1".parse().unwrap();
+        let request = |path: &str| Request::builder().uri(path).body(Body::empty()).unwrap();
+
+        let queued = {
+            let (handler, request) = (handler.clone(), request("/busy"));
+            tokio::spawn(async move { handler.handle_request(request, client).await.unwrap().status() })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let started = std::time::Instant::now();
+        let response = handler.handle_request(request("/free"), client).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(started.elapsed() < Duration::from_millis(500));
+
+        drop(held);
+        assert_eq!(queued.await.unwrap(), StatusCode::OK);
+    }
+
+    /// Teste que la connexion reste comptée jusqu'à la fin du corps de la réponse.
+    #[tokio::test]
+    async fn test_connection_held_until_body_end() {
+        let (release_tx, release_rx) = tokio::sync::watch::channel(false);
+        let port = spawn_server(move |_| {
+            let mut release_rx = release_rx.clone();
+            async move {
+                let (mut sender, body) = Body::channel();
+                tokio::spawn(async move {
+                    sender.send_data("first".into()).await.unwrap();
+                    let _ = release_rx.wait_for(|released| *released).await;
+                    sender.send_data("last".into()).await.unwrap();
+                });
+                Response::new(body)
+            }
+        })
+        .await;
+        let backend = backend_on(port, 1);
+        let handler = RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(vec![backend.clone()]))).with_backends(vec![backend.clone()]);
+        let client: std::net::SocketAddr = "127.0.0.1:1".parse().unwrap();
+
+        // Les en-têtes sont reçus mais le corps est encore en cours de transmission
+        let response = handler.handle_request(Request::new(Body::empty()), client).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(backend.active_connections(), 1);
+
+        release_tx.send(true).unwrap();
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "firstlast");
+        for _ in 0..100 {
+            if backend.active_connections() == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(backend.active_connections(), 0);
+    }
+}
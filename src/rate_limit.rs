@@ -0,0 +1,305 @@
+use std::collections::HashMap; // Importation de HashMap pour les seaux de chaque client
+use std::net::IpAddr; // Importation de IpAddr pour l'adresse du client
+use std::sync::{Arc, Mutex}; // Importation de Arc et Mutex pour partager les seaux et le magasin entre les requêtes
+use std::time::{Duration, Instant}; // Importation de Duration et Instant pour le remplissage des seaux
+use hyper::header::{HeaderMap, HeaderValue, RETRY_AFTER}; // Importation des en-têtes HTTP
+use hyper::{Body, Response, StatusCode}; // Importation des types de réponses HTTP
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration
+use crate::client::{ClientIdentity, Principal}; // Importation de l'identité des clients
+use crate::rate_limit_store::RateLimitStore; // Importation du magasin partagé des compteurs
+
+/// Nombre maximal de seaux suivis : au-delà, les seaux pleins puis les plus anciens sont oubliés.
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+/// Nombre de seaux conservés après une éviction, pour ne pas en refaire une à chaque nouveau client.
+const RETAINED_BUCKETS: usize = MAX_TRACKED_BUCKETS * 9 / 10;
+
+/// Clé sur laquelle porte une limite de débit.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "key", rename_all = "snake_case")]
+pub enum RateLimitKey {
+    ClientIp, // Une limite par adresse IP de client
+    Header { header: String }, // Une limite par valeur d'en-tête (clé d'API), par adresse IP en son absence
+    Route, // Une limite partagée par tous les clients de la route
+    Principal, // Une limite par utilisateur authentifié, par adresse IP à défaut
+}
+
+/// Limite de débit d'une route, appliquée par seau à jetons.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    #[serde(flatten)]
+    pub key: RateLimitKey, // Clé de la limite
+    pub requests_per_second: f64, // Débit soutenu autorisé
+    pub burst: u32, // Capacité du seau : requêtes acceptées d'un coup après une période calme
+}
+
+/// Décision d'une limite de débit pour une requête.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitDecision {
+    pub allowed: bool, // Requête acceptée
+    pub limit: u32, // Capacité du seau
+    pub remaining: u32, // Jetons restants après la requête
+    pub reset: Duration, // Délai jusqu'au remplissage complet du seau
+    pub retry_after: Duration, // Délai jusqu'au prochain jeton (nul si la requête est acceptée)
+}
+
+impl RateLimitDecision {
+    /// Ajoute les en-têtes `RateLimit-Limit`, `RateLimit-Remaining` et `RateLimit-Reset`.
+    pub fn apply_headers(&self, headers: &mut HeaderMap) {
+        headers.insert("ratelimit-limit", HeaderValue::from(self.limit));
+        headers.insert("ratelimit-remaining", HeaderValue::from(self.remaining));
+        headers.insert("ratelimit-reset", HeaderValue::from(ceil_secs(self.reset)));
+    }
+
+    /// Réponse 429 d'une requête refusée, avec `Retry-After` et les en-têtes `RateLimit-*`.
+    pub fn rejection(&self) -> Response<Body> {
+        let mut response = Response::new(Body::from("Too many requests"));
+        *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+        self.apply_headers(response.headers_mut());
+        response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(ceil_secs(self.retry_after).max(1)));
+        response
+    }
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+/// Seau à jetons d'un client.
+struct TokenBucket {
+    tokens: f64, // Jetons disponibles
+    updated: Instant, // Dernier remplissage
+}
+
+/// Limite de débit d'une route : un seau à jetons par identité de client.
+///
+/// Avec un magasin partagé, la limite est globale à toutes les instances du proxy ; les seaux
+/// locaux ne servent alors que lorsque le magasin est injoignable.
+pub struct RateLimiter {
+    config: RateLimitConfig, // Clé, débit et capacité
+    route: String, // Nom de la route, identité des limites partagées
+    index: usize, // Rang de la limite parmi celles de la route, pour nommer ses compteurs partagés
+    buckets: Mutex<HashMap<ClientIdentity, TokenBucket>>, // Seaux des clients
+    store: Option<Arc<RateLimitStore>>, // Magasin partagé des compteurs, le cas échéant
+}
+
+impl RateLimiter {
+    /// Crée la `index`-ième limite de débit de la route donnée.
+    pub fn new(config: RateLimitConfig, route: &str, index: usize) -> Self {
+        Self { config, route: route.to_string(), index, buckets: Mutex::new(HashMap::new()), store: None }
+    }
+
+    /// Partage les compteurs de la limite entre les instances via le magasin donné.
+    pub fn with_store(mut self, store: Arc<RateLimitStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Configuration de la limite.
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Magasin partagé des compteurs, le cas échéant.
+    pub fn store(&self) -> Option<&Arc<RateLimitStore>> {
+        self.store.as_ref()
+    }
+
+    /// Identité à laquelle s'applique la limite pour une requête.
+    pub fn identity(&self, headers: &HeaderMap, principal: Option<&Principal>, client_ip: IpAddr) -> ClientIdentity {
+        match &self.config.key {
+            RateLimitKey::ClientIp => ClientIdentity::Ip(client_ip),
+            RateLimitKey::Header { header } => match headers.get(header).and_then(|value| value.to_str().ok()) {
+                Some(key) => ClientIdentity::ApiKey(key.to_string()),
+                None => ClientIdentity::Ip(client_ip),
+            },
+            RateLimitKey::Route => ClientIdentity::Route(self.route.clone()),
+            RateLimitKey::Principal => match principal {
+                Some(principal) => ClientIdentity::Principal(principal.name.clone()),
+                None => ClientIdentity::Ip(client_ip),
+            },
+        }
+    }
+
+    /// Compte une requête de `identity` : dans le magasin partagé s'il répond, sinon dans le seau local.
+    pub async fn check(&self, identity: ClientIdentity) -> RateLimitDecision {
+        if let Some(store) = &self.store {
+            let key = format!("{}:{}:{}", self.route, self.index, identity);
+            if let Some(decision) = store.hit(&key, self.config.requests_per_second, self.config.burst).await {
+                return decision;
+            }
+        }
+        self.check_at(identity, Instant::now())
+    }
+
+    fn check_at(&self, identity: ClientIdentity, now: Instant) -> RateLimitDecision {
+        let (rate, burst) = (self.config.requests_per_second, self.config.burst as f64);
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_BUCKETS && !buckets.contains_key(&identity) {
+            evict(&mut buckets, now, rate, burst);
+        }
+        let bucket = buckets.entry(identity).or_insert(TokenBucket { tokens: burst, updated: now });
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate).min(burst);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let refill = |missing: f64| Duration::try_from_secs_f64(missing.max(0.0) / rate).unwrap_or(Duration::MAX);
+        RateLimitDecision {
+            allowed,
+            limit: self.config.burst,
+            remaining: bucket.tokens.floor() as u32,
+            reset: refill(burst - bucket.tokens),
+            retry_after: if allowed { Duration::ZERO } else { refill(1.0 - bucket.tokens) },
+        }
+    }
+}
+
+/// Ramène le nombre de seaux à `RETAINED_BUCKETS`.
+///
+/// Les seaux pleins, équivalents à des seaux absents, sont oubliés d'abord ; si cela ne suffit pas
+/// (clés d'API arbitraires choisies par les clients), les seaux remplis le moins récemment le sont aussi.
+fn evict(buckets: &mut HashMap<ClientIdentity, TokenBucket>, now: Instant, rate: f64, burst: f64) {
+    buckets.retain(|_, bucket| bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate < burst);
+    if buckets.len() > RETAINED_BUCKETS {
+        let mut updated: Vec<Instant> = buckets.values().map(|bucket| bucket.updated).collect();
+        let excess = updated.len() - RETAINED_BUCKETS;
+        let (_, cutoff, _) = updated.select_nth_unstable(excess - 1);
+        let cutoff = *cutoff;
+        buckets.retain(|_, bucket| bucket.updated > cutoff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::Request;
+    use crate::config::{BackendConfig, PoolConfig, RouteConfig, SplitConfig};
+    use crate::load_balancer::RoundRobinLoadBalancer;
+    use crate::request_handler::RequestHandler;
+    use crate::router::Router;
+    use crate::backend::BackendServer;
+    use crate::client::AuthMethod;
+
+    fn limiter(key: RateLimitKey, requests_per_second: f64, burst: u32) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig { key, requests_per_second, burst }, "api", 0)
+    }
+
+    /// Teste la rafale, le refus une fois le seau vide puis le remplissage au débit configuré.
+    #[test]
+    fn test_token_bucket() {
+        let limiter = limiter(RateLimitKey::ClientIp, 2.0, 3);
+        let client = ClientIdentity::Ip("203.0.113.7".parse().unwrap());
+        let start = Instant::now();
+        for remaining in [2, 1, 0] {
+            let decision = limiter.check_at(client.clone(), start);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, remaining);
+        }
+        let rejected = limiter.check_at(client.clone(), start);
+        assert!(!rejected.allowed);
+        assert_eq!(rejected.retry_after, Duration::from_millis(500));
+        assert_eq!(rejected.reset, Duration::from_millis(1500));
+
+        // Un autre client a son propre seau ; le premier retrouve un jeton après 500 ms
+        assert!(limiter.check_at(ClientIdentity::Ip("203.0.113.8".parse().unwrap()), start).allowed);
+        assert!(limiter.check_at(client, start + Duration::from_millis(500)).allowed);
+    }
+
+    /// Teste que le nombre de seaux reste borné face à des identités toujours nouvelles,
+    /// en oubliant les plus anciennes.
+    #[test]
+    fn test_bucket_cap() {
+        let limiter = limiter(RateLimitKey::Header { header: "X-Api-Key".to_string() }, 0.001, 5);
+        let start = Instant::now();
+        for i in 0..MAX_TRACKED_BUCKETS + 10 {
+            let now = start + Duration::from_millis(i as u64);
+            assert!(limiter.check_at(ClientIdentity::ApiKey(format!("key-{}", i)), now).allowed);
+        }
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(buckets.len() <= MAX_TRACKED_BUCKETS);
+        assert!(!buckets.contains_key(&ClientIdentity::ApiKey("key-0".to_string())));
+        assert!(buckets.contains_key(&ClientIdentity::ApiKey(format!("key-{}", MAX_TRACKED_BUCKETS + 9))));
+    }
+
+    /// Teste qu'un débit infime ne fait pas déborder le calcul des délais.
+    #[test]
+    fn test_tiny_rate() {
+        let limiter = limiter(RateLimitKey::ClientIp, 1e-300, 1);
+        let client = ClientIdentity::Ip("203.0.113.7".parse().unwrap());
+        assert!(limiter.check_at(client.clone(), Instant::now()).allowed);
+        let rejected = limiter.check_at(client, Instant::now());
+        assert!(!rejected.allowed);
+        assert_eq!(rejected.retry_after, Duration::MAX);
+    }
+
+    /// Teste le choix de l'identité selon la clé de la limite.
+    #[test]
+    fn test_identity() {
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        let by_header = limiter(RateLimitKey::Header { header: "X-Api-Key".to_string() }, 1.0, 1);
+        assert_eq!(by_header.identity(&headers, None, ip), ClientIdentity::Ip(ip));
+        headers.insert("x-api-key", HeaderValue::from_static("secret"));
+        assert_eq!(by_header.identity(&headers, None, ip), ClientIdentity::ApiKey("secret".to_string()));
+        let name = ClientIdentity::ApiKey("secret".to_string()).to_string();
+        assert!(name.starts_with("key:") && !name.contains("secret"));
+        assert_eq!(limiter(RateLimitKey::Route, 1.0, 1).identity(&headers, None, ip), ClientIdentity::Route("api".to_string()));
+        let by_principal = limiter(RateLimitKey::Principal, 1.0, 1);
+        let alice = Principal { name: "alice".to_string(), method: AuthMethod::Basic };
+        assert_eq!(by_principal.identity(&headers, Some(&alice), ip), ClientIdentity::Principal("alice".to_string()));
+        assert_eq!(by_principal.identity(&headers, None, ip), ClientIdentity::Ip(ip));
+
+        let config: RouteConfig = toml::from_str(
+            r#"
+            name = "api"
+            path_prefix = "/api"
+            split = [{ pool = "stable", weight = 100 }]
+            [[rate_limits]]
+            key = "header"
+            header = "X-Api-Key"
+            requests_per_second = 10.0
+            burst = 20
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.rate_limits[0].key, RateLimitKey::Header { header: "X-Api-Key".to_string() });
+    }
+
+    /// Teste la réponse 429 du gestionnaire de requêtes et les en-têtes `RateLimit-*`.
+    #[tokio::test]
+    async fn test_rejection_response() {
+        let pool = PoolConfig {
+            name: "stable".to_string(),
+            // Backend injoignable : seules les requêtes acceptées l'atteignent (502)
+            backends: vec![BackendConfig { address: "127.0.0.1".to_string(), port: 1, ..Default::default() }],
+            ..Default::default()
+        };
+        let route = RouteConfig {
+            name: "api".to_string(),
+            path_prefix: "/api".to_string(),
+            split: vec![SplitConfig { pool: "stable".to_string(), weight: 100 }],
+            rate_limits: vec![RateLimitConfig { key: RateLimitKey::ClientIp, requests_per_second: 0.5, burst: 1 }],
+            ..Default::default()
+        };
+        let router = Arc::new(Router::new(&[pool], &[route], "round_robin", None).unwrap());
+        let default = BackendServer::new("127.0.0.1".to_string(), 1);
+        let handler = RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(vec![default]))).with_router(router);
+        let client = "203.0.113.7:5000".parse().unwrap();
+        let request = || Request::get("http://example.com/api/users").body(Body::empty()).unwrap();
+
+        let accepted = handler.handle_request(request(), client).await.unwrap();
+        assert_eq!(accepted.status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(accepted.headers().get("ratelimit-remaining").unwrap(), "0");
+        let rejected = handler.handle_request(request(), client).await.unwrap();
+        assert_eq!(rejected.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(rejected.headers().get("retry-after").unwrap(), "2");
+        assert_eq!(rejected.headers().get("ratelimit-limit").unwrap(), "1");
+        assert_eq!(rejected.headers().get("ratelimit-reset").unwrap(), "2");
+
+        // Les chemins hors route ne sont pas limités
+        let other = handler.handle_request(Request::new(Body::empty()), client).await.unwrap();
+        assert_eq!(other.status(), StatusCode::BAD_GATEWAY);
+    }
+}
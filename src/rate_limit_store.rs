@@ -0,0 +1,480 @@
+use std::collections::HashMap; // Importation de HashMap pour les compteurs du magasin en mémoire
+use std::net::SocketAddr; // Importation de SocketAddr pour l'adresse du magasin
+use std::sync::Arc; // Importation de Arc pour partager le magasin en mémoire
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH}; // Importation des types de mesure du temps
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream}; // Importation des entrées/sorties asynchrones
+use tokio::net::{TcpListener, TcpStream}; // Importation des sockets TCP
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::rate_limit::RateLimitDecision; // Importation des décisions des limites de débit
+
+/// Configuration du magasin partagé des compteurs de limites de débit.
+///
+/// Le magasin parle le protocole de Redis (RESP) : un serveur Redis convient, de même que le
+/// magasin en mémoire lancé par l'une des instances (`serve = true`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitStoreConfig {
+    pub address: SocketAddr, // Adresse du magasin
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64, // Délai maximal d'une opération avant de revenir aux limites locales
+    #[serde(default = "default_retry_secs")]
+    pub retry_secs: u64, // Délai avant de réessayer le magasin après un échec
+    #[serde(default = "default_key_prefix")]
+    pub key_prefix: String, // Préfixe des clés des compteurs
+    #[serde(default = "default_pool_size")]
+    pub pool_size: usize, // Nombre maximal de connexions simultanées vers le magasin
+    #[serde(default)]
+    pub serve: bool, // Lance le magasin en mémoire sur `address` dans cette instance
+}
+
+fn default_timeout_ms() -> u64 {
+    50
+}
+
+fn default_retry_secs() -> u64 {
+    5
+}
+
+fn default_key_prefix() -> String {
+    "lb:ratelimit:".to_string()
+}
+
+fn default_pool_size() -> usize {
+    8
+}
+
+/// Taille maximale d'une chaîne RESP acceptée ; les clés des compteurs sont bien plus courtes.
+const MAX_BULK_LEN: usize = 4096;
+
+/// Nombre maximal d'arguments d'une commande reçue par le magasin en mémoire.
+const MAX_COMMAND_ARGS: i64 = 16;
+
+/// Intervalle de suppression des compteurs expirés du magasin en mémoire.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(10);
+
+impl RateLimitStoreConfig {
+    /// Configuration par défaut pour le magasin à l'adresse donnée.
+    pub fn new(address: SocketAddr) -> Self {
+        Self {
+            address,
+            timeout_ms: default_timeout_ms(),
+            retry_secs: default_retry_secs(),
+            key_prefix: default_key_prefix(),
+            pool_size: default_pool_size(),
+            serve: false,
+        }
+    }
+}
+
+/// Réponse RESP d'un serveur.
+#[derive(Debug, Clone, PartialEq)]
+enum Reply {
+    Simple(String), // +OK
+    Error(String), // -ERR ...
+    Integer(i64), // :1
+    Bulk(Option<Vec<u8>>), // $3 abc, ou $-1 (absente)
+}
+
+/// Magasin partagé des compteurs de limites de débit.
+///
+/// Chaque limite compte les requêtes d'un client par fenêtre fixe de `burst / requests_per_second`
+/// secondes (`INCR` puis `PEXPIRE`). Sur la durée, le débit moyen est celui du seau à jetons local,
+/// mais pas la forme des rafales : un client peut envoyer `burst` requêtes à la fin d'une fenêtre
+/// puis `burst` au début de la suivante, soit jusqu'à deux fois `burst` en un instant. Les en-têtes
+/// diffèrent aussi : `RateLimit-Remaining` compte les requêtes restantes dans la fenêtre et
+/// `RateLimit-Reset` annonce sa fin, là où le seau local compte ses jetons et le délai pour le remplir.
+/// Lorsque le magasin ne répond pas, les limites reviennent aux seaux locaux de chaque instance
+/// jusqu'à une nouvelle tentative `retry_secs` plus tard.
+///
+/// Les requêtes se partagent jusqu'à `pool_size` connexions ; une connexion n'est réutilisée
+/// qu'après un échange complet, jamais après une erreur ou un délai dépassé.
+pub struct RateLimitStore {
+    config: RateLimitStoreConfig, // Adresse, délais et préfixe
+    idle: std::sync::Mutex<Vec<BufStream<TcpStream>>>, // Connexions libres vers le magasin
+    permits: tokio::sync::Semaphore, // Borne le nombre de connexions ouvertes simultanément
+    unavailable_until: std::sync::Mutex<Option<Instant>>, // Fin de la période de repli sur les limites locales
+}
+
+impl RateLimitStore {
+    /// Crée le client du magasin ; les connexions sont ouvertes à la demande.
+    pub fn new(config: RateLimitStoreConfig) -> Self {
+        let permits = tokio::sync::Semaphore::new(config.pool_size.max(1));
+        Self { config, idle: std::sync::Mutex::new(Vec::new()), permits, unavailable_until: std::sync::Mutex::new(None) }
+    }
+
+    /// Indique si le magasin est utilisé (il n'a pas échoué récemment).
+    pub fn is_available(&self) -> bool {
+        self.unavailable_until.lock().unwrap().is_none_or(|until| Instant::now() >= until)
+    }
+
+    /// Compte une requête dans la fenêtre courante de la limite `key`.
+    ///
+    /// Retourne `None` si le magasin est indisponible : l'appelant applique alors sa limite locale.
+    pub async fn hit(&self, key: &str, requests_per_second: f64, burst: u32) -> Option<RateLimitDecision> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        self.hit_at(key, requests_per_second, burst, now).await
+    }
+
+    /// Compte une requête reçue à l'instant `now` (millisecondes depuis l'époque Unix).
+    async fn hit_at(&self, key: &str, requests_per_second: f64, burst: u32, now: u64) -> Option<RateLimitDecision> {
+        if !self.is_available() || requests_per_second <= 0.0 {
+            return None;
+        }
+        let window = ((burst as f64 / requests_per_second) * 1000.0).ceil().max(1.0) as u64;
+        let key = format!("{}{}:{}:{}", self.config.key_prefix, key, window, now / window);
+        let reset = Duration::from_millis(window - now % window);
+
+        let count = match self.increment(&key, window).await {
+            Ok(count) => count,
+            Err(e) => return self.unavailable(&e.to_string()),
+        };
+        let allowed = count <= burst as i64;
+        Some(RateLimitDecision {
+            allowed,
+            limit: burst,
+            remaining: (burst as i64 - count).max(0) as u32,
+            reset,
+            retry_after: if allowed { Duration::ZERO } else { reset },
+        })
+    }
+
+    /// Incrémente le compteur de la fenêtre et fixe son expiration, en un seul aller-retour.
+    ///
+    /// L'attente d'une connexion libre compte dans le délai `timeout_ms`. En cas d'erreur ou de
+    /// délai dépassé, la connexion est abandonnée : des réponses non lues ne peuvent pas être
+    /// prises par une requête suivante pour les siennes.
+    async fn increment(&self, key: &str, window_ms: u64) -> Result<i64, AppError> {
+        let exchange = async {
+            let _permit = self.permits.acquire().await.map_err(|e| AppError::NetworkError(e.to_string()))?;
+            let idle = self.idle.lock().unwrap().pop();
+            let mut stream = match idle {
+                Some(stream) => stream,
+                None => BufStream::new(TcpStream::connect(self.config.address).await?),
+            };
+            let mut commands = encode_command(&["INCR", key]);
+            commands.extend(encode_command(&["PEXPIRE", key, &window_ms.to_string()]));
+            stream.write_all(&commands).await?;
+            stream.flush().await?;
+            let count = match read_reply(&mut stream).await? {
+                Reply::Integer(count) => count,
+                reply => return Err(AppError::NetworkError(format!("unexpected INCR reply: {:?}", reply))),
+            };
+            read_reply(&mut stream).await?;
+            self.idle.lock().unwrap().push(stream); // Échange complet : la connexion peut resservir
+            Ok(count)
+        };
+        tokio::time::timeout(Duration::from_millis(self.config.timeout_ms), exchange)
+            .await
+            .unwrap_or_else(|_| Err(AppError::NetworkError("timed out".to_string())))
+    }
+
+    /// Passe en repli sur les limites locales pendant `retry_secs`.
+    fn unavailable(&self, reason: &str) -> Option<RateLimitDecision> {
+        let mut unavailable_until = self.unavailable_until.lock().unwrap();
+        if unavailable_until.is_none_or(|until| Instant::now() >= until) {
+            log::warn!("Rate limit store {} unavailable ({}), using local limits", self.config.address, reason);
+        }
+        *unavailable_until = Some(Instant::now() + Duration::from_secs(self.config.retry_secs));
+        None
+    }
+}
+
+/// Encode une commande RESP (tableau de chaînes).
+fn encode_command(args: &[&str]) -> Vec<u8> {
+    let mut command = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        command.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        command.extend_from_slice(arg.as_bytes());
+        command.extend_from_slice(b"\r\n");
+    }
+    command
+}
+
+/// Lit une ligne RESP, sans son CRLF.
+async fn read_line(stream: &mut BufStream<TcpStream>) -> Result<String, AppError> {
+    let mut line = String::new();
+    if stream.read_line(&mut line).await? == 0 {
+        return Err(AppError::NetworkError("connection closed".to_string()));
+    }
+    Ok(line.trim_end_matches("\r\n").to_string())
+}
+
+/// Lit la taille annoncée par une ligne RESP (`*2`, `$5`...).
+fn parse_len(line: &str) -> Result<i64, AppError> {
+    line.get(1..)
+        .and_then(|len| len.parse().ok())
+        .ok_or_else(|| AppError::NetworkError(format!("invalid RESP length: {}", line)))
+}
+
+/// Lit une chaîne RESP de la longueur donnée, suivie de son CRLF ; au-delà de `MAX_BULK_LEN`, la connexion est refusée.
+async fn read_bulk(stream: &mut BufStream<TcpStream>, len: usize) -> Result<Vec<u8>, AppError> {
+    if len > MAX_BULK_LEN {
+        return Err(AppError::NetworkError(format!("RESP string too long: {} bytes", len)));
+    }
+    let mut data = vec![0; len + 2];
+    stream.read_exact(&mut data).await?;
+    data.truncate(len);
+    Ok(data)
+}
+
+/// Lit une réponse RESP simple (les tableaux ne sont pas utilisés par les commandes du proxy).
+async fn read_reply(stream: &mut BufStream<TcpStream>) -> Result<Reply, AppError> {
+    let line = read_line(stream).await?;
+    match line.as_bytes().first() {
+        Some(b'+') => Ok(Reply::Simple(line[1..].to_string())),
+        Some(b'-') => Ok(Reply::Error(line[1..].to_string())),
+        Some(b':') => Ok(Reply::Integer(parse_len(&line)?)),
+        Some(b'$') => match parse_len(&line)? {
+            len if len < 0 => Ok(Reply::Bulk(None)),
+            len => Ok(Reply::Bulk(Some(read_bulk(stream, len as usize).await?))),
+        },
+        _ => Err(AppError::NetworkError(format!("unsupported RESP reply: {}", line))),
+    }
+}
+
+/// Encode une réponse RESP.
+fn encode_reply(reply: &Reply) -> Vec<u8> {
+    match reply {
+        Reply::Simple(value) => format!("+{}\r\n", value).into_bytes(),
+        Reply::Error(message) => format!("-{}\r\n", message).into_bytes(),
+        Reply::Integer(value) => format!(":{}\r\n", value).into_bytes(),
+        Reply::Bulk(None) => b"$-1\r\n".to_vec(),
+        Reply::Bulk(Some(data)) => [format!("${}\r\n", data.len()).as_bytes(), data, b"\r\n"].concat(),
+    }
+}
+
+/// Magasin en mémoire parlant le sous-ensemble de RESP utilisé par le proxy (`PING`, `GET`, `INCR`, `PEXPIRE`, `DEL`).
+///
+/// Il remplace Redis lorsqu'une des instances héberge les compteurs partagés.
+#[derive(Default)]
+pub struct MemoryStore {
+    entries: std::sync::Mutex<HashMap<String, (i64, Option<Instant>)>>, // Compteurs et leur expiration
+}
+
+impl MemoryStore {
+    /// Crée un magasin vide.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sert les clients du magasin sur `addr`.
+    pub async fn serve(self: Arc<Self>, addr: SocketAddr) -> Result<(), AppError> {
+        self.run(TcpListener::bind(addr).await?).await
+    }
+
+    /// Sert les clients du magasin sur un socket déjà ouvert.
+    pub async fn run(self: Arc<Self>, listener: TcpListener) -> Result<(), AppError> {
+        // Les compteurs expirés sont aussi ignorés à l'accès ; le balayage libère ceux qui ne sont plus lus
+        let store = Arc::downgrade(&self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(SWEEP_INTERVAL);
+            loop {
+                ticker.tick().await;
+                match store.upgrade() {
+                    Some(store) => store.sweep(Instant::now()),
+                    None => return,
+                }
+            }
+        });
+        loop {
+            let (stream, peer_addr) = listener.accept().await?;
+            let store = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = store.handle_connection(BufStream::new(stream)).await {
+                    log::debug!("Rate limit store connection from {} closed: {}", peer_addr, e);
+                }
+            });
+        }
+    }
+
+    async fn handle_connection(&self, mut stream: BufStream<TcpStream>) -> Result<(), AppError> {
+        loop {
+            // Chaque commande est un tableau de chaînes
+            let header = match read_line(&mut stream).await {
+                Ok(header) => header,
+                Err(_) => return Ok(()), // Client parti
+            };
+            if !header.starts_with('*') {
+                return Err(AppError::NetworkError(format!("expected a RESP array, got {}", header)));
+            }
+            let count = parse_len(&header)?;
+            if count > MAX_COMMAND_ARGS {
+                return Err(AppError::NetworkError(format!("too many RESP arguments: {}", count)));
+            }
+            let mut args = Vec::new();
+            for _ in 0..count.max(0) {
+                let line = read_line(&mut stream).await?;
+                let len = parse_len(&line)?.max(0) as usize;
+                args.push(String::from_utf8_lossy(&read_bulk(&mut stream, len).await?).into_owned());
+            }
+            let reply = self.execute(&args);
+            stream.write_all(&encode_reply(&reply)).await?;
+            stream.flush().await?;
+        }
+    }
+
+    /// Supprime les compteurs expirés.
+    fn sweep(&self, now: Instant) {
+        self.entries.lock().unwrap().retain(|_, (_, expires)| expires.is_none_or(|expires| expires > now));
+    }
+
+    /// Exécute une commande et retourne sa réponse.
+    fn execute(&self, args: &[String]) -> Reply {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        // Un compteur expiré est supprimé lorsqu'il est désigné par la commande
+        for key in args.iter().skip(1) {
+            if entries.get(key).is_some_and(|(_, expires)| expires.is_some_and(|expires| expires <= now)) {
+                entries.remove(key);
+            }
+        }
+        let command = args.first().map(|command| command.to_ascii_uppercase()).unwrap_or_default();
+        match (command.as_str(), &args[1.min(args.len())..]) {
+            ("PING", _) => Reply::Simple("PONG".to_string()),
+            ("GET", [key]) => Reply::Bulk(entries.get(key).map(|(value, _)| value.to_string().into_bytes())),
+            ("INCR", [key]) => {
+                let (value, _) = entries.entry(key.clone()).or_insert((0, None));
+                *value += 1;
+                Reply::Integer(*value)
+            }
+            ("PEXPIRE", [key, millis]) => match (entries.get_mut(key), millis.parse::<u64>()) {
+                (Some((_, expires)), Ok(millis)) => {
+                    *expires = Some(now + Duration::from_millis(millis));
+                    Reply::Integer(1)
+                }
+                (None, Ok(_)) => Reply::Integer(0),
+                (_, Err(_)) => Reply::Error("ERR value is not an integer or out of range".to_string()),
+            },
+            ("DEL", keys) => Reply::Integer(keys.iter().filter(|key| entries.remove(*key).is_some()).count() as i64),
+            _ => Reply::Error(format!("ERR unknown command or wrong number of arguments for '{}'", command)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::ClientIdentity;
+    use crate::rate_limit::{RateLimitConfig, RateLimitKey, RateLimiter};
+
+    async fn memory_store() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(Arc::new(MemoryStore::new()).run(listener));
+        addr
+    }
+
+    fn instance(store: SocketAddr) -> RateLimiter {
+        // Limite large en fenêtre (10 requêtes sur 100 s) pour ne pas changer de fenêtre pendant le test
+        let config = RateLimitConfig { key: RateLimitKey::ClientIp, requests_per_second: 0.1, burst: 10 };
+        RateLimiter::new(config, "api", 0).with_store(Arc::new(RateLimitStore::new(RateLimitStoreConfig::new(store))))
+    }
+
+    /// Teste le partage d'une limite entre trois instances via le magasin en mémoire.
+    #[tokio::test]
+    async fn test_global_limit() {
+        let store = memory_store().await;
+        let instances = [instance(store), instance(store), instance(store)];
+        let client = ClientIdentity::Ip("203.0.113.7".parse().unwrap());
+        let mut allowed = 0;
+        for round in 0..10 {
+            for instance in &instances {
+                let decision = instance.check(client.clone()).await;
+                allowed += usize::from(decision.allowed);
+                if round == 0 {
+                    assert_eq!(decision.limit, 10);
+                }
+            }
+        }
+        // Sans magasin partagé, chaque instance aurait accepté 10 requêtes
+        assert_eq!(allowed, 10);
+        assert!(instances.iter().all(|instance| instance.store().unwrap().is_available()));
+    }
+
+    /// Teste qu'une fenêtre fixe laisse passer jusqu'à deux rafales de part et d'autre de sa limite.
+    #[tokio::test]
+    async fn test_window_boundary() {
+        let store = RateLimitStore::new(RateLimitStoreConfig::new(memory_store().await));
+        // Fenêtres de 100 s : la première se termine à 100 000 ms
+        let mut allowed = 0;
+        for now in [99_999, 100_000] {
+            for _ in 0..11 {
+                let decision = store.hit_at("client", 0.1, 10, now).await.unwrap();
+                allowed += usize::from(decision.allowed);
+            }
+        }
+        assert_eq!(allowed, 20); // Deux fois `burst` en une milliseconde
+
+        // Remaining et Reset portent sur la fenêtre, pas sur un seau de jetons
+        let decision = store.hit_at("other", 0.1, 10, 99_999).await.unwrap();
+        assert_eq!(decision.remaining, 9);
+        assert_eq!(decision.reset, Duration::from_millis(1));
+    }
+
+    /// Teste le repli sur la limite locale lorsque le magasin est injoignable.
+    #[tokio::test]
+    async fn test_local_fallback() {
+        let unreachable = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let instance = instance(unreachable);
+        let client = ClientIdentity::Ip("203.0.113.7".parse().unwrap());
+        let allowed = futures::future::join_all((0..15).map(|_| instance.check(client.clone()))).await;
+        assert_eq!(allowed.iter().filter(|decision| decision.allowed).count(), 10);
+        assert!(!instance.store().unwrap().is_available());
+    }
+
+    /// Teste les commandes du magasin en mémoire.
+    #[test]
+    fn test_memory_store_commands() {
+        let store = MemoryStore::new();
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert_eq!(store.execute(&args(&["PING"])), Reply::Simple("PONG".to_string()));
+        assert_eq!(store.execute(&args(&["INCR", "a"])), Reply::Integer(1));
+        assert_eq!(store.execute(&args(&["incr", "a"])), Reply::Integer(2));
+        assert_eq!(store.execute(&args(&["GET", "a"])), Reply::Bulk(Some(b"2".to_vec())));
+        assert_eq!(store.execute(&args(&["PEXPIRE", "a", "0"])), Reply::Integer(1));
+        assert_eq!(store.execute(&args(&["GET", "a"])), Reply::Bulk(None));
+        assert!(matches!(store.execute(&args(&["INCR"])), Reply::Error(_)));
+
+        // Les compteurs expirés jamais relus sont supprimés par le balayage
+        store.execute(&args(&["INCR", "b"]));
+        store.execute(&args(&["PEXPIRE", "b", "0"]));
+        store.sweep(Instant::now());
+        assert!(store.entries.lock().unwrap().is_empty());
+    }
+
+    /// Teste le refus des commandes dont la taille annoncée dépasse les limites du magasin en mémoire.
+    #[tokio::test]
+    async fn test_memory_store_rejects_oversized_commands() {
+        let store = memory_store().await;
+        for command in [&b"*1\r\n$9999999999\r\n"[..], b"*100000\r\n"] {
+            let mut stream = TcpStream::connect(store).await.unwrap();
+            stream.write_all(command).await.unwrap();
+            let mut reply = Vec::new();
+            assert_eq!(stream.read_to_end(&mut reply).await.unwrap(), 0); // Connexion fermée sans allocation
+        }
+    }
+
+    /// Teste qu'une connexion dont les réponses arrivent après le délai n'est pas réutilisée.
+    #[tokio::test]
+    async fn test_timed_out_connection_is_discarded() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            // Première connexion : les réponses arrivent après le délai du client
+            let (mut slow, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 256];
+            let _ = slow.read(&mut buf).await;
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let _ = slow.write_all(b":1\r\n:1\r\n").await;
+            // Connexion suivante : le compteur partagé en est à 7
+            let (mut fast, _) = listener.accept().await.unwrap();
+            let _ = fast.read(&mut buf).await;
+            fast.write_all(b":7\r\n:1\r\n").await.unwrap();
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        });
+        let store = RateLimitStore::new(RateLimitStoreConfig { retry_secs: 0, ..RateLimitStoreConfig::new(addr) });
+        assert!(store.hit("client", 0.1, 10).await.is_none());
+        tokio::time::sleep(Duration::from_millis(100)).await; // Les réponses tardives sont arrivées
+        assert_eq!(store.hit("client", 0.1, 10).await.unwrap().remaining, 3);
+    }
+}
@@ -1,31 +1,476 @@
-use hyper::{Body, Response}; // Importation des types nécessaires de la bibliothèque hyper pour les réponses HTTP
-use crate::backend::BackendServer; // Importation de la structure BackendServer pour représenter les serveurs backend
+use std::sync::Arc; // Importation de Arc pour le partage sécurisé entre threads
+use std::sync::atomic::{AtomicBool, Ordering}; // Importation de l'indicateur de dépassement de la taille du corps
+use std::collections::HashMap; // Importation de HashMap pour lire les nouvelles répartitions
+use std::net::SocketAddr; // Importation de SocketAddr pour l'adresse du client
+use hyper::header::{HeaderValue, RETRY_AFTER, WWW_AUTHENTICATE}; // Importation des en-têtes des réponses de délestage
+use hyper::{Body, Method, Request, Response, StatusCode}; // Importation des types nécessaires de la bibliothèque hyper pour les requêtes et réponses HTTP
+use ring::constant_time::verify_slices_are_equal; // Importation de la comparaison en temps constant du jeton d'administration
+use crate::access::AccessList; // Importation des listes d'accès par adresse cliente
+use crate::auth_request::AuthRequestError; // Importation des refus de l'autorisation externe
+use crate::backend::{BackendServer, ConnectionGuard}; // Importation de la structure BackendServer et du comptage des connexions
+use crate::client::Principal; // Importation du client authentifié
+use crate::concurrency::{AdaptiveConcurrencyConfig, ConcurrencyLimiter, ConcurrencyPermit}; // Importation de la limite de concurrence adaptative
+use crate::grpc; // Importation de la correspondance des erreurs en statuts gRPC
+use crate::load_balancer::SharedLoadBalancer; // Importation du load balancer partagé
+use crate::limits::LimitsConfig; // Importation des limites de taille des requêtes
+use crate::queue::{QueueConfig, RequestQueue}; // Importation de la file d'attente des requêtes
+use crate::rate_limit::RateLimitDecision; // Importation des décisions des limites de débit
+use crate::override_routing::{self, Override, OverrideRouting}; // Importation du forçage de routage par en-têtes
+use crate::router::Router; // Importation du routeur vers les pools de backends
+use crate::shutdown::Shutdown; // Importation du signal d'arrêt
+use crate::tunnel; // Importation des tunnels de mise à niveau (WebSocket, h2c)
+use crate::upstream; // Importation de la transmission des requêtes aux backends
+
+/// Préfixe des chemins de l'interface d'administration.
+const ADMIN_PREFIX: &str = "/_lb/";
+/// Préfixe des chemins d'administration des routes.
+const ADMIN_ROUTES_PREFIX: &str = "/_lb/routes/";
+/// Chemin des statistiques des pools de connexions.
+const ADMIN_CONNECTIONS_PATH: &str = "/_lb/connections";
 
 /// Structure qui représente un gestionnaire de requêtes.
 pub struct RequestHandler {
-    backend: BackendServer, // Serveur backend associé à ce gestionnaire de requêtes
+    load_balancer: SharedLoadBalancer, // Load balancer utilisé pour les requêtes ne correspondant à aucune route
+    backends: Vec<Arc<BackendServer>>, // Backends du load balancer principal, pour les statistiques
+    router: Option<Arc<Router>>, // Routes réparties entre plusieurs pools, le cas échéant
+    admin_token: Option<String>, // Jeton de l'interface d'administration ; désactivée si absent
+    override_routing: Option<OverrideRouting>, // Forçage du routage par en-têtes, le cas échéant
+    shutdown: Shutdown, // Signal d'arrêt fermant les tunnels en cours
+    queue: RequestQueue, // File d'attente des requêtes lorsque tous les backends principaux sont saturés
+    concurrency_limiter: Option<Arc<ConcurrencyLimiter>>, // Limite de concurrence adaptative des backends principaux
+    limits: LimitsConfig, // Limites de taille des requêtes et délai de lecture des en-têtes
+    access_list: Option<Arc<AccessList>>, // Adresses clientes autorisées et refusées sur toutes les requêtes
 }
 
 impl RequestHandler {
-    /// Crée une nouvelle instance de RequestHandler avec un serveur backend spécifié.
+    /// Crée une nouvelle instance de RequestHandler avec le load balancer spécifié.
     ///
-    /// Une instance de RequestHandler initialisée avec le serveur backend fourni.
-    pub fn new(backend: BackendServer) -> Self {
-        Self { backend } // Initialise et retourne une nouvelle instance de RequestHandler
+    /// Une instance de RequestHandler initialisée avec le load balancer fourni.
+    pub fn new(load_balancer: SharedLoadBalancer) -> Self {
+        Self { load_balancer, backends: Vec::new(), router: None, admin_token: None, override_routing: None, shutdown: Shutdown::new(), queue: RequestQueue::new(QueueConfig::default()), concurrency_limiter: None, limits: LimitsConfig::default(), access_list: None } // Initialise et retourne une nouvelle instance de RequestHandler
+    }
+
+    /// Déclare les backends du load balancer principal, dont les statistiques sont exposées par l'administration.
+    pub fn with_backends(mut self, backends: Vec<Arc<BackendServer>>) -> Self {
+        self.backends = backends;
+        self
+    }
+
+    /// Ajoute un routeur répartissant certaines routes entre plusieurs pools.
+    pub fn with_router(mut self, router: Arc<Router>) -> Self {
+        self.router = Some(router);
+        self
+    }
+
+    /// Active l'interface d'administration, protégée par le jeton donné.
+    pub fn with_admin_token(mut self, token: String) -> Self {
+        self.admin_token = Some(token);
+        self
+    }
+
+    /// Autorise le forçage du routage par en-têtes (`X-Backend-Pool`, `X-Debug-Backend`).
+    pub fn with_override_routing(mut self, override_routing: OverrideRouting) -> Self {
+        self.override_routing = Some(override_routing);
+        self
+    }
+
+    /// Associe le signal d'arrêt du proxy, qui ferme les tunnels en cours et refuse les nouveaux.
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Configure la file d'attente des requêtes arrivant lorsque tous les backends principaux sont saturés ;
+    /// les pools du routeur ont leur propre file.
+    pub fn with_queue(mut self, config: QueueConfig) -> Self {
+        self.queue = RequestQueue::new(config);
+        self
+    }
+
+    /// Limite la concurrence vers les backends principaux d'après leur latence ; les pools du routeur ont leur propre limite.
+    pub fn with_adaptive_concurrency(mut self, config: AdaptiveConcurrencyConfig) -> Self {
+        self.concurrency_limiter = Some(Arc::new(ConcurrencyLimiter::new(config)));
+        self
+    }
+
+    /// Limite la taille des requêtes (corps, en-têtes, URI) et le délai de réception des en-têtes.
+    pub fn with_limits(mut self, limits: LimitsConfig) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Limites appliquées aux requêtes, également utilisées par les listeners.
+    pub fn limits(&self) -> &LimitsConfig {
+        &self.limits
+    }
+
+    /// Filtre toutes les requêtes d'après l'adresse du client ; les routes peuvent avoir leur propre liste.
+    pub fn with_access_list(mut self, access_list: Arc<AccessList>) -> Self {
+        self.access_list = Some(access_list);
+        self
     }
 
     /// Gère une requête HTTP et retourne une réponse.
-    
-    /// Une réponse HTTP avec un message indiquant le serveur backend utilisé pour le traitement.
-    pub async fn handle_request(&self) -> Result<Response<Body>, hyper::Error> {
-        // Crée le corps de la réponse en incluant les informations du serveur backend
-        let response_body = format!(
-            "Handling request with backend: {}:{}",
-            self.backend.address(), // Adresse du serveur backend
-            self.backend.port() // Port du serveur backend
-        );
-
-        // Crée une nouvelle réponse HTTP avec le corps de la réponse et la retourne
-        Ok(Response::new(Body::from(response_body)))
+    ///
+    /// La réponse du serveur backend sélectionné, ou une erreur 502 s'il ne répond pas.
+    ///
+    /// Les requêtes `Connection: Upgrade` (WebSocket, h2c) sont transmises avec leur demande de mise
+    /// à niveau ; si le backend l'accepte, les deux connexions sont reliées par un tunnel.
+    /// Les requêtes dépassant une limite de débit de leur route reçoivent un 429, celles dépassant
+    /// une limite de taille un 414, 431 ou 413, les clients refusés par une liste d'accès un 403 et
+    /// celles sans jeton valide sur une route authentifiée un 401.
+    pub async fn handle_request(&self, mut req: Request<Body>, client_addr: SocketAddr) -> Result<Response<Body>, hyper::Error> {
+        // Les requêtes trop grandes sont refusées avant tout traitement
+        if let Err((status, message)) = self.limits.check_head(&req) {
+            log::warn!("Request from {} rejected with {}: {}", client_addr, status, message);
+            return Ok(proxy_error(grpc::is_grpc(req.headers()), status, message));
+        }
+
+        // Les listes d'accès sont évaluées sur l'adresse réelle du client (PROXY protocol le cas échéant)
+        if !self.is_allowed(&req, client_addr) {
+            log::warn!("Access denied to {} on {}", client_addr, req.uri().path());
+            return Ok(proxy_error(grpc::is_grpc(req.headers()), StatusCode::FORBIDDEN, "Forbidden"));
+        }
+
+        // Les requêtes d'administration ne sont pas transmises aux backends
+        if self.admin_token.is_some() && req.uri().path().starts_with(ADMIN_PREFIX) {
+            return self.handle_admin(req).await;
+        }
+
+        // Les erreurs du proxy sont rapportées en statut gRPC aux clients gRPC
+        let grpc = grpc::is_grpc(req.headers());
+
+        // L'authentification de la route précède les limites de débit, qui peuvent porter sur le client authentifié
+        if let Some(response) = self.authenticate(&mut req, client_addr, grpc).await {
+            return Ok(response);
+        }
+
+        // Les limites de débit de la route s'appliquent avant le choix d'un backend
+        let rate_limit = match self.check_rate_limits(&req, client_addr).await {
+            Ok(decision) => decision,
+            Err(decision) => {
+                let principal = req.extensions().get::<Principal>().map(|principal| format!(" ({})", principal)).unwrap_or_default();
+                log::warn!("Rate limit exceeded by {}{} on {}", client_addr, principal, req.uri().path());
+                let mut response = decision.rejection();
+                if grpc {
+                    let headers = std::mem::take(response.headers_mut());
+                    response = grpc::error_response(StatusCode::TOO_MANY_REQUESTS, "Too many requests");
+                    response.headers_mut().extend(headers);
+                }
+                return Ok(response);
+            }
+        };
+        // L'autorisation externe de la route est demandée juste avant la transmission
+        let mut response = match self.authorize_externally(&mut req, client_addr, grpc).await {
+            Some(response) => response,
+            None => self.forward(req, client_addr, grpc).await?,
+        };
+        if let Some(decision) = rate_limit {
+            decision.apply_headers(response.headers_mut());
+        }
+        Ok(response)
+    }
+
+    /// Transmet une requête au backend choisi et retourne sa réponse, ou l'erreur du proxy.
+    async fn forward(&self, mut req: Request<Body>, client_addr: SocketAddr, grpc: bool) -> Result<Response<Body>, hyper::Error> {
+        // Un forçage autorisé contourne le choix du load balancer
+        let forced = match &self.override_routing {
+            Some(override_routing) => override_routing.resolve(req.headers(), client_addr.ip(), self.router.as_deref()),
+            None => Override::None,
+        };
+        override_routing::strip_headers(req.headers_mut());
+        let route = self.router.as_ref().and_then(|router| router.route_for(req.uri().path()));
+        if let Some(auth) = route.and_then(|route| route.auth()) {
+            auth.strip_credentials(req.headers_mut()); // Les backends ne reçoivent ni mot de passe ni clé d'API
+        }
+        let (backend, connection, permit) = match forced {
+            Override::Backend(backend) => {
+                let connection = backend.track_connection(); // Le backend forcé est utilisé même saturé
+                (backend, connection, None)
+            }
+            Override::Unavailable(reason) => {
+                log::warn!("Routing override from {} rejected: {}", client_addr, reason);
+                return Ok(proxy_error(grpc, StatusCode::SERVICE_UNAVAILABLE, &reason));
+            }
+            Override::None => match self.acquire_backend(&req, client_addr, grpc).await {
+                Ok(acquired) => acquired,
+                Err(response) => {
+                    log::warn!("Request from {} rejected with {}", client_addr, response.status());
+                    return Ok(response);
+                }
+            },
+        };
+
+        // Une demande de mise à niveau n'est pas acceptée pendant l'arrêt
+        let upgrade = tunnel::upgrade_protocol(req.headers());
+        if upgrade.is_some() && self.shutdown.is_triggered() {
+            return Ok(proxy_error(grpc, StatusCode::SERVICE_UNAVAILABLE, "Shutting down"));
+        }
+        let client_upgrade = upgrade.as_ref().map(|_| hyper::upgrade::on(&mut req));
+
+        // Transmet la requête au serveur backend sélectionné et retourne sa réponse
+        let mut req = upstream::prepare_request(req, client_addr);
+        if let Some(protocol) = upgrade {
+            tunnel::restore_upgrade_headers(req.headers_mut(), protocol);
+        }
+        let body_too_large = Arc::new(AtomicBool::new(false));
+        let req = self.limits.limit_body(req, body_too_large.clone());
+        // La connexion reste comptée jusqu'à la fin du corps de la réponse, ou du tunnel
+        let (result, connection) = match client_upgrade {
+            Some(_) => (upstream::send_request(&backend, req).await, Some(connection)),
+            None => (upstream::send_request_holding(&backend, req, connection).await, None),
+        };
+        let body_too_large = body_too_large.load(Ordering::SeqCst);
+        if let Some(permit) = permit {
+            // La latence jusqu'aux en-têtes de la réponse ajuste la limite ; un échec du backend la réduit
+            let dropped = result.as_ref().map_or(!body_too_large, |response| {
+                matches!(response.status(), StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT)
+            });
+            permit.record(dropped);
+        }
+        match result {
+            Ok(mut response) => {
+                if let (Some(client_upgrade), Some(connection), StatusCode::SWITCHING_PROTOCOLS) = (client_upgrade, connection, response.status()) {
+                    // Le tunnel reste compté comme connexion active du backend jusqu'à sa fermeture
+                    let backend_upgrade = hyper::upgrade::on(&mut response);
+                    tokio::spawn(tunnel::splice(client_upgrade, backend_upgrade, connection, self.shutdown.clone()));
+                }
+                Ok(response)
+            }
+            Err(_) if body_too_large => {
+                log::warn!("Request body from {} exceeds {:?} bytes", client_addr, self.limits.max_body_bytes);
+                Ok(proxy_error(grpc, StatusCode::PAYLOAD_TOO_LARGE, "Payload too large"))
+            }
+            Err(e) => {
+                log::error!("Backend {}:{} failed: {}", backend.address(), backend.port(), e);
+                Ok(proxy_error(grpc, StatusCode::BAD_GATEWAY, "Bad gateway"))
+            }
+        }
+    }
+
+    /// Indique si les listes d'accès globale et de la route acceptent le client.
+    fn is_allowed(&self, req: &Request<Body>, client_addr: SocketAddr) -> bool {
+        let ip = client_addr.ip();
+        if self.access_list.as_ref().is_some_and(|list| !list.allows(ip)) {
+            return false;
+        }
+        let route = self.router.as_ref().and_then(|router| router.route_for(req.uri().path()));
+        route.and_then(|route| route.access_list()).is_none_or(|list| list.allows(ip))
+    }
+
+    /// Authentifie une requête si sa route l'exige et enregistre le client authentifié dans ses extensions.
+    ///
+    /// Retourne la réponse 401 à renvoyer au client si ses identifiants sont absents ou refusés.
+    async fn authenticate(&self, req: &mut Request<Body>, client_addr: SocketAddr, grpc: bool) -> Option<Response<Body>> {
+        let route = self.router.as_ref().and_then(|router| router.route_for(req.uri().path()));
+        let auth = route.and_then(|route| route.auth())?;
+        match auth.authenticate(req.headers_mut()).await {
+            Ok(Some(principal)) => {
+                log::debug!("Request from {} on {} authenticated as {}", client_addr, req.uri().path(), principal);
+                req.extensions_mut().insert(principal);
+                None
+            }
+            Ok(None) => None,
+            Err(rejection) => {
+                log::warn!("Authentication of {} on {} failed: {}", client_addr, req.uri().path(), rejection.reason);
+                let mut response = proxy_error(grpc, StatusCode::UNAUTHORIZED, "Unauthorized");
+                for challenge in rejection.challenges {
+                    response.headers_mut().append(WWW_AUTHENTICATE, challenge);
+                }
+                Some(response)
+            }
+        }
+    }
+
+    /// Soumet une requête au service d'autorisation externe de sa route, le cas échéant.
+    ///
+    /// Retourne la réponse à renvoyer au client si le service refuse la requête (401, 403) ou
+    /// ne peut pas se prononcer (500).
+    async fn authorize_externally(&self, req: &mut Request<Body>, client_addr: SocketAddr, grpc: bool) -> Option<Response<Body>> {
+        let route = self.router.as_ref().and_then(|router| router.route_for(req.uri().path()));
+        let auth_request = route.and_then(|route| route.auth_request())?;
+        let error = auth_request.authorize(req, client_addr).await.err()?;
+        log::warn!("External authorization of {} on {} failed: {}", client_addr, req.uri().path(), error);
+        Some(match error {
+            AuthRequestError::Denied { status, challenge } => {
+                let mut response = proxy_error(grpc, status, status.canonical_reason().unwrap_or("Denied"));
+                if let Some(challenge) = challenge {
+                    response.headers_mut().insert(WWW_AUTHENTICATE, challenge);
+                }
+                response
+            }
+            AuthRequestError::Failed(_) => proxy_error(grpc, StatusCode::INTERNAL_SERVER_ERROR, "Internal server error"),
+        })
+    }
+
+    /// Applique les limites de débit de la route d'une requête.
+    ///
+    /// Retourne la décision la plus restrictive si la requête est acceptée, `None` si la route
+    /// n'a pas de limite, ou la décision de la limite dépassée.
+    async fn check_rate_limits(&self, req: &Request<Body>, client_addr: SocketAddr) -> Result<Option<RateLimitDecision>, RateLimitDecision> {
+        let route = match self.router.as_ref().and_then(|router| router.route_for(req.uri().path())) {
+            Some(route) => route,
+            None => return Ok(None),
+        };
+        let mut tightest: Option<RateLimitDecision> = None;
+        for limiter in route.rate_limits() {
+            let decision = limiter.check(limiter.identity(req.headers(), req.extensions().get(), client_addr.ip())).await;
+            if !decision.allowed {
+                return Err(decision);
+            }
+            if tightest.is_none_or(|tightest| decision.remaining < tightest.remaining) {
+                tightest = Some(decision);
+            }
+        }
+        Ok(tightest)
+    }
+
+    /// Sélectionne le serveur backend d'une requête et y réserve une connexion.
+    ///
+    /// Le backend est choisi via la route correspondante si elle existe, sinon via le load balancer
+    /// par défaut. Au-delà de la limite de concurrence adaptative du pool, la requête est refusée
+    /// (503 avec `Retry-After`). Si tous les backends du pool sont saturés, ou si des requêtes attendent
+    /// déjà, elle rejoint la file d'attente du pool. Retourne la réponse d'erreur si la requête est refusée.
+    async fn acquire_backend(
+        &self,
+        req: &Request<Body>,
+        client_addr: SocketAddr,
+        grpc: bool,
+    ) -> Result<(Arc<BackendServer>, ConnectionGuard, Option<ConcurrencyPermit>), Response<Body>> {
+        let (load_balancer, backends, concurrency_limiter, queue) = self
+            .router
+            .as_ref()
+            .and_then(|router| {
+                let route = router.route_for(req.uri().path())?;
+                router.choose_pool(route, req.headers(), client_addr.ip())
+            })
+            .map(|pool| (pool.load_balancer(), pool.backends(), pool.concurrency_limiter(), pool.queue()))
+            .unwrap_or_else(|| (self.load_balancer.clone(), self.backends.as_slice(), self.concurrency_limiter.as_ref(), &self.queue));
+
+        // Les requêtes en excès sont délestées sans attendre
+        let mut permit = match concurrency_limiter {
+            Some(limiter) => match limiter.try_acquire() {
+                Some(permit) => Some(permit),
+                None => {
+                    let mut response = proxy_error(grpc, StatusCode::SERVICE_UNAVAILABLE, "Concurrency limit reached");
+                    response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(limiter.retry_after().as_secs()));
+                    return Err(response);
+                }
+            },
+            None => None,
+        };
+
+        // Le load balancer évite les backends saturés : s'il en retourne un, tous le sont
+        let acquire = || {
+            let backend = load_balancer.select_backend();
+            let connection = backend.try_track_connection()?;
+            Some((backend, connection))
+        };
+        if queue.is_empty() {
+            if let Some((backend, connection)) = acquire() {
+                return Ok((backend, connection, permit));
+            }
+        }
+        match queue.wait(backends, acquire).await {
+            Ok((backend, connection)) => {
+                if let Some(permit) = &mut permit {
+                    permit.restart(); // L'attente en file n'est pas de la latence du backend
+                }
+                Ok((backend, connection, permit))
+            }
+            Err(rejection) => Err(proxy_error(grpc, StatusCode::SERVICE_UNAVAILABLE, rejection.message())),
+        }
+    }
+
+    /// Gère une requête d'administration.
+    ///
+    /// `PUT /_lb/routes/<route>/split` avec un corps JSON `{"stable": 95, "canary": 5}` remplace
+    /// la répartition de la route sans redémarrage. `GET /_lb/connections` retourne les statistiques
+    /// des pools de connexions de chaque backend. L'en-tête `X-Admin-Token` doit porter le jeton configuré.
+    async fn handle_admin(&self, req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+        let authorized = req
+            .headers()
+            .get("X-Admin-Token")
+            .and_then(|value| value.to_str().ok())
+            .zip(self.admin_token.as_deref())
+            // Comparaison en temps constant : la durée ne révèle pas le préfixe correct du jeton
+            .is_some_and(|(token, expected)| verify_slices_are_equal(token.as_bytes(), expected.as_bytes()).is_ok());
+        if !authorized {
+            return Ok(status_response(StatusCode::UNAUTHORIZED, "Invalid admin token"));
+        }
+
+        if req.uri().path() == ADMIN_CONNECTIONS_PATH {
+            return Ok(self.connection_stats());
+        }
+        let route_name = match req.uri().path().strip_prefix(ADMIN_ROUTES_PREFIX).and_then(|path| path.strip_suffix("/split")) {
+            Some(route_name) => route_name.to_string(),
+            None => return Ok(status_response(StatusCode::NOT_FOUND, "Unknown admin endpoint")),
+        };
+        if req.method() != Method::PUT {
+            return Ok(status_response(StatusCode::METHOD_NOT_ALLOWED, "Use PUT to update a traffic split"));
+        }
+        let router = match &self.router {
+            Some(router) => router,
+            None => return Ok(status_response(StatusCode::NOT_FOUND, "No routes configured")),
+        };
+
+        // Lit la nouvelle répartition depuis le corps JSON
+        let body = hyper::body::to_bytes(req.into_body()).await?;
+        let weights: HashMap<String, u32> = match serde_json::from_slice(&body) {
+            Ok(weights) => weights,
+            Err(e) => return Ok(status_response(StatusCode::BAD_REQUEST, &format!("Invalid traffic split: {}", e))),
+        };
+        let mut weights: Vec<(String, u32)> = weights.into_iter().collect();
+        weights.sort(); // Ordre stable pour que l'affectation collante ne dépende pas de l'ordre du JSON
+
+        match router.set_split(&route_name, weights) {
+            Ok(()) => {
+                log::info!("Traffic split of route {} updated", route_name);
+                Ok(status_response(StatusCode::NO_CONTENT, ""))
+            }
+            Err(e) => Ok(status_response(StatusCode::BAD_REQUEST, &e.to_string())),
+        }
+    }
+}
+
+impl RequestHandler {
+    /// Statistiques des pools de connexions, par pool puis par backend (`adresse:port`).
+    fn connection_stats(&self) -> Response<Body> {
+        let stats_of = |backends: &[Arc<BackendServer>]| -> serde_json::Map<String, serde_json::Value> {
+            backends
+                .iter()
+                .map(|backend| {
+                    let stats = backend.connection_pool().stats();
+                    (format!("{}:{}", backend.address(), backend.port()), serde_json::json!(stats))
+                })
+                .collect()
+        };
+        let mut pools = serde_json::Map::new();
+        pools.insert("default".to_string(), serde_json::Value::Object(stats_of(&self.backends)));
+        if let Some(router) = &self.router {
+            for pool in router.pools() {
+                pools.insert(pool.name().to_string(), serde_json::Value::Object(stats_of(pool.backends())));
+            }
+        }
+
+        let mut response = Response::new(Body::from(serde_json::Value::Object(pools).to_string()));
+        response.headers_mut().insert(hyper::header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        response
+    }
+}
+
+/// Construit une réponse avec le code de statut et le message donnés.
+fn status_response(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(message.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+/// Construit la réponse d'une erreur du proxy, en statut gRPC si le client parle gRPC.
+fn proxy_error(grpc: bool, status: StatusCode, message: &str) -> Response<Body> {
+    if grpc {
+        grpc::error_response(status, message)
+    } else {
+        status_response(status, message)
     }
 }
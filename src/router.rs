@@ -0,0 +1,525 @@
+use std::collections::hash_map::DefaultHasher; // Importation du hacheur utilisé pour l'affectation collante des utilisateurs
+use std::collections::HashMap; // Importation de HashMap pour indexer les pools par nom
+use std::hash::{Hash, Hasher}; // Importation des traits de hachage
+use std::net::IpAddr; // Importation de IpAddr pour la clé collante par adresse cliente
+use std::sync::{Arc, RwLock}; // Importation de RwLock pour ajuster les répartitions à chaud
+use hyper::header::{HeaderMap, COOKIE}; // Importation des en-têtes HTTP
+use rand::Rng; // Importation de Rng pour le tirage aléatoire du pool
+use crate::access::AccessList; // Importation des listes d'accès par adresse cliente
+use crate::backend::BackendServer; // Importation de la structure BackendServer depuis le module backend
+use crate::concurrency::ConcurrencyLimiter; // Importation de la limite de concurrence adaptative
+use crate::config::{BackendConfig, PoolConfig, RouteConfig}; // Importation de la configuration des pools et des routes
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::auth::RouteAuth; // Importation de l'authentification des routes
+use crate::auth_request::AuthRequest; // Importation de l'autorisation externe des routes
+use crate::queue::{QueueConfig, RequestQueue}; // Importation des files d'attente des pools
+use crate::rate_limit::RateLimiter; // Importation des limites de débit des routes
+use crate::rate_limit_store::RateLimitStore; // Importation du magasin partagé des limites de débit
+use crate::load_balancer::{build_topology_load_balancer, SharedLoadBalancer}; // Importation des stratégies de répartition
+use crate::priority::DEFAULT_OVERPROVISIONING_FACTOR; // Importation de la marge par défaut des tiers de priorité
+use crate::slow_start::SlowStart; // Importation de la configuration de montée en charge progressive
+use crate::zone::ZoneAwareConfig; // Importation du routage par zone
+
+/// Pool nommé de serveurs backend, servi par sa propre stratégie de répartition.
+pub struct Pool {
+    name: String, // Nom du pool
+    backends: Vec<Arc<BackendServer>>, // Serveurs backend du pool
+    strategy: String, // Nom de la stratégie de répartition du pool
+    load_balancer: SharedLoadBalancer, // Stratégie de répartition appliquée au pool, par tier de priorité et par zone
+    concurrency_limiter: Option<Arc<ConcurrencyLimiter>>, // Limite de concurrence adaptative, si configurée
+    queue: RequestQueue, // File d'attente des requêtes lorsque tous les backends du pool sont saturés
+}
+
+impl Pool {
+    /// Nom du pool.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Serveurs backend du pool.
+    pub fn backends(&self) -> &[Arc<BackendServer>] {
+        &self.backends
+    }
+
+    /// Sélectionne un serveur backend du pool avec la stratégie configurée.
+    pub fn select_backend(&self) -> Arc<BackendServer> {
+        self.load_balancer.select_backend()
+    }
+
+    /// Stratégie de répartition du pool, pour la partager avec d'autres listeners.
+    pub fn load_balancer(&self) -> SharedLoadBalancer {
+        self.load_balancer.clone()
+    }
+
+    /// Limite de concurrence adaptative du pool, si configurée.
+    pub fn concurrency_limiter(&self) -> Option<&Arc<ConcurrencyLimiter>> {
+        self.concurrency_limiter.as_ref()
+    }
+
+    /// File d'attente du pool, indépendante de celles des autres pools.
+    pub fn queue(&self) -> &RequestQueue {
+        &self.queue
+    }
+}
+
+/// Répartition pondérée du trafic d'une route entre plusieurs pools, ajustable à chaud.
+pub struct TrafficSplit {
+    weights: RwLock<Vec<(String, u32)>>, // Paires (nom du pool, poids) dans l'ordre de la configuration
+}
+
+impl TrafficSplit {
+    /// Crée une répartition à partir de paires (nom du pool, poids).
+    pub fn new(weights: Vec<(String, u32)>) -> Self {
+        Self { weights: RwLock::new(weights) }
+    }
+
+    /// Répartition courante, sous forme de paires (nom du pool, poids).
+    pub fn weights(&self) -> Vec<(String, u32)> {
+        self.weights.read().unwrap().clone()
+    }
+
+    /// Choisit le pool destinataire d'une requête.
+    ///
+    /// Avec une clé collante (valeur d'en-tête, de cookie ou adresse du client), le choix est déterministe :
+    /// un même utilisateur reste sur le même pool tant que la répartition ne change pas.
+    pub fn choose(&self, sticky_key: Option<&str>) -> Option<String> {
+        let weights = self.weights.read().unwrap();
+        let total: u64 = weights.iter().map(|(_, weight)| *weight as u64).sum();
+        if total == 0 {
+            return None;
+        }
+
+        // Position du tirage dans l'intervalle [0, total)
+        let point = match sticky_key {
+            Some(key) => {
+                let mut hasher = DefaultHasher::new();
+                key.hash(&mut hasher);
+                hasher.finish() % total
+            }
+            None => rand::thread_rng().gen_range(0..total),
+        };
+
+        let mut cumulative = 0;
+        weights.iter().find_map(|(pool, weight)| {
+            cumulative += *weight as u64;
+            (point < cumulative).then(|| pool.clone())
+        })
+    }
+
+    /// Remplace la répartition courante.
+    fn set(&self, weights: Vec<(String, u32)>) {
+        *self.weights.write().unwrap() = weights;
+    }
+}
+
+/// Source de la clé collante d'une route.
+pub enum StickyKey {
+    Header(String), // Nom de l'en-tête portant l'identifiant de l'utilisateur
+    Cookie(String), // Nom du cookie portant l'identifiant de l'utilisateur
+    ClientIp, // Adresse du client, recouvrée du PROXY protocol derrière un répartiteur
+}
+
+impl StickyKey {
+    /// Extrait la valeur de la clé collante d'une requête, à partir de ses en-têtes ou de l'adresse du client.
+    fn extract(&self, headers: &HeaderMap, client_ip: IpAddr) -> Option<String> {
+        match self {
+            StickyKey::Header(name) => headers.get(name.as_str()).and_then(|value| value.to_str().ok()).map(str::to_string),
+            StickyKey::ClientIp => Some(client_ip.to_string()),
+            StickyKey::Cookie(name) => headers
+                .get_all(COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|cookies| cookies.split(';'))
+                .filter_map(|cookie| cookie.trim().split_once('='))
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.to_string()),
+        }
+    }
+}
+
+/// Route : préfixe de chemin dont le trafic est réparti entre plusieurs pools.
+pub struct Route {
+    name: String, // Nom de la route
+    path_prefix: String, // Préfixe des chemins servis par la route
+    split: TrafficSplit, // Répartition du trafic entre les pools
+    sticky: Option<StickyKey>, // Clé collante optionnelle
+    rate_limits: Vec<RateLimiter>, // Limites de débit des clients de la route
+    access_list: Option<Arc<AccessList>>, // Adresses clientes autorisées et refusées sur la route
+    auth: Option<RouteAuth>, // Authentification des requêtes de la route
+    auth_request: Option<AuthRequest>, // Autorisation des requêtes par un service externe
+}
+
+impl Route {
+    /// Nom de la route.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Répartition du trafic de la route.
+    pub fn split(&self) -> &TrafficSplit {
+        &self.split
+    }
+
+    /// Limites de débit des clients de la route.
+    pub fn rate_limits(&self) -> &[RateLimiter] {
+        &self.rate_limits
+    }
+
+    /// Liste d'accès de la route, le cas échéant.
+    pub fn access_list(&self) -> Option<&Arc<AccessList>> {
+        self.access_list.as_ref()
+    }
+
+    /// Authentification des requêtes de la route, le cas échéant.
+    pub fn auth(&self) -> Option<&RouteAuth> {
+        self.auth.as_ref()
+    }
+
+    /// Autorisation externe des requêtes de la route, le cas échéant.
+    pub fn auth_request(&self) -> Option<&AuthRequest> {
+        self.auth_request.as_ref()
+    }
+}
+
+/// Routeur : associe les requêtes aux routes configurées puis aux pools de backends.
+pub struct Router {
+    pools: HashMap<String, Pool>, // Pools indexés par nom
+    routes: Vec<Route>, // Routes configurées
+    slow_start: Option<SlowStart>, // Montée en charge progressive des backends des pools, si configurée
+}
+
+impl Router {
+    /// Construit le routeur à partir de la configuration des pools et des routes.
+    ///
+    /// Les pools sans stratégie propre utilisent `default_strategy`, appliquée par tier de priorité
+    /// (backends de secours compris). Retourne une erreur de configuration si une route référence
+    /// un pool inconnu.
+    pub fn new(
+        pools: &[PoolConfig],
+        routes: &[RouteConfig],
+        default_strategy: &str,
+        slow_start: Option<SlowStart>,
+    ) -> Result<Self, AppError> {
+        let mut router_pools = HashMap::new();
+        for pool in pools {
+            if pool.backends.is_empty() {
+                return Err(AppError::ConfigError(format!("Pool {} has no backend servers", pool.name)));
+            }
+            // Les réglages du pool (TLS, protocole, santé, connexions) s'appliquent aux backends qui n'en déclarent pas
+            let backends = pool
+                .backends
+                .iter()
+                .map(|backend| {
+                    BackendServer::from_config(&BackendConfig {
+                        tls: backend.tls.clone().or_else(|| pool.tls.clone()),
+                        protocol: backend.protocol.or(pool.protocol),
+                        health_check: backend.health_check.clone().or_else(|| pool.health_check.clone()),
+                        connection_pool: backend.connection_pool.clone().or_else(|| pool.connection_pool.clone()),
+                        ..backend.clone()
+                    })
+                })
+                .collect::<Result<Vec<_>, AppError>>()?;
+            let strategy = pool.strategy.as_deref().unwrap_or(default_strategy);
+            let load_balancer =
+                build_topology_load_balancer(strategy, backends.clone(), slow_start, DEFAULT_OVERPROVISIONING_FACTOR, None)?;
+            let concurrency_limiter = pool.adaptive_concurrency.clone().map(|config| Arc::new(ConcurrencyLimiter::new(config)));
+            let queue = RequestQueue::new(QueueConfig::default());
+            let strategy = strategy.to_string();
+            router_pools.insert(
+                pool.name.clone(),
+                Pool { name: pool.name.clone(), backends, strategy, load_balancer, concurrency_limiter, queue },
+            );
+        }
+
+        let mut router = Self { pools: router_pools, routes: Vec::new(), slow_start };
+        for route in routes {
+            let weights: Vec<_> = route.split.iter().map(|s| (s.pool.clone(), s.weight)).collect();
+            router.validate_split(&route.name, &weights)?;
+            let sticky = match (&route.sticky_header, &route.sticky_cookie) {
+                (Some(header), _) => Some(StickyKey::Header(header.clone())),
+                (None, Some(cookie)) => Some(StickyKey::Cookie(cookie.clone())),
+                (None, None) if route.sticky_client_ip => Some(StickyKey::ClientIp),
+                (None, None) => None,
+            };
+            router.routes.push(Route {
+                name: route.name.clone(),
+                path_prefix: route.path_prefix.clone(),
+                split: TrafficSplit::new(weights),
+                sticky,
+                rate_limits: route
+                    .rate_limits
+                    .iter()
+                    .enumerate()
+                    .map(|(index, limit)| RateLimiter::new(limit.clone(), &route.name, index))
+                    .collect(),
+                access_list: route.access.clone().map(AccessList::new).transpose()?.map(Arc::new),
+                auth: RouteAuth::from_config(route)?,
+                auth_request: route.auth_request.clone().map(AuthRequest::new).transpose()?,
+            });
+        }
+        Ok(router)
+    }
+
+    /// Partage les compteurs des limites de débit de toutes les routes via le magasin donné.
+    pub fn with_rate_limit_store(mut self, store: Arc<RateLimitStore>) -> Self {
+        for route in &mut self.routes {
+            route.rate_limits = std::mem::take(&mut route.rate_limits)
+                .into_iter()
+                .map(|limit| limit.with_store(store.clone()))
+                .collect();
+        }
+        self
+    }
+
+    /// Applique la marge des tiers de priorité et, si la zone du proxy est connue, la préférence
+    /// pour les backends de la même zone à la stratégie de chaque pool.
+    pub fn with_topology(mut self, overprovisioning_factor: f64, zone_aware: Option<&ZoneAwareConfig>) -> Result<Self, AppError> {
+        for pool in self.pools.values_mut() {
+            pool.load_balancer =
+                build_topology_load_balancer(&pool.strategy, pool.backends.clone(), self.slow_start, overprovisioning_factor, zone_aware)?;
+        }
+        Ok(self)
+    }
+
+    /// Configure la file d'attente de chaque pool ; un pool saturé ne fait pas attendre les autres.
+    pub fn with_queue(mut self, config: QueueConfig) -> Self {
+        for pool in self.pools.values_mut() {
+            pool.queue = RequestQueue::new(config.clone());
+        }
+        self
+    }
+
+    /// Listes d'accès des routes, dont les fichiers sont à surveiller.
+    pub fn access_lists(&self) -> impl Iterator<Item = &Arc<AccessList>> {
+        self.routes.iter().filter_map(Route::access_list)
+    }
+
+    /// Pool portant le nom donné, s'il existe.
+    pub fn pool(&self, name: &str) -> Option<&Pool> {
+        self.pools.get(name)
+    }
+
+    /// Tous les pools du routeur.
+    pub fn pools(&self) -> impl Iterator<Item = &Pool> {
+        self.pools.values()
+    }
+
+    /// Route servant le chemin donné : celle dont le préfixe est le plus long.
+    ///
+    /// Le préfixe s'arrête sur une limite de segment : `/api` sert `/api` et `/api/users`, pas `/apiary`.
+    pub fn route_for(&self, path: &str) -> Option<&Route> {
+        self.routes
+            .iter()
+            .filter(|route| {
+                let prefix = route.path_prefix.as_str();
+                path.strip_prefix(prefix)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/') || prefix.ends_with('/'))
+            })
+            .max_by_key(|route| route.path_prefix.len())
+    }
+
+    /// Route portant le nom donné, si elle existe.
+    pub fn route(&self, name: &str) -> Option<&Route> {
+        self.routes.iter().find(|route| route.name == name)
+    }
+
+    /// Choisit le pool d'une requête du client `client_ip` sur la route donnée.
+    pub fn choose_pool(&self, route: &Route, headers: &HeaderMap, client_ip: IpAddr) -> Option<&Pool> {
+        let sticky_key = route.sticky.as_ref().and_then(|sticky| sticky.extract(headers, client_ip));
+        route.split.choose(sticky_key.as_deref()).and_then(|name| self.pools.get(&name))
+    }
+
+    /// Ajuste à chaud la répartition d'une route.
+    ///
+    /// Retourne une erreur si la route ou l'un des pools est inconnu, ou si tous les poids sont nuls.
+    pub fn set_split(&self, route_name: &str, weights: Vec<(String, u32)>) -> Result<(), AppError> {
+        let route = self
+            .route(route_name)
+            .ok_or_else(|| AppError::ConfigError(format!("Unknown route: {}", route_name)))?;
+        self.validate_split(route_name, &weights)?;
+        route.split.set(weights);
+        Ok(())
+    }
+
+    /// Vérifie qu'une répartition ne référence que des pools connus et que son poids total est non nul.
+    fn validate_split(&self, route_name: &str, weights: &[(String, u32)]) -> Result<(), AppError> {
+        if let Some((pool, _)) = weights.iter().find(|(pool, _)| !self.pools.contains_key(pool)) {
+            return Err(AppError::ConfigError(format!("Route {} references unknown pool {}", route_name, pool)));
+        }
+        if weights.iter().all(|(_, weight)| *weight == 0) {
+            return Err(AppError::ConfigError(format!("Route {} has no traffic split", route_name)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+    use hyper::{Body, Response};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use crate::config::SplitConfig;
+    use crate::listener::serve_http;
+    use crate::load_balancer::RoundRobinLoadBalancer;
+    use crate::proxy_protocol::{self, ProxyAddresses, ProxyProtocolVersion};
+    use crate::request_handler::RequestHandler;
+    use crate::shutdown::Shutdown;
+    use crate::test_support::spawn_server;
+
+    const CLIENT: IpAddr = IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+    fn pool(name: &str, port: u16) -> PoolConfig {
+        PoolConfig {
+            name: name.to_string(),
+            backends: vec![BackendConfig { address: "127.0.0.1".to_string(), port, ..Default::default() }],
+            ..Default::default()
+        }
+    }
+
+    fn router(stable: u32, canary: u32) -> Router {
+        let route = RouteConfig {
+            name: "api".to_string(),
+            path_prefix: "/api".to_string(),
+            split: vec![
+                SplitConfig { pool: "stable".to_string(), weight: stable },
+                SplitConfig { pool: "canary".to_string(), weight: canary },
+            ],
+            sticky_header: Some("X-User-Id".to_string()),
+            ..Default::default()
+        };
+        Router::new(&[pool("stable", 8080), pool("canary", 9090)], &[route], "round_robin", None).unwrap()
+    }
+
+    /// Teste la répartition en pourcentage entre les pools.
+    #[test]
+    fn test_percentage_split() {
+        let router = router(95, 5);
+        let route = router.route_for("/api/users").unwrap();
+        let headers = HeaderMap::new();
+        let canary = (0..10_000)
+            .filter(|_| router.choose_pool(route, &headers, CLIENT).unwrap().name() == "canary")
+            .count();
+        assert!((300..700).contains(&canary), "canary received {} requests", canary);
+        assert!(router.route_for("/static").is_none());
+    }
+
+    /// Teste qu'un utilisateur identifié par en-tête reste sur le même pool.
+    #[test]
+    fn test_sticky_header() {
+        let router = router(50, 50);
+        let route = router.route_for("/api").unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("X-User-Id", "user-42".parse().unwrap());
+        let first = router.choose_pool(route, &headers, CLIENT).unwrap().name().to_string();
+        for _ in 0..20 {
+            assert_eq!(router.choose_pool(route, &headers, CLIENT).unwrap().name(), first);
+        }
+    }
+
+    /// Teste de bout en bout l'affectation collante par adresse cliente, recouvrée du PROXY protocol
+    /// alors que toutes les connexions proviennent du même répartiteur.
+    #[tokio::test]
+    async fn test_sticky_client_ip_behind_proxy() {
+        let stable = spawn_server(|_| async { Response::new(Body::from("stable")) }).await;
+        let canary = spawn_server(|_| async { Response::new(Body::from("canary")) }).await;
+        let route = RouteConfig {
+            name: "api".to_string(),
+            path_prefix: "/api".to_string(),
+            split: vec![
+                SplitConfig { pool: "stable".to_string(), weight: 50 },
+                SplitConfig { pool: "canary".to_string(), weight: 50 },
+            ],
+            sticky_client_ip: true,
+            ..Default::default()
+        };
+        let router = Router::new(&[pool("stable", stable), pool("canary", canary)], &[route], "round_robin", None).unwrap();
+
+        // Deux adresses d'origine affectées à des pools différents
+        let route = router.route("api").unwrap();
+        let sources: Vec<SocketAddr> = (1..=255).map(|i| SocketAddr::from(([203, 0, 113, i], 40000))).collect();
+        let expected = |source: &SocketAddr| router.choose_pool(route, &HeaderMap::new(), source.ip()).unwrap().name().to_string();
+        let first = sources[0];
+        let second = *sources.iter().find(|source| expected(source) != expected(&first)).unwrap();
+        let (first_pool, second_pool) = (expected(&first), expected(&second));
+
+        let addr = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let default_backend = BackendServer::new("127.0.0.1".to_string(), stable);
+        let handler = RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(vec![default_backend]))).with_router(Arc::new(router));
+        tokio::spawn(serve_http(addr, Arc::new(handler), Shutdown::new(), true));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        for (source, pool) in [(first, first_pool), (second, second_pool)] {
+            for _ in 0..3 {
+                let mut stream = TcpStream::connect(addr).await.unwrap();
+                let origin = ProxyAddresses { source, destination: addr };
+                stream.write_all(&proxy_protocol::encode_header(ProxyProtocolVersion::V1, origin)).await.unwrap();
+                stream.write_all(b"GET /api HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n").await.unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).await.unwrap();
+                assert!(response.ends_with(&pool), "{} expected on {}: {}", source, pool, response);
+            }
+        }
+    }
+
+    /// Teste l'ajustement à chaud de la répartition.
+    #[test]
+    fn test_set_split_at_runtime() {
+        let router = router(95, 5);
+        router.set_split("api", vec![("stable".to_string(), 0), ("canary".to_string(), 100)]).unwrap();
+        let route = router.route("api").unwrap();
+        assert_eq!(router.choose_pool(route, &HeaderMap::new(), CLIENT).unwrap().select_backend().port(), 9090);
+
+        assert!(router.set_split("api", vec![("unknown".to_string(), 100)]).is_err());
+        assert!(router.set_split("api", vec![("stable".to_string(), 0)]).is_err());
+        assert!(router.set_split("missing", vec![("stable".to_string(), 100)]).is_err());
+    }
+
+    /// Teste que le préfixe d'une route s'arrête sur une limite de segment.
+    #[test]
+    fn test_path_prefix_boundary() {
+        let router = router(100, 0);
+        assert!(router.route_for("/api").is_some());
+        assert!(router.route_for("/api/users").is_some());
+        assert!(router.route_for("/apiary").is_none());
+        assert!(router.route_for("/api-v2/users").is_none());
+    }
+
+    /// Teste que les tiers de priorité, les secours et la zone des backends d'un pool sont appliqués.
+    #[test]
+    fn test_pool_topology() {
+        let backend = |port, priority, backup, zone: &str| BackendConfig {
+            address: "127.0.0.1".to_string(),
+            port,
+            priority,
+            backup,
+            zone: Some(zone.to_string()),
+            ..Default::default()
+        };
+        let pools = vec![PoolConfig {
+            name: "stable".to_string(),
+            backends: vec![backend(9090, 0, true, "eu-west-1a"), backend(8081, 1, false, "eu-west-1a"), backend(8080, 0, false, "eu-west-1a")],
+            ..Default::default()
+        }];
+        let router = Router::new(&pools, &[], "round_robin", None).unwrap();
+        let pool = router.pool("stable").unwrap();
+        assert!((0..10).all(|_| pool.select_backend().port() == 8080));
+        pool.backends()[2].set_healthy(false);
+        assert!((0..10).all(|_| pool.select_backend().port() == 8081));
+        pool.backends()[1].set_healthy(false);
+        assert!((0..10).all(|_| pool.select_backend().port() == 9090));
+
+        // La zone du proxy est préférée au sein du pool
+        let pools = vec![PoolConfig {
+            name: "stable".to_string(),
+            backends: vec![backend(8080, 0, false, "eu-west-1b"), backend(8081, 0, false, "eu-west-1a")],
+            ..Default::default()
+        }];
+        let zone_aware = ZoneAwareConfig { local_zone: "eu-west-1a".to_string(), min_local_healthy: 0.7 };
+        let router = Router::new(&pools, &[], "round_robin", None).unwrap().with_topology(1.4, Some(&zone_aware)).unwrap();
+        let pool = router.pool("stable").unwrap();
+        assert!((0..10).all(|_| pool.select_backend().port() == 8081));
+    }
+}
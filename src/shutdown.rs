@@ -0,0 +1,62 @@
+use std::sync::Arc; // Importation de Arc pour partager l'émetteur du signal
+use tokio::sync::watch; // Importation du canal watch diffusant le signal d'arrêt
+
+/// Signal d'arrêt partagé entre les listeners et les connexions de longue durée.
+///
+/// Une fois déclenché, les listeners cessent d'accepter des connexions et les tunnels
+/// (WebSocket, h2c) en cours sont fermés afin que le processus puisse s'arrêter.
+#[derive(Clone)]
+pub struct Shutdown {
+    sender: Arc<watch::Sender<bool>>, // Émetteur commun à toutes les copies
+    receiver: watch::Receiver<bool>, // Récepteur propre à chaque copie
+}
+
+impl Shutdown {
+    /// Crée un signal d'arrêt non déclenché.
+    pub fn new() -> Self {
+        let (sender, receiver) = watch::channel(false);
+        Self { sender: Arc::new(sender), receiver }
+    }
+
+    /// Déclenche l'arrêt.
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+
+    /// Indique si l'arrêt a été déclenché.
+    pub fn is_triggered(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Attend le déclenchement de l'arrêt.
+    pub async fn wait(&self) {
+        let mut receiver = self.receiver.clone();
+        // L'émetteur vit aussi longtemps que cette copie : l'attente ne peut pas échouer
+        let _ = receiver.wait_for(|triggered| *triggered).await;
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Teste que toutes les copies observent le déclenchement, même survenu avant l'attente.
+    #[tokio::test]
+    async fn test_trigger() {
+        let shutdown = Shutdown::new();
+        let copy = shutdown.clone();
+        assert!(!copy.is_triggered());
+
+        let waiter = tokio::spawn(async move { copy.wait().await });
+        shutdown.trigger();
+        waiter.await.unwrap();
+        assert!(shutdown.is_triggered());
+        shutdown.clone().wait().await;
+    }
+}
@@ -0,0 +1,94 @@
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration du slow start
+use std::time::{Duration, Instant}; // Importation des types de mesure du temps
+use crate::backend::BackendServer; // Importation de la structure BackendServer depuis le module backend
+
+/// Configuration de la montée en charge progressive (slow start).
+///
+/// Pendant `window_secs` secondes après son ajout ou son rétablissement, le poids effectif
+/// d'un backend passe progressivement d'une petite fraction de son poids nominal au poids complet.
+/// `aggression` contrôle la forme de la courbe : 1.0 donne une rampe linéaire, une valeur plus
+/// élevée attribue davantage de trafic dès le début de la fenêtre.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct SlowStart {
+    pub window_secs: u64, // Durée de la fenêtre de montée en charge, en secondes
+    #[serde(default = "default_aggression")]
+    pub aggression: f64, // Agressivité de la courbe de montée en charge
+    #[serde(default = "default_min_weight_fraction")]
+    pub min_weight_fraction: f64, // Fraction minimale du poids appliquée au début de la fenêtre
+}
+
+fn default_aggression() -> f64 {
+    1.0
+}
+
+fn default_min_weight_fraction() -> f64 {
+    0.1
+}
+
+impl SlowStart {
+    /// Crée une configuration de slow start linéaire sur la fenêtre donnée.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window_secs: window.as_secs(),
+            aggression: default_aggression(),
+            min_weight_fraction: default_min_weight_fraction(),
+        }
+    }
+
+    /// Facteur (entre `min_weight_fraction` et 1.0) à appliquer au poids d'un backend
+    /// dont la montée en charge a démarré il y a `elapsed`.
+    pub fn factor(&self, elapsed: Duration) -> f64 {
+        let window = Duration::from_secs(self.window_secs);
+        if window.is_zero() || elapsed >= window {
+            return 1.0; // Fenêtre terminée : le backend reçoit tout son poids
+        }
+        let progress = elapsed.as_secs_f64() / window.as_secs_f64();
+        // Une agressivité nulle ou négative est ramenée à une rampe linéaire
+        let aggression = if self.aggression > 0.0 { self.aggression } else { 1.0 };
+        progress
+            .powf(1.0 / aggression)
+            .clamp(self.min_weight_fraction.clamp(0.0, 1.0), 1.0)
+    }
+
+    /// Poids effectif d'un backend à l'instant `now`, en tenant compte de sa montée en charge.
+    pub fn effective_weight(&self, backend: &BackendServer, weight: u32, now: Instant) -> f64 {
+        match backend.warmup_started() {
+            Some(started) => weight as f64 * self.factor(now.saturating_duration_since(started)),
+            None => weight as f64, // Aucun slow start en cours pour ce backend
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Teste la rampe linéaire du facteur de poids.
+    #[test]
+    fn test_linear_ramp() {
+        let slow_start = SlowStart::new(Duration::from_secs(100));
+        assert_eq!(slow_start.factor(Duration::from_secs(0)), 0.1); // Plancher au début de la fenêtre
+        assert!((slow_start.factor(Duration::from_secs(50)) - 0.5).abs() < 1e-9);
+        assert_eq!(slow_start.factor(Duration::from_secs(100)), 1.0);
+        assert_eq!(slow_start.factor(Duration::from_secs(500)), 1.0);
+    }
+
+    /// Teste qu'une agressivité plus élevée attribue plus de poids en début de fenêtre.
+    #[test]
+    fn test_aggressive_curve() {
+        let slow_start = SlowStart { window_secs: 100, aggression: 2.0, min_weight_fraction: 0.0 };
+        assert!((slow_start.factor(Duration::from_secs(25)) - 0.5).abs() < 1e-9);
+    }
+
+    /// Teste que le poids effectif n'est réduit que pendant la montée en charge.
+    #[test]
+    fn test_effective_weight() {
+        let slow_start = SlowStart::new(Duration::from_secs(60));
+        let backend = BackendServer::with_weight("127.0.0.1".to_string(), 8080, 10);
+        assert_eq!(slow_start.effective_weight(&backend, 10, Instant::now()), 10.0);
+
+        backend.set_healthy(false);
+        backend.set_healthy(true); // Le rétablissement démarre le slow start
+        assert!(slow_start.effective_weight(&backend, 10, Instant::now()) < 2.0);
+    }
+}
@@ -0,0 +1,401 @@
+use std::io; // Importation des erreurs d'entrée/sortie
+use std::net::SocketAddr; // Importation de SocketAddr pour les adresses d'écoute et des clients
+use std::sync::Arc; // Importation de Arc pour le partage sécurisé entre threads
+use std::sync::atomic::{AtomicU64, Ordering}; // Importation de AtomicU64 pour l'instant de la dernière activité
+use std::time::{Duration, Instant}; // Importation des types de mesure du temps
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration des listeners TCP
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt}; // Importation des traits d'entrée/sortie asynchrones
+use tokio::net::{TcpListener, TcpStream}; // Importation des sockets TCP
+use crate::proxy_protocol::{self, ProxyAddresses, ProxyProtocolVersion}; // Importation du PROXY protocol
+use crate::backend::BackendServer; // Importation de la structure BackendServer depuis le module backend
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::health::HealthChecker; // Importation des vérifications de santé
+use crate::load_balancer::SharedLoadBalancer; // Importation des stratégies de répartition
+
+/// Délai d'inactivité par défaut d'une connexion relayée.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+/// Délai par défaut d'établissement d'une connexion vers un backend.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Configuration d'un listener TCP (couche 4).
+#[derive(Debug, Clone, Deserialize)]
+pub struct TcpProxyConfig {
+    pub listen: SocketAddr, // Adresse d'écoute
+    #[serde(default)]
+    pub pool: Option<String>, // Pool de destination ; les serveurs backend principaux si absent
+    #[serde(default = "default_idle_timeout_secs")]
+    pub idle_timeout_secs: u64, // Fermeture des connexions sans trafic dans les deux sens
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64, // Délai de connexion à un backend avant d'en essayer un autre
+    #[serde(default = "default_health_check_interval_secs")]
+    pub health_check_interval_secs: u64, // Intervalle des vérifications de santé par connexion TCP
+    #[serde(default)]
+    pub accept_proxy_protocol: bool, // Lit l'adresse d'origine dans l'en-tête PROXY protocol des connexions entrantes
+    #[serde(default)]
+    pub send_proxy_protocol: Option<ProxyProtocolVersion>, // Annonce l'adresse d'origine aux backends (v1 ou v2)
+}
+
+fn default_idle_timeout_secs() -> u64 {
+    DEFAULT_IDLE_TIMEOUT.as_secs()
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    DEFAULT_CONNECT_TIMEOUT.as_secs()
+}
+
+fn default_health_check_interval_secs() -> u64 {
+    10
+}
+
+/// Instant de la dernière activité d'une connexion relayée, partagé par ses deux sens.
+struct Activity {
+    start: Instant, // Origine des instants mesurés
+    last_ms: AtomicU64, // Millisecondes écoulées depuis `start` lors de la dernière activité
+}
+
+impl Activity {
+    fn new() -> Self {
+        Self { start: Instant::now(), last_ms: AtomicU64::new(0) }
+    }
+
+    /// Enregistre une activité maintenant.
+    fn touch(&self) {
+        self.last_ms.store(self.start.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Instant de la dernière activité.
+    fn last(&self) -> Instant {
+        self.start + Duration::from_millis(self.last_ms.load(Ordering::Relaxed))
+    }
+}
+
+/// Relaie les octets d'un sens jusqu'à la fin du flux, puis propage la fermeture (demi-fermeture).
+async fn copy_half<R, W>(mut read: R, mut write: W, activity: &Activity) -> io::Result<u64>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = vec![0; 16 * 1024];
+    let mut copied = 0;
+    loop {
+        let n = read.read(&mut buf).await?;
+        if n == 0 {
+            let _ = write.shutdown().await; // L'autre côté voit la fin du flux
+            return Ok(copied);
+        }
+        activity.touch();
+        write.write_all(&buf[..n]).await?;
+        activity.touch();
+        copied += n as u64;
+    }
+}
+
+/// Relaie les octets dans les deux sens entre deux flux jusqu'à leur fermeture.
+///
+/// Les deux sens progressent indépendamment : un côté qui ne lit plus ne bloque que le sens
+/// qui lui écrit. La fermeture d'un côté est propagée à l'autre (demi-fermeture), et la connexion
+/// est abandonnée si aucun octet n'est lu ni écrit pendant `idle_timeout`, y compris lorsqu'une
+/// écriture reste bloquée. Retourne le nombre d'octets transmis de `a` vers `b` et de `b` vers `a`.
+pub async fn pipe<A, B>(a: A, b: B, idle_timeout: Duration) -> io::Result<(u64, u64)>
+where
+    A: AsyncRead + AsyncWrite + Unpin,
+    B: AsyncRead + AsyncWrite + Unpin,
+{
+    let (a_read, a_write) = tokio::io::split(a);
+    let (b_read, b_write) = tokio::io::split(b);
+    let activity = Activity::new();
+    let copy = async {
+        tokio::try_join!(copy_half(a_read, b_write, &activity), copy_half(b_read, a_write, &activity))
+    };
+    let idle = async {
+        loop {
+            let deadline = activity.last() + idle_timeout;
+            if Instant::now() >= deadline {
+                return io::Error::new(io::ErrorKind::TimedOut, "Connection idle timeout");
+            }
+            tokio::time::sleep_until(deadline.into()).await;
+        }
+    };
+    tokio::select! {
+        result = copy => result,
+        e = idle => Err(e),
+    }
+}
+
+/// Répartition de charge en couche 4.
+///
+/// Chaque connexion TCP acceptée est relayée telle quelle vers un backend choisi par la
+/// stratégie configurée. Un backend qui refuse la connexion est marqué en mauvaise santé et
+/// la connexion est retentée sur un autre ; les vérifications de santé par connexion TCP le
+/// réintègrent lorsqu'il répond de nouveau.
+pub struct TcpProxy {
+    load_balancer: SharedLoadBalancer, // Stratégie de choix du backend
+    backends: Vec<Arc<BackendServer>>, // Serveurs backend servis par la stratégie
+    idle_timeout: Duration, // Délai d'inactivité d'une connexion relayée
+    connect_timeout: Duration, // Délai de connexion à un backend
+    accept_proxy_protocol: bool, // Lecture de l'en-tête PROXY protocol des clients
+    send_proxy_protocol: Option<ProxyProtocolVersion>, // Version du PROXY protocol émise vers les backends
+}
+
+impl TcpProxy {
+    /// Crée une nouvelle instance de `TcpProxy` avec les délais par défaut.
+    pub fn new(load_balancer: SharedLoadBalancer, backends: Vec<Arc<BackendServer>>) -> Self {
+        Self {
+            load_balancer,
+            backends,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            accept_proxy_protocol: false,
+            send_proxy_protocol: None,
+        }
+    }
+
+    /// Définit le délai d'inactivité des connexions relayées.
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Définit le délai de connexion aux backends.
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Lit l'adresse d'origine des clients dans l'en-tête PROXY protocol de leurs connexions.
+    pub fn with_accept_proxy_protocol(mut self, accept: bool) -> Self {
+        self.accept_proxy_protocol = accept;
+        self
+    }
+
+    /// Annonce l'adresse d'origine des clients aux backends avec le PROXY protocol.
+    pub fn with_send_proxy_protocol(mut self, version: Option<ProxyProtocolVersion>) -> Self {
+        self.send_proxy_protocol = version;
+        self
+    }
+
+    /// Ouvre une connexion vers un backend sain, en essayant les suivants en cas d'échec.
+    pub async fn connect_backend(&self) -> Result<(Arc<BackendServer>, TcpStream), AppError> {
+        for _ in 0..self.backends.len() {
+            // La stratégie ne retourne un backend hors service que si aucun n'est sain
+            let backend = self.load_balancer.select_backend();
+            if !backend.is_healthy() {
+                break;
+            }
+            let connect = TcpStream::connect((backend.address(), backend.port()));
+            match tokio::time::timeout(self.connect_timeout, connect).await {
+                Ok(Ok(stream)) => return Ok((backend, stream)),
+                Ok(Err(e)) => log::warn!("Backend {}:{} refused connection: {}", backend.address(), backend.port(), e),
+                Err(_) => log::warn!("Connection to backend {}:{} timed out", backend.address(), backend.port()),
+            }
+            backend.set_healthy(false); // Retiré jusqu'à la prochaine vérification de santé réussie
+        }
+        Err(AppError::BackendServerError("No healthy backend accepted the connection".to_string()))
+    }
+
+    /// Relaie une connexion cliente vers un backend.
+    pub async fn handle_connection(&self, mut client: TcpStream, peer_addr: SocketAddr) -> Result<(), AppError> {
+        // Adresses d'origine : celles annoncées par le répartiteur en amont, sinon celles du socket
+        let announced = if self.accept_proxy_protocol {
+            tokio::time::timeout(self.connect_timeout, proxy_protocol::read_header(&mut client))
+                .await
+                .map_err(|_| AppError::NetworkError("PROXY protocol header timed out".to_string()))??
+        } else {
+            None
+        };
+        let addresses = match announced {
+            Some(addresses) => addresses,
+            None => ProxyAddresses { source: peer_addr, destination: client.local_addr()? },
+        };
+
+        let (backend, mut upstream) = self.connect_backend().await?;
+        let _connection = backend.track_connection(); // Comptée pour la stratégie de moindre connexion
+        if let Some(version) = self.send_proxy_protocol {
+            upstream.write_all(&proxy_protocol::encode_header(version, addresses)).await?;
+        }
+        let (sent, received) = pipe(client, upstream, self.idle_timeout).await?;
+        log::debug!(
+            "Connection from {} to {}:{} closed ({} bytes sent, {} received)",
+            addresses.source,
+            backend.address(),
+            backend.port(),
+            sent,
+            received
+        );
+        Ok(())
+    }
+
+    /// Accepte les connexions sur `addr` et les relaie vers les backends.
+    pub async fn serve(self: Arc<Self>, addr: SocketAddr) -> Result<(), AppError> {
+        let listener = TcpListener::bind(addr).await?;
+        loop {
+            let (stream, client_addr) = listener.accept().await?;
+            let this = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = this.handle_connection(stream, client_addr).await {
+                    log::debug!("TCP connection from {} failed: {}", client_addr, e);
+                }
+            });
+        }
+    }
+
+    /// Lance en tâche de fond les vérifications de santé par connexion TCP des backends.
+    pub fn watch_health(self: Arc<Self>, interval: Duration) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                for backend in &self.backends {
+                    let healthy = HealthChecker::check_tcp(backend.clone(), self.connect_timeout).await;
+                    backend.set_healthy(healthy);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_balancer::{LeastConnectionsLoadBalancer, RoundRobinLoadBalancer};
+
+    /// Démarre un serveur d'écho et retourne son port.
+    async fn echo_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let (mut read, mut write) = stream.split();
+                    let _ = tokio::io::copy(&mut read, &mut write).await;
+                });
+            }
+        });
+        port
+    }
+
+    /// Teste le relais vers un backend sain après l'échec de connexion à un backend arrêté.
+    #[tokio::test]
+    async fn test_relays_and_skips_dead_backend() {
+        // Un port libéré aussitôt réservé : la connexion y sera refusée
+        let dead_port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        let dead = BackendServer::new("127.0.0.1".to_string(), dead_port);
+        let live = BackendServer::new("127.0.0.1".to_string(), echo_server().await);
+        let backends = vec![dead.clone(), live.clone()];
+        let proxy = TcpProxy::new(Arc::new(RoundRobinLoadBalancer::new(backends.clone())), backends);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (stream, peer_addr) = listener.accept().await.unwrap();
+                proxy.handle_connection(stream, peer_addr).await.unwrap();
+            }
+        });
+
+        for _ in 0..2 {
+            let mut client = TcpStream::connect(addr).await.unwrap();
+            client.write_all(b"PING").await.unwrap();
+            let mut reply = [0; 4];
+            client.read_exact(&mut reply).await.unwrap();
+            assert_eq!(&reply, b"PING");
+        }
+        assert!(!dead.is_healthy());
+        assert!(live.is_healthy());
+    }
+
+    /// Teste qu'un backend arrêté listé en premier, retiré dès son échec, n'est plus choisi
+    /// par la stratégie de moindre connexion bien qu'il n'ait aucune connexion active.
+    #[tokio::test]
+    async fn test_least_connections_skips_dead_backend() {
+        let dead_port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        let dead = BackendServer::new("127.0.0.1".to_string(), dead_port);
+        let live = BackendServer::new("127.0.0.1".to_string(), echo_server().await);
+        let backends = vec![dead.clone(), live.clone()];
+        let load_balancer = LeastConnectionsLoadBalancer::new(backends.iter().map(|b| (b.clone(), 0)).collect());
+        let proxy = TcpProxy::new(Arc::new(load_balancer), backends);
+
+        let mut connections = Vec::new();
+        for _ in 0..3 {
+            let (backend, stream) = proxy.connect_backend().await.unwrap();
+            assert_eq!(backend.port(), live.port());
+            connections.push((backend.track_connection(), stream)); // Le backend sain devient le plus chargé
+        }
+        assert!(!dead.is_healthy());
+    }
+
+    /// Teste la fermeture d'une connexion inactive.
+    #[tokio::test]
+    async fn test_idle_timeout() {
+        let (client, _client_peer) = tokio::io::duplex(64);
+        let (upstream, _upstream_peer) = tokio::io::duplex(64);
+        let result = pipe(client, upstream, Duration::from_millis(50)).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+    }
+
+    /// Teste qu'un côté qui ne lit plus ne bloque ni l'autre sens ni le délai d'inactivité.
+    #[tokio::test]
+    async fn test_stalled_reader() {
+        let (client, client_peer) = tokio::io::duplex(64);
+        let (upstream, mut upstream_peer) = tokio::io::duplex(64);
+        let relay = tokio::spawn(pipe(client, upstream, Duration::from_millis(100)));
+
+        // Le client envoie sans fin et le backend ne lit jamais : le sens client → backend reste bloqué
+        let (mut client_read, mut client_write) = tokio::io::split(client_peer);
+        tokio::spawn(async move { while client_write.write_all(&[0; 1024]).await.is_ok() {} });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // Le sens backend → client continue de circuler
+        upstream_peer.write_all(b"pong").await.unwrap();
+        let mut reply = [0; 4];
+        client_read.read_exact(&mut reply).await.unwrap();
+        assert_eq!(&reply, b"pong");
+
+        // Puis, sans autre activité, la connexion est fermée malgré l'écriture bloquée
+        let result = tokio::time::timeout(Duration::from_secs(2), relay).await.expect("pipe never timed out");
+        assert_eq!(result.unwrap().unwrap_err().kind(), io::ErrorKind::TimedOut);
+    }
+
+    /// Teste la lecture de l'adresse d'origine annoncée par le client et sa transmission au backend.
+    #[tokio::test]
+    async fn test_proxy_protocol() {
+        let backend_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend = BackendServer::new("127.0.0.1".to_string(), backend_listener.local_addr().unwrap().port());
+        let proxy = TcpProxy::new(Arc::new(RoundRobinLoadBalancer::new(vec![backend.clone()])), vec![backend])
+            .with_accept_proxy_protocol(true)
+            .with_send_proxy_protocol(Some(ProxyProtocolVersion::V2));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, peer_addr) = listener.accept().await.unwrap();
+            let _ = proxy.handle_connection(stream, peer_addr).await;
+        });
+
+        let origin = ProxyAddresses { source: "203.0.113.7:51234".parse().unwrap(), destination: "198.51.100.1:5432".parse().unwrap() };
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(&proxy_protocol::encode_header(ProxyProtocolVersion::V1, origin)).await.unwrap();
+        client.write_all(b"PING").await.unwrap();
+
+        let (mut upstream, _) = backend_listener.accept().await.unwrap();
+        assert_eq!(proxy_protocol::read_header(&mut upstream).await.unwrap(), Some(origin));
+        let mut data = [0; 4];
+        upstream.read_exact(&mut data).await.unwrap();
+        assert_eq!(&data, b"PING");
+    }
+
+    /// Teste l'abandon d'une connexion dont l'en-tête PROXY protocol n'arrive pas dans le délai de connexion.
+    #[tokio::test]
+    async fn test_proxy_protocol_timeout() {
+        let backend = BackendServer::new("127.0.0.1".to_string(), 9);
+        let proxy = TcpProxy::new(Arc::new(RoundRobinLoadBalancer::new(vec![backend.clone()])), vec![backend])
+            .with_accept_proxy_protocol(true)
+            .with_connect_timeout(Duration::from_millis(50));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _client = TcpStream::connect(addr).await.unwrap();
+        let (stream, peer_addr) = listener.accept().await.unwrap();
+
+        let result = tokio::time::timeout(Duration::from_secs(2), proxy.handle_connection(stream, peer_addr)).await;
+        assert!(matches!(result.expect("header read never timed out"), Err(AppError::NetworkError(_))));
+    }
+}
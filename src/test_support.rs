@@ -0,0 +1,49 @@
+use std::convert::Infallible; // Importation de Infallible pour les services qui ne peuvent pas échouer
+use std::future::Future; // Importation du trait Future pour les services de test
+use hyper::server::conn::Http; // Importation du serveur de connexion de hyper
+use hyper::service::service_fn; // Importation de la construction des services HTTP
+use hyper::{Body, Request, Response}; // Importation des types HTTP de hyper
+use tokio::net::TcpListener; // Importation du listener TCP de Tokio
+
+/// Lance un serveur HTTP/1.1 de test servant chaque requête par `handle`, et retourne son port.
+///
+/// Les connexions acceptent les upgrades (WebSocket).
+pub async fn spawn_server<F, Fut>(handle: F) -> u16
+where
+    F: Fn(Request<Body>) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Response<Body>> + Send + 'static,
+{
+    serve(Http::new(), handle).await
+}
+
+/// Lance un serveur HTTP/2 de test (sans négociation, comme un backend gRPC), et retourne son port.
+pub async fn spawn_h2_server<F, Fut>(handle: F) -> u16
+where
+    F: Fn(Request<Body>) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Response<Body>> + Send + 'static,
+{
+    let mut http = Http::new();
+    http.http2_only(true);
+    serve(http, handle).await
+}
+
+async fn serve<F, Fut>(http: Http, handle: F) -> u16
+where
+    F: Fn(Request<Body>) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Response<Body>> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let handle = handle.clone();
+            let service = service_fn(move |req| {
+                let response = handle(req);
+                async move { Ok::<_, Infallible>(response.await) }
+            });
+            tokio::spawn(http.serve_connection(stream, service).with_upgrades());
+        }
+    });
+    port
+}
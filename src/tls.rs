@@ -0,0 +1,273 @@
+use std::collections::HashMap; // Importation de HashMap pour indexer les certificats par nom de serveur
+use std::fs::File; // Importation de File pour lire les certificats et les clés
+use std::io::BufReader; // Importation de BufReader pour le décodage PEM
+use std::sync::Arc; // Importation de Arc pour le partage sécurisé entre threads
+use rustls::crypto::CryptoProvider; // Importation du fournisseur cryptographique de rustls
+use rustls::server::{ClientHello, ResolvesServerCert}; // Importation de la sélection de certificat côté serveur
+use rustls::sign::CertifiedKey; // Importation de la paire certificat / clé de signature
+use rustls::{ServerConfig, SupportedProtocolVersion}; // Importation de la configuration TLS du serveur
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration TLS
+use crate::cert_reload::CertificateReloader; // Importation du rechargement à chaud des certificats
+use crate::error::AppError; // Importation des erreurs de l'application
+
+/// Configuration d'un certificat servi par le listener.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CertificateConfig {
+    #[serde(default)]
+    pub server_names: Vec<String>, // Noms (SNI) servis par ce certificat, jokers `*.example.com` acceptés
+    pub cert_path: String, // Chemin de la chaîne de certificats au format PEM
+    pub key_path: String,  // Chemin de la clé privée au format PEM
+    #[serde(default)]
+    pub default: bool,     // Certificat servi lorsque le SNI est absent ou ne correspond à aucun nom
+}
+
+/// Configuration de la terminaison TLS sur le listener.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TlsConfig {
+    pub certificates: Vec<CertificateConfig>, // Certificats sélectionnés par SNI
+    #[serde(default)]
+    pub cipher_suites: Vec<String>, // Suites de chiffrement autorisées (toutes celles du fournisseur si vide)
+    #[serde(default)]
+    pub protocol_versions: Vec<String>, // Versions de TLS autorisées : "1.2", "1.3" (les deux si vide)
+    #[serde(default = "default_alpn_protocols")]
+    pub alpn_protocols: Vec<String>, // Protocoles proposés par ALPN, par ordre de préférence
+    #[serde(default = "default_reload_interval_secs")]
+    pub reload_interval_secs: u64, // Intervalle de vérification des fichiers de certificats, en secondes
+}
+
+fn default_alpn_protocols() -> Vec<String> {
+    vec!["h2".to_string(), "http/1.1".to_string()]
+}
+
+fn default_reload_interval_secs() -> u64 {
+    30
+}
+
+/// Sélection du certificat selon le nom de serveur (SNI) annoncé par le client.
+#[derive(Debug, Default)]
+pub struct SniResolver {
+    by_name: HashMap<String, Arc<CertifiedKey>>, // Certificats indexés par nom exact ou joker
+    default: Option<Arc<CertifiedKey>>, // Certificat servi à défaut de correspondance
+}
+
+impl SniResolver {
+    /// Charge les certificats déclarés dans la configuration.
+    pub fn from_config(certificates: &[CertificateConfig], provider: &CryptoProvider) -> Result<Self, AppError> {
+        let mut resolver = Self::default();
+        for certificate in certificates {
+            let key = Arc::new(load_certified_key(&certificate.cert_path, &certificate.key_path, provider)?);
+            for name in &certificate.server_names {
+                resolver.by_name.insert(name.to_ascii_lowercase(), key.clone());
+            }
+            if certificate.default || resolver.default.is_none() {
+                resolver.default = Some(key); // Le premier certificat sert de défaut s'il n'y en a pas d'explicite
+            }
+        }
+        if resolver.default.is_none() {
+            return Err(AppError::TlsError("No certificate configured".to_string()));
+        }
+        Ok(resolver)
+    }
+
+    /// Certificat correspondant au nom de serveur donné.
+    pub fn lookup(&self, server_name: Option<&str>) -> Option<Arc<CertifiedKey>> {
+        let name = match server_name {
+            Some(name) => name.to_ascii_lowercase(),
+            None => return self.default.clone(),
+        };
+        // Nom exact, puis joker couvrant le premier label, puis certificat par défaut
+        let wildcard = name.split_once('.').map(|(_, parent)| format!("*.{}", parent));
+        self.by_name
+            .get(&name)
+            .or_else(|| wildcard.and_then(|wildcard| self.by_name.get(&wildcard)))
+            .or(self.default.as_ref())
+            .cloned()
+    }
+}
+
+impl ResolvesServerCert for SniResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        self.lookup(client_hello.server_name())
+    }
+}
+
+/// Charge une chaîne de certificats et sa clé privée depuis des fichiers PEM.
+pub fn load_certified_key(cert_path: &str, key_path: &str, provider: &CryptoProvider) -> Result<CertifiedKey, AppError> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(cert_path)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(AppError::TlsError(format!("No certificate found in {}", cert_path)));
+    }
+    let key = rustls_pemfile::private_key(&mut BufReader::new(File::open(key_path)?))?
+        .ok_or_else(|| AppError::TlsError(format!("No private key found in {}", key_path)))?;
+    let key = provider
+        .key_provider
+        .load_private_key(key)
+        .map_err(|e| AppError::TlsError(format!("Invalid private key in {}: {}", key_path, e)))?;
+
+    let certified_key = CertifiedKey::new(certs, key);
+    certified_key
+        .keys_match()
+        .map_err(|e| AppError::TlsError(format!("Certificate {} does not match key {}: {}", cert_path, key_path, e)))?;
+    Ok(certified_key)
+}
+
+/// Fournisseur cryptographique restreint aux suites de chiffrement configurées.
+fn crypto_provider(cipher_suites: &[String]) -> Result<CryptoProvider, AppError> {
+    let mut provider = rustls::crypto::ring::default_provider();
+    if !cipher_suites.is_empty() {
+        let mut selected = Vec::new();
+        for name in cipher_suites {
+            let suite = provider
+                .cipher_suites
+                .iter()
+                .find(|suite| format!("{:?}", suite.suite()).eq_ignore_ascii_case(name))
+                .ok_or_else(|| AppError::TlsError(format!("Unsupported cipher suite: {}", name)))?;
+            selected.push(*suite);
+        }
+        provider.cipher_suites = selected;
+    }
+    Ok(provider)
+}
+
+/// Versions de TLS correspondant à la configuration.
+fn protocol_versions(versions: &[String]) -> Result<Vec<&'static SupportedProtocolVersion>, AppError> {
+    if versions.is_empty() {
+        return Ok(rustls::DEFAULT_VERSIONS.to_vec());
+    }
+    versions
+        .iter()
+        .map(|version| match version.trim_start_matches("TLS").trim_start_matches("v").trim() {
+            "1.2" => Ok(&rustls::version::TLS12),
+            "1.3" => Ok(&rustls::version::TLS13),
+            _ => Err(AppError::TlsError(format!("Unsupported TLS version: {}", version))),
+        })
+        .collect()
+}
+
+/// Construit la configuration rustls du listener à partir de la configuration TLS.
+///
+/// Les certificats sont servis par un `CertificateReloader`, retourné afin que l'appelant
+/// puisse démarrer la surveillance des fichiers.
+pub fn build_server_config(config: &TlsConfig) -> Result<(Arc<ServerConfig>, Arc<CertificateReloader>), AppError> {
+    if config.reload_interval_secs == 0 {
+        return Err(AppError::ConfigError("TLS reload_interval_secs must be greater than 0".to_string()));
+    }
+    let provider = Arc::new(crypto_provider(&config.cipher_suites)?);
+    let reloader = Arc::new(CertificateReloader::new(config.certificates.clone(), provider.clone())?);
+    let versions = protocol_versions(&config.protocol_versions)?;
+
+    let mut server_config = ServerConfig::builder_with_provider(provider)
+        .with_protocol_versions(&versions)
+        .map_err(|e| AppError::TlsError(e.to_string()))?
+        .with_no_client_auth()
+        .with_cert_resolver(reloader.clone());
+    server_config.alpn_protocols = config.alpn_protocols.iter().map(|p| p.as_bytes().to_vec()).collect();
+    Ok((Arc::new(server_config), reloader))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use rustls::pki_types::{CertificateDer, ServerName};
+    use rustls::{ClientConfig, RootCertStore};
+    use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+    /// Génère un certificat auto-signé pour les noms donnés et l'écrit dans un répertoire temporaire.
+    pub(crate) fn self_signed(dir: &str, names: &[&str]) -> (CertificateConfig, CertificateDer<'static>) {
+        let dir = std::env::temp_dir().join(format!("exam-tls-{}-{}", dir, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let certified = rcgen::generate_simple_self_signed(names.iter().map(|n| n.to_string()).collect::<Vec<_>>()).unwrap();
+        let path = |file: &str| -> PathBuf { dir.join(format!("{}-{}", names[0], file)) };
+        std::fs::write(path("cert.pem"), certified.cert.pem()).unwrap();
+        std::fs::write(path("key.pem"), certified.key_pair.serialize_pem()).unwrap();
+        let config = CertificateConfig {
+            server_names: names.iter().map(|n| n.to_string()).collect(),
+            cert_path: path("cert.pem").to_string_lossy().into_owned(),
+            key_path: path("key.pem").to_string_lossy().into_owned(),
+            default: false,
+        };
+        (config, certified.cert.der().clone())
+    }
+
+    /// Effectue une poignée de main TLS en mémoire et retourne le certificat et le protocole ALPN obtenus.
+    pub(crate) async fn handshake(
+        server_config: Arc<ServerConfig>,
+        trusted: &[CertificateDer<'static>],
+        server_name: &str,
+    ) -> (CertificateDer<'static>, Option<Vec<u8>>) {
+        let mut roots = RootCertStore::empty();
+        for cert in trusted {
+            roots.add(cert.clone()).unwrap();
+        }
+        let mut client_config = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        client_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        let (client, server) = tokio::io::duplex(16 * 1024);
+        let acceptor = TlsAcceptor::from(server_config);
+        let server = tokio::spawn(async move { acceptor.accept(server).await.map(|_| ()) });
+        let stream = TlsConnector::from(Arc::new(client_config))
+            .connect(ServerName::try_from(server_name.to_string()).unwrap(), client)
+            .await
+            .unwrap();
+        server.await.unwrap().unwrap();
+
+        let (_, connection) = stream.get_ref();
+        let certificate = connection.peer_certificates().unwrap()[0].clone();
+        (certificate, connection.alpn_protocol().map(|p| p.to_vec()))
+    }
+
+    /// Teste la sélection du certificat par SNI et la négociation ALPN.
+    #[tokio::test]
+    async fn test_sni_certificate_selection() {
+        let (first, first_der) = self_signed("sni", &["a.example.com"]);
+        let (second, second_der) = self_signed("sni", &["b.example.com", "*.b.example.com"]);
+        let config = TlsConfig {
+            certificates: vec![first, second],
+            cipher_suites: Vec::new(),
+            protocol_versions: Vec::new(),
+            alpn_protocols: default_alpn_protocols(),
+            reload_interval_secs: default_reload_interval_secs(),
+        };
+        let (server_config, _) = build_server_config(&config).unwrap();
+        let trusted = [first_der.clone(), second_der.clone()];
+
+        let (certificate, alpn) = handshake(server_config.clone(), &trusted, "b.example.com").await;
+        assert_eq!(certificate, second_der);
+        assert_eq!(alpn.as_deref(), Some(&b"h2"[..]));
+
+        let (certificate, _) = handshake(server_config.clone(), &trusted, "a.example.com").await;
+        assert_eq!(certificate, first_der);
+
+        let (certificate, _) = handshake(server_config, &trusted, "api.b.example.com").await;
+        assert_eq!(certificate, second_der);
+    }
+
+    /// Teste la validation des suites de chiffrement, des versions et de l'intervalle de rechargement configurés.
+    #[test]
+    fn test_invalid_tls_settings() {
+        let (certificate, _) = self_signed("settings", &["c.example.com"]);
+        let mut config = TlsConfig {
+            certificates: vec![certificate],
+            cipher_suites: vec!["TLS13_AES_256_GCM_SHA384".to_string()],
+            protocol_versions: vec!["1.3".to_string()],
+            alpn_protocols: default_alpn_protocols(),
+            reload_interval_secs: default_reload_interval_secs(),
+        };
+        assert!(build_server_config(&config).is_ok());
+
+        config.cipher_suites = vec!["TLS_RSA_WITH_RC4_128_MD5".to_string()];
+        assert!(build_server_config(&config).is_err());
+        config.cipher_suites.clear();
+        config.protocol_versions = vec!["1.0".to_string()];
+        assert!(build_server_config(&config).is_err());
+        config.protocol_versions.clear();
+        config.reload_interval_secs = 0; // La surveillance des fichiers paniquerait
+        assert!(build_server_config(&config).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use hyper::header::{HeaderMap, HeaderValue, CONNECTION, UPGRADE}; // Importation des en-têtes de mise à niveau
+use hyper::upgrade::OnUpgrade; // Importation des connexions mises à niveau par hyper
+use crate::backend::ConnectionGuard; // Importation du comptage des connexions actives
+use crate::shutdown::Shutdown; // Importation du signal d'arrêt
+use crate::tcp::{pipe, DEFAULT_IDLE_TIMEOUT}; // Importation du relais bidirectionnel
+
+/// Protocole demandé par une requête de mise à niveau (`Connection: Upgrade` et `Upgrade: websocket`, `h2c`...).
+pub fn upgrade_protocol(headers: &HeaderMap) -> Option<HeaderValue> {
+    let requested = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|token| token.trim().eq_ignore_ascii_case("upgrade"));
+    if requested {
+        headers.get(UPGRADE).cloned()
+    } else {
+        None
+    }
+}
+
+/// Rétablit les en-têtes de mise à niveau retirés avec les en-têtes propres à la connexion.
+pub fn restore_upgrade_headers(headers: &mut HeaderMap, protocol: HeaderValue) {
+    headers.insert(CONNECTION, HeaderValue::from_static("upgrade"));
+    headers.insert(UPGRADE, protocol);
+}
+
+/// Relie la connexion mise à niveau du client à celle du backend.
+///
+/// Le tunnel reste compté comme connexion active du backend (`guard`) pendant toute sa durée
+/// et il est fermé à l'arrêt du proxy ou après une longue inactivité.
+pub async fn splice(client: OnUpgrade, backend: OnUpgrade, guard: ConnectionGuard, shutdown: Shutdown) {
+    let (client, backend) = match tokio::try_join!(client, backend) {
+        Ok(upgraded) => upgraded,
+        Err(e) => {
+            log::debug!("Upgrade failed: {}", e);
+            return;
+        }
+    };
+    tokio::select! {
+        result = pipe(client, backend, DEFAULT_IDLE_TIMEOUT) => {
+            if let Err(e) = result {
+                log::debug!("Tunnel closed with error: {}", e);
+            }
+        }
+        _ = shutdown.wait() => log::debug!("Closing tunnel on shutdown"),
+    }
+    drop(guard); // La connexion n'est plus comptée qu'une fois le tunnel fermé
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use hyper::server::conn::Http;
+    use hyper::service::service_fn;
+    use hyper::{Body, Request, Response, StatusCode};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use crate::backend::BackendServer;
+    use crate::load_balancer::RoundRobinLoadBalancer;
+    use crate::request_handler::RequestHandler;
+    use crate::test_support::spawn_server;
+
+    /// Backend acceptant les mises à niveau et renvoyant en écho les octets du tunnel.
+    async fn echo_backend() -> u16 {
+        spawn_server(|mut req: Request<Body>| async move {
+            assert_eq!(upgrade_protocol(req.headers()).unwrap(), "websocket");
+            tokio::spawn(async move {
+                let mut upgraded = hyper::upgrade::on(&mut req).await.unwrap();
+                let mut buf = [0; 64];
+                while let Ok(len @ 1..) = upgraded.read(&mut buf).await {
+                    upgraded.write_all(&buf[..len]).await.unwrap();
+                }
+            });
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+            restore_upgrade_headers(response.headers_mut(), HeaderValue::from_static("websocket"));
+            response
+        })
+        .await
+    }
+
+    /// Teste le tunnel WebSocket de bout en bout, son comptage comme connexion active et sa fermeture à l'arrêt.
+    #[tokio::test]
+    async fn test_websocket_tunnel() {
+        let backend = BackendServer::new("127.0.0.1".to_string(), echo_backend().await);
+        let shutdown = Shutdown::new();
+        let handler = Arc::new(
+            RequestHandler::new(Arc::new(RoundRobinLoadBalancer::new(vec![backend.clone()]))).with_shutdown(shutdown.clone()),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, client_addr): (TcpStream, SocketAddr) = listener.accept().await.unwrap();
+            let service = service_fn(move |req| {
+                let handler = handler.clone();
+                async move { handler.handle_request(req, client_addr).await }
+            });
+            let _ = Http::new().serve_connection(stream, service).with_upgrades().await;
+        });
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client
+            .write_all(b"GET /chat HTTP/1.1\r\nHost: example.com\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\r\n")
+            .await
+            .unwrap();
+        let mut buf = [0; 512];
+        let len = client.read(&mut buf).await.unwrap();
+        assert!(String::from_utf8_lossy(&buf[..len]).starts_with("HTTP/1.1 101"));
+
+        client.write_all(b"hello").await.unwrap();
+        let len = client.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"hello");
+        assert_eq!(backend.active_connections(), 1);
+
+        shutdown.trigger();
+        let closed = tokio::time::timeout(Duration::from_secs(2), client.read(&mut buf)).await.unwrap();
+        assert!(matches!(closed, Ok(0) | Err(_)));
+        for _ in 0..100 {
+            if backend.active_connections() == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(backend.active_connections(), 0);
+    }
+}
@@ -0,0 +1,404 @@
+use std::collections::hash_map::DefaultHasher; // Importation du hacheur utilisé par le mode sans état
+use std::collections::HashMap; // Importation de HashMap pour la table des sessions
+use std::hash::{Hash, Hasher}; // Importation des traits de hachage
+use std::net::SocketAddr; // Importation de SocketAddr pour les adresses des clients et des backends
+use std::sync::{Arc, Mutex}; // Importation de Arc et Mutex pour partager la table des sessions
+use std::time::{Duration, Instant}; // Importation de Duration et Instant pour l'expiration des sessions
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration des listeners UDP
+use tokio::net::UdpSocket; // Importation des sockets UDP
+use tokio::sync::OnceCell; // Importation de OnceCell pour les sessions en cours d'ouverture
+use crate::backend::BackendServer; // Importation de la structure BackendServer depuis le module backend
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::load_balancer::SharedLoadBalancer; // Importation des stratégies de répartition
+
+/// Taille maximale d'un datagramme UDP.
+const MAX_DATAGRAM_LEN: usize = 65_535;
+
+/// Mode de répartition des datagrammes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UdpMode {
+    /// Chaque client reçoit un backend choisi par la stratégie et une session relayant les réponses.
+    #[default]
+    Session,
+    /// Le backend est déduit d'un hachage de l'adresse du client, sans table de sessions.
+    ///
+    /// Mode à sens unique : les datagrammes partent d'un socket commun à tous les clients, si bien
+    /// que les réponses des backends ne peuvent pas leur être rendues et sont ignorées. Réservé à
+    /// syslog et aux autres flux sans réponse ; le DNS doit utiliser le mode session.
+    Hash,
+}
+
+/// Configuration d'un listener UDP.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UdpProxyConfig {
+    pub listen: SocketAddr, // Adresse d'écoute
+    #[serde(default)]
+    pub pool: Option<String>, // Pool de destination ; les serveurs backend principaux si absent
+    #[serde(default)]
+    pub mode: UdpMode, // Sessions (par défaut) ou hachage sans état
+    #[serde(default = "default_session_timeout_secs")]
+    pub session_timeout_secs: u64, // Expiration d'une session sans datagramme dans aucun sens
+    #[serde(default = "default_max_sessions")]
+    pub max_sessions: usize, // Nombre maximal de sessions ; les datagrammes des nouveaux clients sont ignorés au-delà
+}
+
+fn default_session_timeout_secs() -> u64 {
+    60
+}
+
+fn default_max_sessions() -> usize {
+    10_000
+}
+
+/// Session d'un client, ouverte une seule fois même si plusieurs datagrammes arrivent pendant son ouverture.
+type SessionSlot = Arc<OnceCell<Arc<UdpSession>>>;
+
+/// Session associant un client à un backend.
+struct UdpSession {
+    backend: Arc<BackendServer>, // Backend choisi pour le client
+    upstream: UdpSocket, // Socket dédié à la session, connecté au backend
+    last_activity: Mutex<Instant>, // Dernier datagramme échangé dans un sens ou dans l'autre
+}
+
+impl UdpSession {
+    fn touch(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_activity.lock().unwrap().elapsed()
+    }
+}
+
+/// Répartition de charge UDP.
+///
+/// En mode session, chaque adresse cliente (et donc chaque 4-uplet, l'adresse d'écoute étant
+/// fixe) se voit attribuer un backend par la stratégie configurée et un socket dédié, par
+/// lequel les réponses du backend sont relayées au client. Les sessions inactives expirent, et
+/// leur nombre est borné pour qu'un afflux d'adresses sources (usurpées) n'épuise pas les sockets ;
+/// elles sont ouvertes hors de la boucle de réception, qu'une résolution lente ne bloque donc pas.
+/// En mode hachage, aucun état n'est conservé : le backend est choisi par hachage de rendez-vous
+/// de l'adresse cliente parmi les backends sains, de sorte qu'un client reste sur le même backend
+/// et que seuls les clients d'un backend défaillant sont redistribués.
+pub struct UdpProxy {
+    load_balancer: SharedLoadBalancer, // Stratégie de choix du backend des nouvelles sessions
+    backends: Vec<Arc<BackendServer>>, // Serveurs backend servis par la stratégie
+    mode: UdpMode, // Mode de répartition
+    session_timeout: Duration, // Délai d'expiration des sessions inactives
+    max_sessions: usize, // Nombre maximal de sessions simultanées
+    sessions: Mutex<HashMap<SocketAddr, SessionSlot>>, // Sessions ouvertes ou en cours d'ouverture, par adresse cliente
+}
+
+impl UdpProxy {
+    /// Crée une nouvelle instance de `UdpProxy` en mode session.
+    pub fn new(load_balancer: SharedLoadBalancer, backends: Vec<Arc<BackendServer>>) -> Self {
+        Self {
+            load_balancer,
+            backends,
+            mode: UdpMode::Session,
+            session_timeout: Duration::from_secs(default_session_timeout_secs()),
+            max_sessions: default_max_sessions(),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Définit le mode de répartition.
+    pub fn with_mode(mut self, mode: UdpMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Définit le délai d'expiration des sessions inactives.
+    pub fn with_session_timeout(mut self, session_timeout: Duration) -> Self {
+        self.session_timeout = session_timeout;
+        self
+    }
+
+    /// Définit le nombre maximal de sessions simultanées.
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions;
+        self
+    }
+
+    /// Nombre de sessions en cours.
+    pub fn session_count(&self) -> usize {
+        self.sessions.lock().unwrap().len()
+    }
+
+    /// Écoute sur `addr` et relaie les datagrammes vers les backends.
+    pub async fn serve(self: Arc<Self>, addr: SocketAddr) -> Result<(), AppError> {
+        let socket = Arc::new(UdpSocket::bind(addr).await?);
+        self.run(socket).await
+    }
+
+    /// Relaie les datagrammes reçus sur `socket` vers les backends.
+    pub async fn run(self: Arc<Self>, socket: Arc<UdpSocket>) -> Result<(), AppError> {
+        let mut buf = vec![0; MAX_DATAGRAM_LEN];
+        // En mode hachage, un unique socket sortant sert tous les clients
+        let outbound = match self.mode {
+            UdpMode::Hash => Some(UdpSocket::bind(unspecified_for(socket.local_addr()?)).await?),
+            UdpMode::Session => None,
+        };
+        loop {
+            let (len, client) = socket.recv_from(&mut buf).await?;
+            let result = match &outbound {
+                Some(outbound) => self.forward_hashed(outbound, client, &buf[..len]).await,
+                None => self.forward_session(&socket, client, &buf[..len]),
+            };
+            if let Err(e) = result {
+                log::debug!("Dropping datagram from {}: {}", client, e);
+            }
+        }
+    }
+
+    /// Transmet un datagramme par la session du client.
+    ///
+    /// Une session ouverte est utilisée sur place ; sinon, l'ouverture et l'envoi ont lieu dans
+    /// une tâche dédiée. Au-delà de `max_sessions`, les datagrammes des nouveaux clients sont refusés.
+    fn forward_session(self: &Arc<Self>, socket: &Arc<UdpSocket>, client: SocketAddr, datagram: &[u8]) -> Result<(), AppError> {
+        let slot = {
+            let mut sessions = self.sessions.lock().unwrap();
+            match sessions.get(&client) {
+                Some(slot) => slot.clone(),
+                None if sessions.len() >= self.max_sessions => {
+                    return Err(AppError::NetworkError(format!("Session limit of {} reached", self.max_sessions)));
+                }
+                None => sessions.entry(client).or_default().clone(),
+            }
+        };
+        if let Some(session) = slot.get() {
+            session.touch();
+            // Le socket connecté n'attend pas le backend : l'envoi échoue plutôt que de bloquer
+            session.upstream.try_send(datagram)?;
+            return Ok(());
+        }
+
+        let this = self.clone();
+        let socket = socket.clone();
+        let datagram = datagram.to_vec();
+        tokio::spawn(async move {
+            let sent = async {
+                let session = slot.get_or_try_init(|| this.open_session(&socket, client)).await?;
+                session.touch();
+                session.upstream.send(&datagram).await?;
+                Ok::<_, AppError>(())
+            };
+            if let Err(e) = sent.await {
+                log::debug!("Dropping datagram from {}: {}", client, e);
+                if slot.get().is_none() {
+                    // Ouverture échouée : le prochain datagramme du client retentera
+                    let mut sessions = this.sessions.lock().unwrap();
+                    if sessions.get(&client).is_some_and(|current| Arc::ptr_eq(current, &slot)) {
+                        sessions.remove(&client);
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Ouvre une session vers un backend sain et lance le relais de ses réponses.
+    async fn open_session(self: &Arc<Self>, socket: &Arc<UdpSocket>, client: SocketAddr) -> Result<Arc<UdpSession>, AppError> {
+        // La stratégie ne retourne un backend hors service que si aucun n'est sain
+        let backend = self.load_balancer.select_backend();
+        if !backend.is_healthy() {
+            return Err(AppError::BackendServerError("No healthy backend".to_string()));
+        }
+        let backend_addr = resolve(&backend).await?;
+        let upstream = UdpSocket::bind(unspecified_for(backend_addr)).await?;
+        upstream.connect(backend_addr).await?;
+
+        let session = Arc::new(UdpSession { backend, upstream, last_activity: Mutex::new(Instant::now()) });
+        log::debug!("UDP session {} -> {}", client, backend_addr);
+
+        let this = self.clone();
+        let socket = socket.clone();
+        let relayed = session.clone();
+        tokio::spawn(async move {
+            this.relay_replies(socket, client, relayed).await;
+            this.sessions.lock().unwrap().remove(&client);
+        });
+        Ok(session)
+    }
+
+    /// Relaie les réponses du backend au client jusqu'à l'expiration de la session.
+    async fn relay_replies(&self, socket: Arc<UdpSocket>, client: SocketAddr, session: Arc<UdpSession>) {
+        let mut buf = vec![0; MAX_DATAGRAM_LEN];
+        loop {
+            let remaining = self.session_timeout.saturating_sub(session.idle_for());
+            if remaining.is_zero() {
+                return; // Session expirée
+            }
+            match tokio::time::timeout(remaining, session.upstream.recv(&mut buf)).await {
+                Ok(Ok(len)) => {
+                    session.touch();
+                    if let Err(e) = socket.send_to(&buf[..len], client).await {
+                        log::debug!("Cannot relay reply to {}: {}", client, e);
+                    }
+                }
+                Ok(Err(e)) => {
+                    // Typiquement un port fermé signalé par ICMP : le backend ne répond plus
+                    log::debug!("UDP session {} with {}:{} closed: {}", client, session.backend.address(), session.backend.port(), e);
+                    return;
+                }
+                Err(_) => {} // Le délai est recalculé : le client a pu être actif entre-temps
+            }
+        }
+    }
+
+    /// Transmet un datagramme au backend désigné par le hachage de l'adresse cliente.
+    async fn forward_hashed(&self, outbound: &UdpSocket, client: SocketAddr, datagram: &[u8]) -> Result<(), AppError> {
+        let backend = self
+            .hashed_backend(client)
+            .ok_or_else(|| AppError::BackendServerError("No healthy backend".to_string()))?;
+        outbound.send_to(datagram, resolve(&backend).await?).await?;
+        Ok(())
+    }
+
+    /// Backend sain de plus haut score de rendez-vous pour l'adresse cliente.
+    fn hashed_backend(&self, client: SocketAddr) -> Option<Arc<BackendServer>> {
+        let score = |backend: &Arc<BackendServer>| {
+            let mut hasher = DefaultHasher::new();
+            client.hash(&mut hasher);
+            backend.address().hash(&mut hasher);
+            backend.port().hash(&mut hasher);
+            hasher.finish()
+        };
+        self.backends.iter().filter(|b| b.is_healthy()).max_by_key(|b| score(b)).cloned()
+    }
+}
+
+/// Adresse du backend, résolue si elle est donnée par nom.
+async fn resolve(backend: &BackendServer) -> Result<SocketAddr, AppError> {
+    tokio::net::lookup_host((backend.address(), backend.port()))
+        .await?
+        .next()
+        .ok_or_else(|| AppError::NetworkError(format!("Cannot resolve {}", backend.address())))
+}
+
+/// Adresse locale non spécifiée de la même famille que `addr`.
+fn unspecified_for(addr: SocketAddr) -> SocketAddr {
+    if addr.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_balancer::{LeastConnectionsLoadBalancer, RoundRobinLoadBalancer};
+
+    /// Démarre un serveur d'écho UDP qui préfixe ses réponses par son port.
+    async fn echo_server() -> Arc<BackendServer> {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buf = [0; 512];
+            loop {
+                let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+                let reply = format!("{}:{}", port, String::from_utf8_lossy(&buf[..len]));
+                socket.send_to(reply.as_bytes(), peer).await.unwrap();
+            }
+        });
+        BackendServer::new("127.0.0.1".to_string(), port)
+    }
+
+    async fn exchange(client: &UdpSocket, proxy: SocketAddr, message: &str) -> String {
+        client.send_to(message.as_bytes(), proxy).await.unwrap();
+        let mut buf = [0; 512];
+        let len = tokio::time::timeout(Duration::from_secs(2), client.recv(&mut buf)).await.unwrap().unwrap();
+        String::from_utf8_lossy(&buf[..len]).into_owned()
+    }
+
+    /// Teste que chaque client garde son backend, reçoit ses réponses et que les sessions expirent.
+    #[tokio::test]
+    async fn test_sessions() {
+        let backends = vec![echo_server().await, echo_server().await];
+        let proxy = Arc::new(
+            UdpProxy::new(Arc::new(RoundRobinLoadBalancer::new(backends.clone())), backends)
+                .with_session_timeout(Duration::from_millis(200)),
+        );
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(proxy.clone().run(socket));
+
+        let first = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let second = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let a1 = exchange(&first, addr, "a").await;
+        let b1 = exchange(&second, addr, "b").await;
+        let a2 = exchange(&first, addr, "a").await;
+        assert_eq!(a1, a2); // Même backend pour le même client
+        assert_ne!(a1.split(':').next(), b1.split(':').next()); // Clients répartis par round robin
+        assert_eq!(proxy.session_count(), 2);
+
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(proxy.session_count(), 0);
+    }
+
+    /// Teste qu'un backend hors service listé en premier est évité par la stratégie de moindre connexion.
+    #[tokio::test]
+    async fn test_least_connections_skips_unhealthy() {
+        let dead = BackendServer::new("127.0.0.1".to_string(), 9);
+        dead.set_healthy(false);
+        let live = echo_server().await;
+        let backends = vec![dead, live.clone()];
+        let load_balancer = LeastConnectionsLoadBalancer::new(backends.iter().map(|b| (b.clone(), 0)).collect());
+        let proxy = Arc::new(UdpProxy::new(Arc::new(load_balancer), backends));
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(proxy.run(socket));
+
+        for _ in 0..3 {
+            let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            assert_eq!(exchange(&client, addr, "a").await, format!("{}:a", live.port()));
+        }
+    }
+
+    /// Teste le refus des nouveaux clients au-delà du nombre maximal de sessions.
+    #[tokio::test]
+    async fn test_max_sessions() {
+        let backends = vec![echo_server().await];
+        let proxy = Arc::new(
+            UdpProxy::new(Arc::new(RoundRobinLoadBalancer::new(backends.clone())), backends)
+                .with_session_timeout(Duration::from_millis(200))
+                .with_max_sessions(1),
+        );
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(proxy.clone().run(socket));
+
+        let first = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let second = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        exchange(&first, addr, "a").await;
+        second.send_to(b"b", addr).await.unwrap();
+        let mut buf = [0; 512];
+        assert!(tokio::time::timeout(Duration::from_millis(100), second.recv(&mut buf)).await.is_err());
+        assert_eq!(proxy.session_count(), 1);
+
+        // Une fois la session expirée, un nouveau client est accepté
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        exchange(&second, addr, "b").await;
+    }
+
+    /// Teste que le mode hachage est stable et évite les backends en mauvaise santé.
+    #[tokio::test]
+    async fn test_hash_mode() {
+        let backends: Vec<_> = (0..3).map(|i| BackendServer::new("127.0.0.1".to_string(), 9000 + i)).collect();
+        let proxy = UdpProxy::new(Arc::new(RoundRobinLoadBalancer::new(backends.clone())), backends.clone())
+            .with_mode(UdpMode::Hash);
+
+        let clients: Vec<SocketAddr> = (0..20).map(|i| SocketAddr::from(([10, 0, 0, i], 5353))).collect();
+        let before: Vec<u16> = clients.iter().map(|c| proxy.hashed_backend(*c).unwrap().port()).collect();
+        backends[0].set_healthy(false);
+        for (client, port) in clients.iter().zip(before) {
+            let after = proxy.hashed_backend(*client).unwrap().port();
+            assert_ne!(after, 9000);
+            if port != 9000 {
+                assert_eq!(after, port); // Seuls les clients du backend défaillant changent de backend
+            }
+        }
+    }
+}
@@ -0,0 +1,623 @@
+use std::fs::File; // Importation de File pour lire les certificats
+use std::io::BufReader; // Importation de BufReader pour le décodage PEM
+use std::net::SocketAddr; // Importation de SocketAddr pour l'en-tête X-Forwarded-For
+use std::pin::Pin; // Importation de Pin pour implémenter les traits d'entrée/sortie asynchrones
+use std::sync::Arc; // Importation de Arc pour le partage sécurisé entre threads
+use std::task::{Context, Poll}; // Importation des types de polling asynchrone
+use hyper::header::{HeaderName, HeaderValue, CONNECTION, HOST, TE}; // Importation des en-têtes HTTP
+use hyper::body::HttpBody; // Importation de HttpBody pour relayer le corps et les trailers des réponses
+use hyper::client::conn::SendRequest; // Importation de l'émetteur de requêtes d'une connexion hyper
+use hyper::{Body, Method, Request, Response, StatusCode, Uri}; // Importation des types de requêtes et réponses HTTP
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier}; // Importation de la vérification des certificats serveur
+use rustls::client::ResolvesClientCert; // Importation de la présentation du certificat client
+use rustls::crypto::CryptoProvider; // Importation du fournisseur cryptographique de rustls
+use rustls::sign::CertifiedKey; // Importation de la paire certificat / clé du client
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime}; // Importation des types de certificats
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme}; // Importation de la configuration TLS du client
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration TLS vers les backends
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf}; // Importation des traits d'entrée/sortie asynchrones
+use tokio::net::TcpStream; // Importation de TcpStream pour se connecter aux backends
+use tokio_rustls::client::TlsStream; // Importation du flux TLS côté client
+use tokio_rustls::TlsConnector; // Importation du connecteur TLS
+use crate::backend::BackendServer; // Importation de la structure BackendServer depuis le module backend
+use crate::connection_pool::Lease; // Importation des connexions empruntées au pool
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::tls::load_certified_key; // Importation du chargement d'une paire certificat / clé
+
+/// En-têtes propres à une connexion, qui ne doivent pas être transmis au backend.
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Protocole HTTP parlé avec un backend (ou un pool).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpstreamProtocol {
+    /// HTTP/1.1.
+    #[default]
+    Http1,
+    /// HTTP/2 d'emblée (« prior knowledge »), en clair (h2c) comme en TLS.
+    Http2,
+    /// HTTP/2 si le backend le choisit par ALPN lors de la poignée de main TLS, sinon HTTP/1.1.
+    Auto,
+}
+
+/// Configuration TLS des connexions vers un backend (ou un pool).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpstreamTlsConfig {
+    #[serde(default)]
+    pub ca_file: Option<String>, // Bundle d'autorités de certification (PEM) ; racines web publiques si absent
+    #[serde(default)]
+    pub server_name: Option<String>, // Nom annoncé par SNI et vérifié dans le certificat, sinon l'adresse du backend
+    #[serde(default)]
+    pub client_cert: Option<String>, // Certificat client (PEM) pour le TLS mutuel
+    #[serde(default)]
+    pub client_key: Option<String>, // Clé privée du certificat client (PEM)
+    #[serde(default)]
+    pub insecure_skip_verify: bool, // Désactive la vérification du certificat du backend (laboratoire uniquement)
+}
+
+/// Connecteur TLS vers un backend, prêt à l'emploi.
+pub struct UpstreamTls {
+    connector: TlsConnector, // Connecteur portant la configuration rustls
+    server_name: ServerName<'static>, // Nom annoncé par SNI et vérifié dans le certificat
+}
+
+impl UpstreamTls {
+    /// Construit le connecteur TLS d'un backend dont l'adresse sert de nom par défaut.
+    ///
+    /// Les protocoles proposés par ALPN dépendent du protocole HTTP configuré pour le backend.
+    pub fn from_config(config: &UpstreamTlsConfig, address: &str, protocol: UpstreamProtocol) -> Result<Self, AppError> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| AppError::TlsError(e.to_string()))?;
+
+        // Vérification du certificat du backend
+        let builder = if config.insecure_skip_verify {
+            log::warn!("TLS certificate verification disabled for backend {}", address);
+            builder.dangerous().with_custom_certificate_verifier(Arc::new(NoVerification(provider.clone())))
+        } else {
+            let mut roots = RootCertStore::empty();
+            match &config.ca_file {
+                Some(ca_file) => {
+                    for cert in rustls_pemfile::certs(&mut BufReader::new(File::open(ca_file)?)) {
+                        roots.add(cert?).map_err(|e| AppError::TlsError(format!("Invalid CA in {}: {}", ca_file, e)))?;
+                    }
+                }
+                None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+            }
+            builder.with_root_certificates(roots)
+        };
+
+        // Certificat client pour le TLS mutuel
+        let mut client_config = match (&config.client_cert, &config.client_key) {
+            (Some(cert), Some(key)) => {
+                let certified_key = load_certified_key(cert, key, &provider)?;
+                builder.with_client_cert_resolver(Arc::new(ClientCertificate(Arc::new(certified_key))))
+            }
+            (None, None) => builder.with_no_client_auth(),
+            _ => return Err(AppError::TlsError("client_cert and client_key must be set together".to_string())),
+        };
+
+        client_config.alpn_protocols = match protocol {
+            UpstreamProtocol::Http1 => Vec::new(),
+            UpstreamProtocol::Http2 => vec![b"h2".to_vec()],
+            UpstreamProtocol::Auto => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+        };
+
+        let name = config.server_name.as_deref().unwrap_or(address);
+        let server_name = ServerName::try_from(name.to_string())
+            .map_err(|e| AppError::TlsError(format!("Invalid server name {}: {}", name, e)))?;
+        Ok(Self { connector: TlsConnector::from(Arc::new(client_config)), server_name })
+    }
+}
+
+/// Certificat client présenté à tout backend qui en demande un (TLS mutuel).
+#[derive(Debug)]
+struct ClientCertificate(Arc<CertifiedKey>);
+
+impl ResolvesClientCert for ClientCertificate {
+    fn resolve(&self, _root_hint_subjects: &[&[u8]], _sigschemes: &[SignatureScheme]) -> Option<Arc<CertifiedKey>> {
+        Some(self.0.clone())
+    }
+
+    fn has_certs(&self) -> bool {
+        true
+    }
+}
+
+/// Vérificateur acceptant tout certificat : réservé aux environnements de laboratoire.
+#[derive(Debug)]
+struct NoVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// Connexion vers un backend, en clair ou chiffrée.
+pub enum UpstreamStream {
+    Plain(TcpStream), // Connexion TCP en clair
+    Tls(Box<TlsStream<TcpStream>>), // Connexion TLS
+}
+
+impl AsyncRead for UpstreamStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            UpstreamStream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            UpstreamStream::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for UpstreamStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            UpstreamStream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            UpstreamStream::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            UpstreamStream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            UpstreamStream::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            UpstreamStream::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            UpstreamStream::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+impl UpstreamStream {
+    /// Indique si HTTP/2 a été choisi par ALPN lors de la poignée de main TLS.
+    pub fn negotiated_h2(&self) -> bool {
+        match self {
+            UpstreamStream::Plain(_) => false,
+            UpstreamStream::Tls(stream) => stream.get_ref().1.alpn_protocol() == Some(b"h2"),
+        }
+    }
+}
+
+/// Ouvre une connexion vers un backend, chiffrée si le backend est configuré en TLS.
+pub async fn connect(backend: &BackendServer) -> Result<UpstreamStream, AppError> {
+    let stream = TcpStream::connect((backend.address(), backend.port()))
+        .await
+        .map_err(|e| AppError::NetworkError(format!("{}:{}: {}", backend.address(), backend.port(), e)))?;
+    stream.set_nodelay(true)?;
+    match backend.tls() {
+        Some(tls) => {
+            let stream = tls
+                .connector
+                .connect(tls.server_name.clone(), stream)
+                .await
+                .map_err(|e| AppError::TlsError(format!("{}:{}: {}", backend.address(), backend.port(), e)))?;
+            Ok(UpstreamStream::Tls(Box::new(stream)))
+        }
+        None => Ok(UpstreamStream::Plain(stream)),
+    }
+}
+
+/// Envoie une requête à un backend et retourne sa réponse.
+///
+/// La connexion est empruntée au pool du backend et lui est rendue une fois la réponse lue.
+/// Elle parle HTTP/2 si le backend est configuré ainsi ou s'il l'a choisi par ALPN ;
+/// chaque requête (chaque RPC pour gRPC) est donc répartie indépendamment, même lorsque le
+/// client multiplexe ses flux sur une seule connexion.
+pub async fn send_request(backend: &BackendServer, req: Request<Body>) -> Result<Response<Body>, AppError> {
+    send_request_holding(backend, req, ()).await
+}
+
+/// Transmet une requête comme `send_request`, en conservant `held` jusqu'à la fin du corps de la réponse.
+///
+/// Une connexion comptée (`ConnectionGuard`) reste ainsi active pendant le relais des réponses
+/// volumineuses ou en flux, pour la capacité du backend comme pour la stratégie de moindre connexion.
+pub async fn send_request_holding<T>(backend: &BackendServer, req: Request<Body>, held: T) -> Result<Response<Body>, AppError>
+where
+    T: Send + 'static,
+{
+    let mut lease = backend.connection_pool().checkout(|| open_connection(backend)).await?;
+
+    // Une requête sans corps peut être rejouée si la connexion réutilisée a été fermée par le backend
+    let replay = (lease.is_reused() && req.body().is_end_stream()).then(|| {
+        let mut replay = Request::new(Body::empty());
+        *replay.method_mut() = req.method().clone();
+        *replay.uri_mut() = req.uri().clone();
+        *replay.version_mut() = req.version();
+        *replay.headers_mut() = req.headers().clone();
+        replay
+    });
+
+    let response = match lease.send_request(for_connection(backend, &lease, req)?).await {
+        Ok(response) => response,
+        Err(e) => match replay {
+            // Rejouée une fois sur une connexion neuve si elle est idempotente ou n'a jamais été écrite
+            Some(replay) if is_idempotent(replay.method()) || e.is_canceled() => {
+                log::debug!("Reused connection to {}:{} failed ({}), retrying on a new one", backend.address(), backend.port(), e);
+                lease = backend.connection_pool().open(|| open_connection(backend)).await?;
+                let req = for_connection(backend, &lease, replay)?;
+                lease.send_request(req).await.map_err(|e| AppError::BackendServerError(e.to_string()))?
+            }
+            _ => return Err(AppError::BackendServerError(e.to_string())),
+        },
+    };
+    if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+        return Ok(response); // La connexion mise à niveau quitte le pool
+    }
+
+    // Le corps est relayé par une tâche qui rend la connexion au pool une fois la réponse lue
+    let (parts, mut body) = response.into_parts();
+    let (mut sender, relayed) = Body::channel();
+    tokio::spawn(async move {
+        while let Some(chunk) = body.data().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(_) => return sender.abort(),
+            };
+            if sender.send_data(chunk).await.is_err() {
+                return; // Client parti : la connexion, à moitié lue, est abandonnée
+            }
+        }
+        match body.trailers().await {
+            Ok(Some(trailers)) => {
+                let _ = sender.send_trailers(trailers).await;
+            }
+            Ok(None) => {}
+            Err(_) => return sender.abort(),
+        }
+        drop(sender);
+        drop(held);
+        if lease.ready().await {
+            lease.release();
+        }
+    });
+    Ok(Response::from_parts(parts, relayed))
+}
+
+/// Adapte une requête à la connexion qui va la porter.
+fn for_connection(backend: &BackendServer, lease: &Lease, mut req: Request<Body>) -> Result<Request<Body>, AppError> {
+    if lease.is_http2() {
+        // HTTP/2 exige le schéma et l'autorité (pseudo-en-têtes :scheme et :authority)
+        let scheme = if backend.tls().is_some() { "https" } else { "http" };
+        let authority = match req.headers().get(HOST).and_then(|host| host.to_str().ok()) {
+            Some(host) => host.to_string(),
+            None => format!("{}:{}", backend.address(), backend.port()),
+        };
+        let path = req.uri().path_and_query().map(|p| p.as_str()).unwrap_or("/");
+        *req.uri_mut() = format!("{}://{}{}", scheme, authority, path)
+            .parse()
+            .map_err(|e| AppError::BackendServerError(format!("Invalid request URI: {}", e)))?;
+        req.headers_mut().remove(HOST);
+    }
+    Ok(req)
+}
+
+/// Indique si une méthode peut être rejouée sans effet de bord supplémentaire (RFC 9110, 9.2.2).
+fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE)
+}
+
+/// Ouvre une nouvelle connexion vers un backend et indique si elle parle HTTP/2.
+async fn open_connection(backend: &BackendServer) -> Result<(SendRequest<Body>, bool), AppError> {
+    let stream = connect(backend).await?;
+    let http2 = match backend.protocol() {
+        UpstreamProtocol::Http1 => false,
+        UpstreamProtocol::Http2 => true,
+        UpstreamProtocol::Auto => stream.negotiated_h2(),
+    };
+    let (sender, connection) = hyper::client::conn::Builder::new()
+        .http2_only(http2)
+        .handshake(stream)
+        .await
+        .map_err(|e| AppError::BackendServerError(e.to_string()))?;
+    // La connexion est pilotée dans sa propre tâche jusqu'à sa fermeture
+    tokio::spawn(async move {
+        // Une connexion mise à niveau (WebSocket, h2c) est rendue à l'appelant via `hyper::upgrade::on`
+        if let Err(e) = connection.await {
+            log::debug!("Upstream connection closed with error: {}", e);
+        }
+    });
+    Ok((sender, http2))
+}
+
+/// Prépare une requête cliente pour sa transmission au backend.
+///
+/// L'URI est réduite au chemin (son autorité devenant l'en-tête `Host` s'il manque), les en-têtes
+/// propres à la connexion sont retirés (sauf `TE: trailers`, indispensable à gRPC) et l'adresse
+/// du client est ajoutée à `X-Forwarded-For`.
+pub fn prepare_request(mut req: Request<Body>, client_addr: SocketAddr) -> Request<Body> {
+    // Une requête HTTP/2 porte son hôte dans :authority, sans en-tête Host : il est reconstitué pour HTTP/1.1
+    if !req.headers().contains_key(HOST) {
+        if let Some(host) = req.uri().authority().and_then(|authority| HeaderValue::from_str(authority.as_str()).ok()) {
+            req.headers_mut().insert(HOST, host);
+        }
+    }
+    let path = req.uri().path_and_query().map(|p| p.as_str()).unwrap_or("/").to_string();
+    *req.uri_mut() = path.parse::<Uri>().unwrap_or_else(|_| Uri::from_static("/"));
+
+    // Les en-têtes listés dans Connection sont eux aussi propres à la connexion
+    let trailers = req
+        .headers()
+        .get_all(TE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|value| value.trim().eq_ignore_ascii_case("trailers"));
+    let listed: Vec<HeaderName> = req
+        .headers()
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect();
+    let headers = req.headers_mut();
+    for name in listed {
+        headers.remove(name);
+    }
+    for name in HOP_BY_HOP_HEADERS {
+        headers.remove(name);
+    }
+    if trailers {
+        headers.insert(TE, HeaderValue::from_static("trailers")); // Le client accepte les trailers (gRPC)
+    }
+
+    let forwarded_for = match headers.get("x-forwarded-for").and_then(|v| v.to_str().ok()) {
+        Some(previous) => format!("{}, {}", previous, client_addr.ip()),
+        None => client_addr.ip().to_string(),
+    };
+    if let Ok(value) = HeaderValue::from_str(&forwarded_for) {
+        headers.insert("x-forwarded-for", value);
+    }
+    req
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BackendConfig;
+    use crate::health::{HealthCheckConfig, HealthChecker};
+    use crate::tls::tests::self_signed;
+    use crate::tls::{build_server_config, TlsConfig};
+    use hyper::server::conn::Http;
+    use hyper::service::service_fn;
+    use tokio::net::TcpListener;
+    use tokio_rustls::TlsAcceptor;
+
+    /// Démarre un backend HTTPS de test exigeant un certificat client si `client_ca` est fourni.
+    async fn https_backend(names: &[&str], client_ca: Option<&str>) -> (u16, String) {
+        let (certificate, _) = self_signed("upstream", names);
+        let ca_file = certificate.cert_path.clone();
+        let config = TlsConfig {
+            certificates: vec![certificate],
+            cipher_suites: Vec::new(),
+            protocol_versions: Vec::new(),
+            alpn_protocols: Vec::new(),
+            reload_interval_secs: 30,
+        };
+        let (mut server_config, _) = build_server_config(&config).unwrap();
+        if let Some(client_ca) = client_ca {
+            // Reconstruit la configuration en exigeant un certificat client signé par `client_ca`
+            let mut roots = RootCertStore::empty();
+            for cert in rustls_pemfile::certs(&mut BufReader::new(File::open(client_ca).unwrap())) {
+                roots.add(cert.unwrap()).unwrap();
+            }
+            let provider = Arc::new(rustls::crypto::ring::default_provider());
+            let verifier = rustls::server::WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                .build()
+                .unwrap();
+            server_config = Arc::new(
+                rustls::ServerConfig::builder_with_provider(provider)
+                    .with_safe_default_protocol_versions()
+                    .unwrap()
+                    .with_client_cert_verifier(verifier)
+                    .with_cert_resolver(server_config.cert_resolver.clone()),
+            );
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let acceptor = TlsAcceptor::from(server_config);
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    if let Ok(stream) = acceptor.accept(stream).await {
+                        let service = service_fn(|req: Request<Body>| async move {
+                            let mut response = Response::new(Body::from(format!("secure {}", req.uri().path())));
+                            if req.uri().path() == "/down" {
+                                *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+                            }
+                            Ok::<_, hyper::Error>(response)
+                        });
+                        let _ = Http::new().serve_connection(stream, service).await;
+                    }
+                });
+            }
+        });
+        (port, ca_file)
+    }
+
+    fn backend(port: u16, tls: UpstreamTlsConfig) -> Arc<BackendServer> {
+        BackendServer::from_config(&BackendConfig {
+            address: "127.0.0.1".to_string(),
+            port,
+            tls: Some(tls),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    /// Teste l'envoi d'une requête en TLS avec un bundle d'autorités et un SNI forcé.
+    #[tokio::test]
+    async fn test_tls_with_custom_ca_and_sni() {
+        let (port, ca_file) = https_backend(&["backend.internal"], None).await;
+        let tls = UpstreamTlsConfig {
+            ca_file: Some(ca_file),
+            server_name: Some("backend.internal".to_string()),
+            ..Default::default()
+        };
+        let response = send_request(&backend(port, tls), Request::new(Body::empty())).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"secure /");
+
+        // Avec une autorité inconnue, la vérification du certificat échoue, sauf à la désactiver
+        let wrong_name = UpstreamTlsConfig { ca_file: Some(self_signed("other", &["x.internal"]).0.cert_path), ..Default::default() };
+        assert!(send_request(&backend(port, wrong_name), Request::new(Body::empty())).await.is_err());
+        let insecure = UpstreamTlsConfig { insecure_skip_verify: true, ..Default::default() };
+        assert!(send_request(&backend(port, insecure), Request::new(Body::empty())).await.is_ok());
+    }
+
+    /// Teste le TLS mutuel : le backend exige un certificat client.
+    #[tokio::test]
+    async fn test_mutual_tls() {
+        let (client, _) = self_signed("client", &["client.internal"]);
+        let (port, ca_file) = https_backend(&["mtls.internal"], Some(&client.cert_path)).await;
+        let without_cert = UpstreamTlsConfig {
+            ca_file: Some(ca_file.clone()),
+            server_name: Some("mtls.internal".to_string()),
+            ..Default::default()
+        };
+        let request = || Request::builder().uri("/").body(Body::empty()).unwrap();
+        let rejected = send_request(&backend(port, without_cert.clone()), request()).await;
+        assert!(rejected.is_err());
+
+        let with_cert = UpstreamTlsConfig {
+            client_cert: Some(client.cert_path),
+            client_key: Some(client.key_path),
+            ..without_cert
+        };
+        assert!(send_request(&backend(port, with_cert), request()).await.is_ok());
+    }
+
+    /// Teste la vérification de santé d'un backend HTTPS, saine seulement pour une réponse 2xx.
+    #[tokio::test]
+    async fn test_tls_health_check() {
+        let (port, ca_file) = https_backend(&["health.internal"], None).await;
+        let backend = |path: &str| {
+            BackendServer::from_config(&BackendConfig {
+                address: "127.0.0.1".to_string(),
+                port,
+                tls: Some(UpstreamTlsConfig {
+                    ca_file: Some(ca_file.clone()),
+                    server_name: Some("health.internal".to_string()),
+                    ..Default::default()
+                }),
+                health_check: Some(HealthCheckConfig::Http { path: path.to_string() }),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        assert!(HealthChecker::check_health(backend("/health")).await);
+        assert!(!HealthChecker::check_health(backend("/down")).await);
+    }
+
+    /// Teste la préparation d'une requête avant transmission.
+    #[test]
+    fn test_prepare_request() {
+        let req = Request::builder()
+            .uri("http://proxy.local/api/users?page=2")
+            .header("Connection", "keep-alive, X-Private")
+            .header("X-Private", "secret")
+            .header("X-Forwarded-For", "198.51.100.1")
+            .body(Body::empty())
+            .unwrap();
+        let req = prepare_request(req, "203.0.113.7:4321".parse().unwrap());
+        assert_eq!(req.uri(), "/api/users?page=2");
+        assert!(req.headers().get("connection").is_none());
+        assert!(req.headers().get("x-private").is_none());
+        assert_eq!(req.headers()["x-forwarded-for"], "198.51.100.1, 203.0.113.7");
+        assert_eq!(req.headers()[HOST], "proxy.local");
+
+        // Un en-tête Host présent est conservé
+        let req = Request::builder().uri("https://a.example/").header(HOST, "b.example").body(Body::empty()).unwrap();
+        assert_eq!(prepare_request(req, "203.0.113.7:4321".parse().unwrap()).headers()[HOST], "b.example");
+    }
+
+    /// Démarre un backend HTTP/1.1 qui ferme chaque connexion à réception de sa deuxième requête,
+    /// sans y répondre, et retourne son port et le nombre de connexions ouvertes.
+    async fn closing_backend() -> (u16, Arc<std::sync::atomic::AtomicUsize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = connections.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buf = [0; 4096];
+                    if stream.read(&mut buf).await.unwrap_or(0) == 0 {
+                        return;
+                    }
+                    stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok").await.unwrap();
+                    let _ = stream.read(&mut buf).await; // Deuxième requête lue, connexion fermée
+                });
+            }
+        });
+        (port, connections)
+    }
+
+    /// Teste le rejeu sur une connexion neuve d'une requête idempotente dont la connexion réutilisée a été fermée.
+    #[tokio::test]
+    async fn test_retry_on_closed_connection() {
+        let (port, connections) = closing_backend().await;
+        let backend = BackendServer::from_config(&BackendConfig { address: "127.0.0.1".to_string(), port, ..Default::default() }).unwrap();
+        let request = |method: Method| Request::builder().method(method).uri("/").body(Body::empty()).unwrap();
+
+        for _ in 0..2 {
+            let response = send_request(&backend, request(Method::GET)).await.unwrap();
+            assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "ok");
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await; // Connexion rendue au pool
+        }
+        assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        // Une requête POST déjà écrite n'est pas rejouée
+        assert!(send_request(&backend, request(Method::POST)).await.is_err());
+        assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+}
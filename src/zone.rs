@@ -0,0 +1,231 @@
+use std::sync::Arc; // Importation de Arc pour le partage sécurisé entre threads
+use rand::Rng; // Importation de Rng pour le tirage aléatoire entre zone locale et zones distantes
+use serde::Deserialize; // Importation de Deserialize pour lire la configuration du routage par zone
+use crate::backend::BackendServer; // Importation de la structure BackendServer depuis le module backend
+use crate::error::AppError; // Importation des erreurs de l'application
+use crate::load_balancer::{LoadBalancer, SharedLoadBalancer}; // Importation des stratégies de répartition
+
+/// Configuration du routage par zone.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ZoneAwareConfig {
+    pub local_zone: String, // Zone dans laquelle tourne le proxy
+    #[serde(default = "default_min_local_healthy")]
+    pub min_local_healthy: f64, // Fraction de backends locaux disponibles (sains et non saturés) en dessous de laquelle le trafic déborde vers les autres zones
+}
+
+fn default_min_local_healthy() -> f64 {
+    0.7
+}
+
+/// Répartition de charge tenant compte de la zone.
+///
+/// Les backends de la même zone que le proxy sont préférés. Tant que la fraction de backends
+/// locaux disponibles (sains et non saturés) reste au-dessus du seuil, aucun trafic ne traverse
+/// les zones ; en dessous, la part envoyée vers les autres zones croît proportionnellement au
+/// déficit de capacité locale. La part restée locale évite les backends hors service.
+/// La stratégie appliquée de part et d'autre est fournie par l'appelant, ce qui permet de
+/// composer ce routage avec n'importe quel `LoadBalancer`.
+pub struct ZoneAwareLoadBalancer {
+    local_backends: Vec<Arc<BackendServer>>, // Backends situés dans la zone du proxy
+    remote_backends: Vec<Arc<BackendServer>>, // Backends situés dans les autres zones (ou sans zone)
+    local: Option<SharedLoadBalancer>, // Stratégie appliquée aux backends locaux
+    remote: Option<SharedLoadBalancer>, // Stratégie appliquée aux backends distants
+    min_local_healthy: f64, // Seuil de disponibilité locale en dessous duquel le trafic déborde
+}
+
+impl ZoneAwareLoadBalancer {
+    /// Crée une nouvelle instance de `ZoneAwareLoadBalancer`.
+    ///
+    /// `build` construit la stratégie de répartition à appliquer à un groupe de backends.
+    pub fn new<F>(config: &ZoneAwareConfig, backends: Vec<Arc<BackendServer>>, build: F) -> Result<Self, AppError>
+    where
+        F: Fn(Vec<Arc<BackendServer>>) -> Result<SharedLoadBalancer, AppError>,
+    {
+        if backends.is_empty() {
+            return Err(AppError::ConfigError("No backend servers specified".to_string()));
+        }
+        let (local_backends, remote_backends): (Vec<_>, Vec<_>) = backends
+            .into_iter()
+            .partition(|b| b.zone() == Some(config.local_zone.as_str()));
+
+        // Une stratégie n'est construite que pour les groupes non vides
+        let local = if local_backends.is_empty() { None } else { Some(build(local_backends.clone())?) };
+        let remote = if remote_backends.is_empty() { None } else { Some(build(remote_backends.clone())?) };
+
+        Ok(Self {
+            local_backends,
+            remote_backends,
+            local,
+            remote,
+            min_local_healthy: config.min_local_healthy.clamp(0.0, 1.0),
+        })
+    }
+
+    /// Part du trafic (entre 0.0 et 1.0) envoyée aux backends de la zone locale.
+    pub fn local_share(&self) -> f64 {
+        if self.local_backends.is_empty() {
+            return 0.0;
+        }
+        if self.remote_backends.is_empty() {
+            return 1.0; // Pas d'autre zone vers laquelle déborder
+        }
+        let available = self.local_backends.iter().filter(|b| b.is_healthy() && !b.is_saturated()).count();
+        if available == 0 {
+            return 0.0; // Zone locale hors service ou à pleine capacité, quel que soit le seuil
+        }
+        let available_fraction = available as f64 / self.local_backends.len() as f64;
+        if available_fraction >= self.min_local_healthy {
+            1.0
+        } else {
+            available_fraction / self.min_local_healthy
+        }
+    }
+}
+
+impl LoadBalancer for ZoneAwareLoadBalancer {
+    /// Sélectionne un serveur backend en privilégiant la zone locale.
+    ///
+    /// Une référence partagée au serveur backend sélectionné.
+    fn select_backend(&self) -> Arc<BackendServer> {
+        let local_share = self.local_share();
+        let use_local = local_share >= 1.0 || rand::thread_rng().gen::<f64>() < local_share;
+        let load_balancer = match (use_local, &self.local, &self.remote) {
+            (true, Some(local), _) => local,
+            (false, _, Some(remote)) => remote,
+            (_, Some(local), None) => local,
+            (_, None, Some(remote)) => remote,
+            (_, None, None) => unreachable!("ZoneAwareLoadBalancer requires at least one backend"),
+        };
+        load_balancer.select_backend()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use hyper::{Body, Request, Response, StatusCode};
+    use tokio::net::TcpListener;
+    use crate::config::BackendConfig;
+    use crate::health::HealthChecker;
+    use crate::load_balancer::build_load_balancer;
+    use crate::request_handler::RequestHandler;
+    use crate::test_support::spawn_server;
+
+    fn backend(port: u16, zone: &str) -> Arc<BackendServer> {
+        BackendServer::from_config(&BackendConfig {
+            address: "127.0.0.1".to_string(),
+            port,
+            zone: Some(zone.to_string()),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn zone_aware(backends: Vec<Arc<BackendServer>>) -> ZoneAwareLoadBalancer {
+        let config = ZoneAwareConfig { local_zone: "eu-west-1a".to_string(), min_local_healthy: 0.5 };
+        ZoneAwareLoadBalancer::new(&config, backends, |backends| build_load_balancer("round_robin", backends, None)).unwrap()
+    }
+
+    /// Teste que le trafic reste dans la zone locale tant qu'elle est suffisamment saine.
+    #[test]
+    fn test_prefers_local_zone() {
+        let lb = zone_aware(vec![backend(8080, "eu-west-1a"), backend(8081, "eu-west-1b"), backend(8082, "eu-west-1a")]);
+        for _ in 0..20 {
+            assert_ne!(lb.select_backend().port(), 8081);
+        }
+    }
+
+    /// Teste le débordement vers les autres zones lorsque la santé locale passe sous le seuil.
+    #[test]
+    fn test_cross_zone_below_threshold() {
+        let local = vec![backend(8080, "eu-west-1a"), backend(8082, "eu-west-1a"), backend(8083, "eu-west-1a"), backend(8084, "eu-west-1a")];
+        let mut backends = local.clone();
+        backends.push(backend(8081, "eu-west-1b"));
+        let lb = zone_aware(backends);
+
+        local[0].set_healthy(false); // 75 % de backends locaux sains : toujours au-dessus du seuil
+        assert_eq!(lb.local_share(), 1.0);
+
+        local[1].set_healthy(false);
+        local[2].set_healthy(false); // 25 % de backends locaux sains : la moitié du trafic déborde
+        assert!((lb.local_share() - 0.5).abs() < 1e-9);
+
+        local[3].set_healthy(false);
+        assert_eq!(lb.local_share(), 0.0);
+        assert_eq!(lb.select_backend().port(), 8081);
+    }
+
+    /// Teste qu'en cas de panne partielle la part restée locale ne va qu'aux backends sains,
+    /// et que les backends saturés comptent comme de la capacité manquante.
+    #[test]
+    fn test_partial_local_failure() {
+        let local: Vec<_> = (8080..8084).map(|port| backend(port, "eu-west-1a")).collect();
+        let mut backends = local.clone();
+        backends.push(backend(9090, "eu-west-1b"));
+        let lb = zone_aware(backends);
+
+        for dead in &local[..3] {
+            dead.set_healthy(false);
+        }
+        assert!((lb.local_share() - 0.5).abs() < 1e-9);
+        for _ in 0..50 {
+            assert!(matches!(lb.select_backend().port(), 8083 | 9090));
+        }
+
+        // Le dernier backend local sain, saturé, ne laisse plus de capacité locale
+        let saturated = BackendServer::from_config(&BackendConfig {
+            address: "127.0.0.1".to_string(),
+            port: 8085,
+            zone: Some("eu-west-1a".to_string()),
+            max_connections: Some(1),
+            ..Default::default()
+        })
+        .unwrap();
+        let lb = zone_aware(vec![saturated.clone(), backend(8086, "eu-west-1a"), backend(9090, "eu-west-1b")]);
+        assert_eq!(lb.local_share(), 1.0);
+        let _connection = saturated.track_connection();
+        assert_eq!(lb.local_share(), 1.0); // 50 % disponibles : le seuil de 0.5 est atteint
+        lb.local_backends[1].set_healthy(false); // Seul reste le backend sain mais saturé
+        assert_eq!(lb.local_share(), 0.0);
+    }
+
+    /// Teste qu'un seuil nul ne retient pas le trafic dans une zone locale entièrement hors service.
+    #[test]
+    fn test_zero_threshold_with_dead_zone() {
+        let local = backend(8080, "eu-west-1a");
+        let config = ZoneAwareConfig { local_zone: "eu-west-1a".to_string(), min_local_healthy: 0.0 };
+        let lb = ZoneAwareLoadBalancer::new(&config, vec![local.clone(), backend(8081, "eu-west-1b")], |backends| {
+            build_load_balancer("round_robin", backends, None)
+        })
+        .unwrap();
+        assert_eq!(lb.local_share(), 1.0);
+        local.set_healthy(false);
+        assert_eq!(lb.local_share(), 0.0);
+        assert_eq!(lb.select_backend().port(), 8081);
+    }
+
+    /// Teste de bout en bout le débordement vers une autre zone lorsque la vérification de santé
+    /// détecte que le backend local est injoignable.
+    #[tokio::test]
+    async fn test_spill_over_from_unreachable_zone() {
+        let unreachable = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        let remote = spawn_server(|_| async { Response::new(Body::from("eu-west-1b")) }).await;
+        let backends = vec![backend(unreachable, "eu-west-1a"), backend(remote, "eu-west-1b")];
+        let handler = RequestHandler::new(Arc::new(zone_aware(backends.clone())));
+        let client: std::net::SocketAddr = "127.0.0.1:1000".parse().unwrap();
+
+        HealthChecker::watch(backends.clone(), Duration::from_millis(20));
+        for _ in 0..100 {
+            if !backends[0].is_healthy() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(handler.handle_request(Request::new(Body::empty()), client).await.unwrap().status(), StatusCode::OK);
+        for _ in 0..3 {
+            let response = handler.handle_request(Request::new(Body::empty()), client).await.unwrap();
+            assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "eu-west-1b");
+        }
+    }
+}